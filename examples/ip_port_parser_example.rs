@@ -23,8 +23,7 @@ fn main() {
                 println!("解析成功，找到 {} 个IP端口组合:", ip_ports.len());
                 // 只打印前5个结果，避免输出过多
                 let display_count = std::cmp::min(ip_ports.len(), 5);
-                for i in 0..display_count {
-                    let ip_port = &ip_ports[i];
+                for (i, ip_port) in ip_ports.iter().enumerate().take(display_count) {
                     println!("  {}. IP: {}, 端口: {:?}", i + 1, ip_port.ip, ip_port.ports);
                 }
                 if ip_ports.len() > display_count {
@@ -12,7 +12,6 @@ pub fn example_function(x: i32) {
 // 测试宏功能的函数
 pub fn test_macro() -> () {
     example_function(1);
-    ()
 }
 
 #[timing]
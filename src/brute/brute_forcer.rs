@@ -1,24 +1,72 @@
+use crate::brute::cancellation::CancellationToken;
 use crate::brute::task_manager::TaskManager;
-use crate::errors::errors::RtspError;
+use crate::errors::errors::{AuthenticationResult, RtspError};
 use crate::iterator::credential_iterator::CredentialIterator;
 use crate::iterator::ip_iterator::{IpIterator, IpPortAddr};
+use crate::rtsp::client::{ClientOptions, CredentialTarget, RtspClient};
+use crate::rtsp::evidence::{Evidence, EvidenceOptions};
+use crate::rtsp::path_discovery::{self, DiscoveredPath, PathStatus};
+use crate::rtsp::playback::PlaybackStatus;
 use crate::rtsp::rtsp_worker::RTSP_WORKER_MANAGER;
+use crate::rtsp::sdp::{MediaDescription, SessionDescription};
+use crate::rtsp::transport::CertificateInfo;
 use log::{debug, error, info, trace};
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Display;
+use clap::ValueEnum;
 use colored::*;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use futures::stream::StreamExt;
+use serde::Deserialize;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use tokio::sync::Semaphore;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
 use tokio::time::Instant;
 
+// 服务不可用时的最大重试次数
+const MAX_BACKOFF_RETRIES: u32 = 3;
+// 服务器未提供Retry-After时的初始退避时间，每次重试翻倍
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(5);
+// 最长的退避时间
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 找到凭据后停止测试目标的策略
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StopPolicy {
+    // 找到第一个凭据后停止测试该目标
+    #[default]
+    FirstHit,
+    // 测试所有用户名，每个用户名找到密码后跳过其剩余的密码
+    PerUsername,
+    // 测试完整的凭据列表，记录所有有效的凭据
+    Never,
+}
+
 /// 存储找到的RTSP认证凭据信息
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct FoundCredential {
     pub ip_port: IpPortAddr,
     pub username: String,
     pub password: String,
+    // 验证凭据时使用的流路径，未启用路径探测时为空
+    pub path: String,
+    // 跟随重定向后最终验证凭据的URL，未发生重定向时为None
+    pub final_url: Option<String>,
+    // 成功的DESCRIBE响应中SDP描述的媒体列表
+    pub media: Vec<MediaDescription>,
+    // 启用播放验证时SETUP/PLAY的结果
+    pub playback: Option<PlaybackStatus>,
+    // 启用取证录制时录制的媒体文件
+    pub evidence: Option<Evidence>,
+    // RTSPS目标的服务器证书
+    pub certificate: Option<CertificateInfo>,
+    // 凭据属于摄像头还是RTSP代理
+    pub target: CredentialTarget,
+    // TryBoth策略下服务器接受的所有认证方式，如同时接受Digest和明文的Basic
+    pub accepted_schemes: Vec<&'static str>,
 }
 
 impl Display for FoundCredential {
@@ -26,20 +74,148 @@ impl Display for FoundCredential {
         write!(
             f,
             "{} with {}:{}",
-            self.ip_port, self.username, self.password
-        )
+            self.ip_port.location(&self.path),
+            self.username,
+            self.password
+        )?;
+        if self.target == CredentialTarget::Proxy {
+            write!(f, " (proxy credentials)")?;
+        }
+        if let Some(final_url) = &self.final_url {
+            write!(f, " (redirected to {})", final_url)?;
+        }
+        if !self.accepted_schemes.is_empty() {
+            write!(f, " (accepts {})", self.accepted_schemes.join(", "))?;
+        }
+        if !self.media.is_empty() {
+            write!(f, " [{}]", self.media_summary())?;
+        }
+        if let Some(playback) = &self.playback {
+            write!(f, " ({})", playback)?;
+        }
+        if let Some(evidence) = &self.evidence {
+            write!(f, " (evidence: {})", evidence)?;
+        }
+        if let Some(certificate) = &self.certificate {
+            write!(f, " (certificate: {})", certificate)?;
+        }
+        Ok(())
     }
 }
 
 impl FoundCredential {
     /// 返回带颜色的字符串表示，用于终端输出
     pub fn to_colored_string(&self) -> String {
-        format!(
+        let colored = format!(
             "{} with {}:{}",
-            self.ip_port.to_string().cyan(),
+            self.ip_port.location(&self.path).cyan(),
             self.username.to_string().green(),
             self.password.to_string().yellow()
-        )
+        );
+        let colored = if self.target == CredentialTarget::Proxy {
+            format!("{} ({})", colored, "proxy credentials".magenta())
+        } else {
+            colored
+        };
+        let colored = match &self.final_url {
+            Some(final_url) => format!("{} (redirected to {})", colored, final_url.cyan()),
+            None => colored,
+        };
+        let colored = if self.accepted_schemes.is_empty() {
+            colored
+        } else {
+            let schemes = self.accepted_schemes.join(", ");
+            format!("{} (accepts {})", colored, schemes.magenta())
+        };
+        let colored = if self.media.is_empty() {
+            colored
+        } else {
+            format!("{} [{}]", colored, self.media_summary().blue())
+        };
+        let colored = match &self.playback {
+            Some(playback) if playback.media_flowed() => {
+                format!("{} ({})", colored, playback.to_string().green())
+            }
+            Some(playback) => format!("{} ({})", colored, playback.to_string().red()),
+            None => colored,
+        };
+        let colored = match &self.evidence {
+            Some(evidence) => format!("{} (evidence: {})", colored, evidence.to_string().cyan()),
+            None => colored,
+        };
+        match &self.certificate {
+            Some(certificate) => format!("{} (certificate: {})", colored, certificate),
+            None => colored,
+        }
+    }
+
+    /// 媒体列表的简要描述，如"video H264/90000, audio PCMA/8000"
+    pub fn media_summary(&self) -> String {
+        self.media
+            .iter()
+            .map(|media| media.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// 流水线中的一次凭据尝试
+struct WorkItem {
+    ip_port: IpPortAddr,
+    path: String,
+    username: String,
+    password: String,
+    // 尝试完成前保留目标的状态
+    _lease: Arc<HostLease>,
+}
+
+/// 生产者正在轮换的目标及其剩余的凭据
+struct HostWork {
+    ip: IpPortAddr,
+    path: String,
+    token: CancellationToken,
+    credentials: CredentialIterator,
+    lease: Arc<HostLease>,
+}
+
+/// 目标状态的租约，由生产者和已排队或正在执行的尝试共同持有。
+/// 目标的所有租约释放后删除其取消令牌、切换后的路径和已为空的探测结果，
+/// 只保留总结中报告的跳过原因和探测到的路径，内存占用不随已完成的目标数量增长
+struct HostLease {
+    ip: IpPortAddr,
+    host_leases: Arc<Mutex<HashMap<IpPortAddr, usize>>>,
+    host_tokens: Arc<Mutex<HashMap<IpPortAddr, CancellationToken>>>,
+    host_paths: Arc<Mutex<HashMap<IpPortAddr, String>>>,
+    discovered_paths: Arc<Mutex<HashMap<IpPortAddr, Vec<DiscoveredPath>>>>,
+}
+
+impl Drop for HostLease {
+    fn drop(&mut self) {
+        // 重叠的目标范围可能多次生成同一目标，最后一个租约释放时才删除
+        let mut host_leases = self.host_leases.lock().unwrap();
+        let Some(count) = host_leases.get_mut(&self.ip) else {
+            return;
+        };
+        *count -= 1;
+        if *count > 0 {
+            return;
+        }
+        host_leases.remove(&self.ip);
+        self.host_tokens.lock().unwrap().remove(&self.ip);
+        self.host_paths.lock().unwrap().remove(&self.ip);
+        remove_empty_paths(&self.discovered_paths, &self.ip);
+        trace!("Released state of {}", self.ip);
+    }
+}
+
+/// 删除目标已为空的流路径探测结果
+fn remove_empty_paths(
+    discovered_paths: &Mutex<HashMap<IpPortAddr, Vec<DiscoveredPath>>>,
+    ip: &IpPortAddr,
+) {
+    let mut discovered_paths = discovered_paths.lock().unwrap();
+    if discovered_paths.get(ip).is_some_and(Vec::is_empty) {
+        discovered_paths.remove(ip);
     }
 }
 
@@ -49,8 +225,24 @@ pub struct BruteForcer {
     credential_iterator: CredentialIterator,
     ip_iterator: IpIterator,
     max_concurrent: u32,
+    connect_concurrency: Option<u32>, // 端口检查的最大并发数，为None时与max_concurrent相同
+    stop_policy: StopPolicy, // 找到凭据后停止测试目标的策略
     found_credentials: Arc<Mutex<HashSet<FoundCredential>>>, // 跟踪已找到的认证凭据
     task_manager: TaskManager,
+    client_options: ClientOptions,
+    stream_paths: Option<Vec<String>>, // 路径探测使用的流路径字典，为None时不进行探测
+    discovered_paths: Arc<Mutex<HashMap<IpPortAddr, Vec<DiscoveredPath>>>>, // 每个目标探测到的流路径
+    host_paths: Arc<Mutex<HashMap<IpPortAddr, String>>>, // 流路径不存在时切换后的路径
+    skipped_hosts: Arc<Mutex<HashMap<IpPortAddr, AuthenticationResult>>>, // 被跳过的目标及原因
+    host_tokens: Arc<Mutex<HashMap<IpPortAddr, CancellationToken>>>, // 每个目标的取消令牌，找到凭据或跳过目标时取消
+    host_leases: Arc<Mutex<HashMap<IpPortAddr, usize>>>, // 流水线中每个目标的租约数量
+    evidence: Option<EvidenceOptions>, // 取证录制选项，为None时不录制
+}
+
+impl Default for BruteForcer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BruteForcer {
@@ -60,8 +252,18 @@ impl BruteForcer {
             credential_iterator: CredentialIterator::new(vec![], vec![]),
             ip_iterator: IpIterator::new(vec![]),
             max_concurrent: DEFAULT_MAX_CONCURRENT,
+            connect_concurrency: None,
+            stop_policy: StopPolicy::default(),
             found_credentials: Arc::new(Mutex::new(HashSet::new())),
             task_manager: TaskManager::new(DEFAULT_MAX_CONCURRENT),
+            client_options: ClientOptions::default(),
+            stream_paths: None,
+            discovered_paths: Arc::new(Mutex::new(HashMap::new())),
+            host_paths: Arc::new(Mutex::new(HashMap::new())),
+            skipped_hosts: Arc::new(Mutex::new(HashMap::new())),
+            host_tokens: Arc::new(Mutex::new(HashMap::new())),
+            host_leases: Arc::new(Mutex::new(HashMap::new())),
+            evidence: None,
         }
     }
 
@@ -69,7 +271,7 @@ impl BruteForcer {
     pub fn with_cred_iterator(mut self, credential_iterator: CredentialIterator) -> Self {
         info!(
             "Total credential combinations: {}",
-            credential_iterator.clone().count()
+            credential_iterator.len()
         );
         self.credential_iterator = credential_iterator;
         self
@@ -77,7 +279,12 @@ impl BruteForcer {
 
     /// 设置IP迭代器
     pub fn with_ip_iterator(mut self, ip_iterator: IpIterator) -> Self {
-        info!("Total IP:ports to scan: {}", ip_iterator.clone().count());
+        // 目标范围之间有重叠时只能给出上限，重复的目标在迭代时跳过
+        match ip_iterator.size_hint() {
+            (min, Some(max)) if min == max => info!("Total IP:ports to scan: {}", max),
+            (_, Some(max)) => info!("Total IP:ports to scan: up to {}", max),
+            (min, None) => info!("Total IP:ports to scan: at least {}", min),
+        }
         self.ip_iterator = ip_iterator;
         self
     }
@@ -90,184 +297,740 @@ impl BruteForcer {
         self
     }
 
-    /// 尝试单个用户名、密码和URL
+    /// 设置端口检查的最大并发数，与凭据尝试的并发数分开配置
+    pub fn with_connect_concurrency(mut self, connect_concurrency: Option<u32>) -> Self {
+        if let Some(connect_concurrency) = connect_concurrency {
+            info!("Max concurrent port checks: {}", connect_concurrency);
+        }
+        self.connect_concurrency = connect_concurrency;
+        self
+    }
+
+    /// 设置找到凭据后停止测试目标的策略
+    pub fn with_stop_policy(mut self, stop_policy: StopPolicy) -> Self {
+        info!("Stop policy: {:?}", stop_policy);
+        self.stop_policy = stop_policy;
+        self
+    }
+
+    /// 设置RTSP客户端选项
+    pub fn with_client_options(mut self, client_options: ClientOptions) -> Self {
+        debug!("RTSP client options: {:?}", &client_options);
+        self.client_options = client_options;
+        self
+    }
+
+    /// 设置流路径字典，启用路径探测模式
+    pub fn with_stream_paths(mut self, stream_paths: Option<Vec<String>>) -> Self {
+        if let Some(paths) = &stream_paths {
+            info!("Stream path discovery enabled with {} paths", paths.len());
+        }
+        self.stream_paths = stream_paths;
+        self
+    }
+
+    /// 设置取证录制选项，找到凭据后录制一段媒体流
+    pub fn with_evidence(mut self, evidence: Option<EvidenceOptions>) -> Self {
+        if let Some(options) = &evidence {
+            info!(
+                "Evidence capture enabled: {:?} of media into {}",
+                options.duration,
+                options.directory.display()
+            );
+        }
+        self.evidence = evidence;
+        self
+    }
+
+    /// 获取每个目标探测到的流路径
+    pub fn discovered_paths(&self) -> HashMap<IpPortAddr, Vec<DiscoveredPath>> {
+        self.discovered_paths.lock().unwrap().clone()
+    }
+
+    /// 尝试单个用户名、密码、目标和流路径
     pub async fn try_credentials(
         &self,
         username: &str,
         password: &str,
         ip_port: &IpPortAddr,
+        path: &str,
     ) -> Result<Option<FoundCredential>, RtspError> {
-        let rtsp_url = format!("rtsp://{}:{}", ip_port.ip, ip_port.port);
-        debug!(
-            "Task started: Scanning {}: {}:{} on thread {:?}",
-            rtsp_url,
-            username,
-            password,
-            thread::current().id()
-        );
+        // 其他任务可能已经因为路径不存在而切换了流路径
+        let mut path = self.current_path(ip_port, path);
+        let mut retries = 0;
+        let token = self.host_token(ip_port);
+
+        loop {
+            // 目标已经找到有效的凭据或已被跳过时，剩余的尝试作为已取消处理
+            if token.is_cancelled() || self.is_username_done(ip_port, username) {
+                debug!("Cancelled {}:{} on {}", username, password, ip_port);
+                return Err(RtspError::Cancelled);
+            }
 
-        // 检查IP是否已经找到有效的凭据
-        if self.has_valid_credentials_for_ip(ip_port) {
+            let rtsp_url = self.rtsp_url(ip_port, &path);
             debug!(
-                "Skipping {}:{} as valid credentials already found",
-                &ip_port.ip, &ip_port.port
+                "Task started: Scanning {}: {}:{} on thread {:?}",
+                rtsp_url,
+                username,
+                password,
+                thread::current().id()
             );
-            return Ok(None);
-        }
+            let start_time = Instant::now();
 
-        let start_time = Instant::now();
+            // 其他任务找到凭据或跳过目标时立即放弃正在进行的尝试
+            let options = self.client_options_for(ip_port);
+            let request = RTSP_WORKER_MANAGER.describe_request(username, password, &rtsp_url, &options);
+            let outcome = tokio::select! {
+                outcome = request => outcome,
+                _ = token.cancelled() => {
+                    debug!("Cancelled in-flight attempt {}:{} on {}", username, password, rtsp_url);
+                    return Err(RtspError::Cancelled);
+                }
+            };
+            let outcome = match outcome {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    debug!("Error during authentication attempt: {:?}", e);
+                    return Err(e);
+                }
+            };
+            let final_url = outcome
+                .redirected_from(&rtsp_url)
+                .then(|| outcome.url.clone());
+            let session = outcome.session;
+            let media = session
+                .as_ref()
+                .map(|session| session.media.clone())
+                .unwrap_or_default();
+            let playback = outcome.playback;
+            let certificate = outcome.certificate;
+            let accepted_schemes = outcome.accepted_schemes;
+            let result = outcome.result;
+            debug!(
+                "Task completed in {:?}: Scanning {}: {}:{} ({})",
+                start_time.elapsed(),
+                rtsp_url,
+                username,
+                password,
+                result
+            );
 
-        match RTSP_WORKER_MANAGER
-            .auth_request(username, password, &rtsp_url)
-            .await
-        {
-            Ok(result) => {
-                let duration = start_time.elapsed();
-                debug!(
-                    "Task completed in {:?}: Scanning {}: {}:{}",
-                    duration, rtsp_url, username, password
-                );
-
-                match result {
-                    Some((valid_username, valid_password)) => {
-                        let found_cred = FoundCredential {
-                            ip_port: ip_port.clone(),
-                            username: valid_username.to_string(),
-                            password: valid_password.to_string(),
-                        };
-
-                        // 添加到已找到凭据集合
-                        self.add_found_credential(found_cred.clone());
-
-                        Ok(Some(found_cred))
+            let (valid_username, valid_password) = match result {
+                AuthenticationResult::Success => (username, password),
+                AuthenticationResult::NoAuthenticationRequired => ("", ""),
+                AuthenticationResult::Failed => {
+                    debug!("Failed attempt: {}:{}", username, password);
+                    return Ok(None);
+                }
+                // 路径不存在时切换到其他流路径重试
+                AuthenticationResult::NotFound => match self.next_path(ip_port, &path).await {
+                    Some(next_path) => {
+                        debug!("{} not found, switching to {}", rtsp_url, next_path);
+                        path = next_path;
+                        continue;
                     }
                     None => {
-                        debug!("Failed attempt: {}:{}", username, password);
-                        Ok(None)
+                        self.skip_host(ip_port, result);
+                        return Ok(None);
+                    }
+                },
+                // 服务不可用或被限速时退避后重试
+                AuthenticationResult::ServiceUnavailable { retry_after }
+                    if retries < MAX_BACKOFF_RETRIES =>
+                {
+                    let delay = retry_after
+                        .unwrap_or(DEFAULT_BACKOFF * 2u32.pow(retries))
+                        .min(MAX_BACKOFF);
+                    debug!("{} unavailable, backing off for {:?}", rtsp_url, delay);
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = token.cancelled() => return Err(RtspError::Cancelled),
                     }
+                    retries += 1;
+                    continue;
+                }
+                // 禁止访问、会话错误、重定向等情况下继续尝试没有意义，跳过该目标
+                outcome => {
+                    self.skip_host(ip_port, outcome);
+                    return Ok(None);
+                }
+            };
+
+            let mut found_cred = FoundCredential {
+                ip_port: ip_port.clone(),
+                username: valid_username.to_string(),
+                password: valid_password.to_string(),
+                path,
+                final_url,
+                media,
+                playback,
+                evidence: None,
+                certificate,
+                target: self.client_options.credential_target,
+                accepted_schemes,
+            };
+
+            // 添加到已找到凭据集合，目标第一次找到凭据时使用凭据验证其他探测到的路径并录制取证材料
+            let first_for_host = !self.has_valid_credentials_for_ip(ip_port);
+            if self.add_found_credential(found_cred.clone()) && first_for_host {
+                self.verify_paths(&found_cred).await;
+                if let Some(session) = &session {
+                    found_cred = self.capture_evidence(found_cred, &rtsp_url, session).await;
                 }
             }
-            Err(e) => {
-                debug!("Error during authentication attempt: {:?}", e);
-                Err(e)
+
+            return Ok(Some(found_cred));
+        }
+    }
+
+    /// 生成目标的RTSP URL，TLS端口上使用rtsps://
+    fn rtsp_url(&self, ip_port: &IpPortAddr, path: &str) -> String {
+        let scheme = self.client_options.tls.scheme(ip_port);
+        format!("{}://{}{}", scheme, ip_port.authority(), path)
+    }
+
+    /// 目标使用的客户端选项，域名目标固定连接当前测试的地址，URL和Host头中保留域名，
+    /// 带http://前缀的目标通过HTTP隧道连接
+    fn client_options_for(&self, ip_port: &IpPortAddr) -> Cow<'_, ClientOptions> {
+        if ip_port.hostname.is_none() && !ip_port.http_tunnel {
+            return Cow::Borrowed(&self.client_options);
+        }
+        let mut options = self.client_options.clone();
+        if let Some(hostname) = &ip_port.hostname {
+            options
+                .pinned_addresses
+                .insert(hostname.to_ascii_lowercase(), ip_port.ip);
+        }
+        if ip_port.http_tunnel {
+            options.http_tunnel_targets.insert(ip_port.socket_addr());
+        }
+        Cow::Owned(options)
+    }
+
+    /// 获取目标当前使用的流路径
+    fn current_path(&self, ip_port: &IpPortAddr, default: &str) -> String {
+        self.host_paths
+            .lock()
+            .unwrap()
+            .get(ip_port)
+            .cloned()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// 流路径不存在时选择下一个需要认证的流路径，没有可用路径时返回None
+    async fn next_path(&self, ip_port: &IpPortAddr, missing: &str) -> Option<String> {
+        // 其他任务已经切换了路径
+        let current = self.current_path(ip_port, missing);
+        if current != missing {
+            return Some(current);
+        }
+
+        let known = self.discovered_paths.lock().unwrap().get(ip_port).cloned();
+        let remaining = match known {
+            Some(paths) => paths,
+            // 尚未探测过流路径时使用路径字典（默认为常见路径）进行探测
+            None => {
+                let paths = self
+                    .stream_paths
+                    .clone()
+                    .unwrap_or_else(path_discovery::default_stream_paths);
+                self.discover_paths(ip_port, &paths).await
             }
+        };
+        let remaining: Vec<DiscoveredPath> = remaining
+            .into_iter()
+            .filter(|discovered| discovered.path != missing)
+            .collect();
+        if let Some(paths) = self.discovered_paths.lock().unwrap().get_mut(ip_port) {
+            paths.retain(|discovered| discovered.path != missing);
+        }
+
+        let next = self.choose_path(ip_port, &remaining);
+        if let Some(next) = &next {
+            self.host_paths
+                .lock()
+                .unwrap()
+                .insert(ip_port.clone(), next.clone());
+        }
+        next
+    }
+
+    /// 获取目标的取消令牌
+    pub fn host_token(&self, ip_port: &IpPortAddr) -> CancellationToken {
+        self.host_tokens
+            .lock()
+            .unwrap()
+            .entry(ip_port.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// 取消目标剩余的凭据尝试，目标没有取消令牌时说明没有需要取消的尝试
+    fn cancel_host(&self, ip_port: &IpPortAddr) {
+        let token = self.host_tokens.lock().unwrap().get(ip_port).cloned();
+        if token.is_some_and(|token| token.cancel()) {
+            debug!("Cancelled remaining attempts for {}", ip_port);
+        }
+    }
+
+    /// 获取目标状态的租约
+    fn lease_host(&self, ip_port: &IpPortAddr) -> Arc<HostLease> {
+        *self
+            .host_leases
+            .lock()
+            .unwrap()
+            .entry(ip_port.clone())
+            .or_default() += 1;
+        Arc::new(HostLease {
+            ip: ip_port.clone(),
+            host_leases: self.host_leases.clone(),
+            host_tokens: self.host_tokens.clone(),
+            host_paths: self.host_paths.clone(),
+            discovered_paths: self.discovered_paths.clone(),
+        })
+    }
+
+    /// 跳过目标并记录原因，取消目标剩余的尝试
+    fn skip_host(&self, ip_port: &IpPortAddr, outcome: AuthenticationResult) {
+        let mut skipped_hosts = self.skipped_hosts.lock().unwrap();
+        if !skipped_hosts.contains_key(ip_port) {
+            info!("Skipping {}: {}", ip_port, outcome);
+            skipped_hosts.insert(ip_port.clone(), outcome);
         }
+        drop(skipped_hosts);
+        self.cancel_host(ip_port);
+    }
+
+    /// 检查目标是否已被跳过
+    pub fn is_host_skipped(&self, ip_port: &IpPortAddr) -> bool {
+        self.skipped_hosts.lock().unwrap().contains_key(ip_port)
     }
 
-    /// 添加找到的凭据到集合
-    pub fn add_found_credential(&self, credential: FoundCredential) {
+    /// 流水线中仍保留取消令牌或切换后路径的目标数量
+    pub fn active_hosts(&self) -> usize {
+        let host_tokens = self.host_tokens.lock().unwrap();
+        let host_paths = self.host_paths.lock().unwrap();
+        host_tokens
+            .keys()
+            .chain(host_paths.keys())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// 获取被跳过的目标及原因
+    pub fn skipped_hosts(&self) -> HashMap<IpPortAddr, AuthenticationResult> {
+        self.skipped_hosts.lock().unwrap().clone()
+    }
+
+    /// 添加找到的凭据到集合并按停止策略取消目标剩余的尝试，返回是否为新的凭据
+    pub fn add_found_credential(&self, credential: FoundCredential) -> bool {
         let mut found_credentials = self.found_credentials.lock().unwrap();
-        if found_credentials.insert(credential.clone()) {
-            // 日志中使用原始字符串，终端输出使用带颜色的字符串
-            debug!("Found credential: {}", credential);
-            println!("Found credential: {}", credential.to_colored_string());
+        if !found_credentials.insert(credential.clone()) {
+            return false;
+        }
+        drop(found_credentials);
+        // 日志中使用原始字符串，终端输出使用带颜色的字符串
+        debug!("Found credential: {}", credential);
+        println!("Found credential: {}", credential.to_colored_string());
+        // 无需认证的目标接受任何凭据，继续尝试没有意义
+        let no_auth = credential.username.is_empty() && credential.password.is_empty();
+        if self.stop_policy == StopPolicy::FirstHit || no_auth {
+            self.cancel_host(&credential.ip_port);
+        }
+        true
+    }
+
+    /// 获取所有找到的凭据
+    pub fn found_credentials(&self) -> Vec<FoundCredential> {
+        self.found_credentials.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// 使用无认证的DESCRIBE请求探测目标上存在的流路径
+    pub async fn discover_paths(&self, ip_port: &IpPortAddr, paths: &[String]) -> Vec<DiscoveredPath> {
+        let mut discovered = Vec::new();
+        for path in paths {
+            let rtsp_url = self.rtsp_url(ip_port, path);
+            match RTSP_WORKER_MANAGER
+                .probe_request(&rtsp_url, &self.client_options_for(ip_port))
+                .await
+            {
+                Ok(response) => match PathStatus::from_response(&response) {
+                    Some(status) => {
+                        debug!("Stream path {} is {}", rtsp_url, status);
+                        discovered.push(DiscoveredPath {
+                            path: path.clone(),
+                            status,
+                        });
+                    }
+                    None => debug!(
+                        "Unexpected response for {}: {}",
+                        rtsp_url,
+                        response.status_line()
+                    ),
+                },
+                Err(e) => debug!("Failed to probe {}: {:?}", rtsp_url, e),
+            }
+        }
+
+        let existing: Vec<DiscoveredPath> = discovered
+            .into_iter()
+            .filter(|discovered| discovered.status.exists())
+            .collect();
+        self.discovered_paths
+            .lock()
+            .unwrap()
+            .insert(ip_port.clone(), existing.clone());
+        existing
+    }
+
+    /// 使用找到的凭据验证探测到的其他流路径
+    /// 部分设备在路由之前先进行认证，未认证时所有路径都返回401，只有认证之后才能区分404
+    async fn verify_paths(&self, credential: &FoundCredential) {
+        let pending: Vec<String> = match self.discovered_paths.lock().unwrap().get(&credential.ip_port) {
+            Some(paths) => paths
+                .iter()
+                .filter(|discovered| discovered.status == PathStatus::AuthRequired)
+                .map(|discovered| discovered.path.clone())
+                .collect(),
+            None => return,
+        };
+
+        let mut verified = HashMap::new();
+        for path in pending {
+            let status = if path == credential.path {
+                Some(PathStatus::Authorized)
+            } else {
+                let rtsp_url = self.rtsp_url(&credential.ip_port, &path);
+                match RTSP_WORKER_MANAGER
+                    .describe_request(
+                        &credential.username,
+                        &credential.password,
+                        &rtsp_url,
+                        &self.client_options_for(&credential.ip_port),
+                    )
+                    .await
+                {
+                    Ok(outcome) => PathStatus::from_authentication(&outcome.result),
+                    Err(e) => {
+                        debug!("Failed to verify {}: {:?}", rtsp_url, e);
+                        None
+                    }
+                }
+            };
+            if let Some(status) = status {
+                verified.insert(path, status);
+            }
+        }
+
+        let mut discovered_paths = self.discovered_paths.lock().unwrap();
+        if let Some(paths) = discovered_paths.get_mut(&credential.ip_port) {
+            for discovered in paths.iter_mut() {
+                if let Some(status) = verified.get(&discovered.path) {
+                    discovered.status = *status;
+                }
+            }
+            paths.retain(|discovered| discovered.status.exists());
         }
     }
 
+    /// 选择用于凭据尝试的流路径，返回None表示跳过该目标
+    async fn select_path(&self, ip: &IpPortAddr) -> Option<String> {
+        match &self.stream_paths {
+            Some(paths) => {
+                let discovered = self.discover_paths(ip, paths).await;
+                let path = self.choose_path(ip, &discovered);
+                // 跳过的目标不会进入凭据阶段，不保留空的探测结果
+                if path.is_none() {
+                    remove_empty_paths(&self.discovered_paths, ip);
+                }
+                path
+            }
+            // 未启用路径探测时使用URL的默认路径
+            None => Some(String::new()),
+        }
+    }
+
+    /// 从探测到的路径中选择需要认证的路径，只有无需认证的流时直接记录
+    fn choose_path(&self, ip: &IpPortAddr, discovered: &[DiscoveredPath]) -> Option<String> {
+        if let Some(protected) = discovered
+            .iter()
+            .find(|discovered| discovered.status == PathStatus::AuthRequired)
+        {
+            return Some(protected.path.clone());
+        }
+
+        // 没有需要认证的路径时，记录无需认证的流
+        if let Some(open) = discovered
+            .iter()
+            .find(|discovered| discovered.status == PathStatus::Open)
+        {
+            self.add_found_credential(FoundCredential {
+                ip_port: ip.clone(),
+                username: String::new(),
+                password: String::new(),
+                path: open.path.clone(),
+                final_url: None,
+                media: Vec::new(),
+                playback: None,
+                evidence: None,
+                certificate: None,
+                target: CredentialTarget::Stream,
+                accepted_schemes: Vec::new(),
+            });
+        } else {
+            info!("No stream paths found on {}", ip);
+            self.skip_host(ip, AuthenticationResult::NotFound);
+        }
+        None
+    }
+
+    /// 使用找到的凭据录制取证材料，并更新已找到凭据集合中的记录
+    async fn capture_evidence(
+        &self,
+        credential: FoundCredential,
+        rtsp_url: &str,
+        session: &SessionDescription,
+    ) -> FoundCredential {
+        let options = match &self.evidence {
+            Some(options) => options,
+            None => return credential,
+        };
+        let url = credential.final_url.as_deref().unwrap_or(rtsp_url);
+        let directory = options.host_directory(&credential.ip_port);
+        let result = RtspClient::new(&credential.username, &credential.password)
+            .with_options(self.client_options_for(&credential.ip_port).into_owned())
+            .capture_evidence(url, session, options.duration, &directory)
+            .await;
+        let evidence = match result {
+            Ok(evidence) => {
+                info!("Captured evidence for {}: {}", credential.ip_port, evidence);
+                evidence
+            }
+            Err(e) => {
+                error!("Failed to capture evidence for {}: {}", url, e);
+                return credential;
+            }
+        };
+
+        let mut found_credentials = self.found_credentials.lock().unwrap();
+        found_credentials.remove(&credential);
+        let credential = FoundCredential {
+            evidence: Some(evidence),
+            ..credential
+        };
+        found_credentials.insert(credential.clone());
+        credential
+    }
+
     /// 检查IP是否已经找到有效凭据
     pub fn has_valid_credentials_for_ip(&self, ip: &IpPortAddr) -> bool {
         let found_credentials = self.found_credentials.lock().unwrap();
         found_credentials.iter().any(|cred| cred.ip_port == *ip)
     }
 
+    /// 按用户名停止时，检查用户名是否已经在目标上找到密码
+    fn is_username_done(&self, ip: &IpPortAddr, username: &str) -> bool {
+        if self.stop_policy != StopPolicy::PerUsername {
+            return false;
+        }
+        let found_credentials = self.found_credentials.lock().unwrap();
+        found_credentials
+            .iter()
+            .any(|cred| cred.ip_port == *ip && cred.username == username)
+    }
+
     /// 执行暴力枚举
+    /// 端口检查、凭据生成和凭据尝试分为三个阶段，通过有界队列连接：
+    /// 可连接的目标选择流路径后按完成的顺序进入凭据阶段，生产者按需从迭代器生成凭据尝试，
+    /// 工作任务从队列中取出执行，结果在完成时立即处理，内存占用与目标和凭据的数量无关
     pub async fn brute_force(&self) -> Result<(), RtspError> {
         let start_time = Instant::now();
+        let capacity = self.task_manager.channel_capacity();
+        let (host_tx, host_rx) = mpsc::channel(self.connect_concurrency());
+        let (work_tx, work_rx) = mpsc::channel(capacity);
+        let (result_tx, result_rx) = mpsc::channel(capacity);
 
-        let mut tasks = Vec::new();
-
-        // 创建信号量限制并发数
-        let semaphore = Arc::new(Semaphore::new(self.max_concurrent as usize));
-
-        // 并行尝试连接所有IP
-        let mut connect_tasks = Vec::new();
-        for (_, ip) in self.ip_iterator.clone().enumerate() {
-            let ip_clone = ip.clone();
-            let semaphore_clone = semaphore.clone();
-            connect_tasks.push(tokio::spawn(async move {
-                // 获取信号量许可
-                let _permit = semaphore_clone.acquire_owned().await.unwrap();
-                let is_connected = ip_clone.try_connect().await;
-                (ip_clone, is_connected)
-            }));
-        }
+        let this = Arc::new(self.clone());
+        let checker = tokio::spawn({
+            let this = this.clone();
+            async move { this.check_hosts(host_tx).await }
+        });
+        let producer = tokio::spawn({
+            let this = this.clone();
+            async move { this.produce_work(host_rx, work_tx).await }
+        });
 
-        let total_tasks = Arc::new(AtomicUsize::new(0));
+        let work_rx = Arc::new(tokio::sync::Mutex::new(work_rx));
+        let workers: Vec<_> = (0..self.max_concurrent.max(1))
+            .map(|id| {
+                let worker = this.clone().run_worker(id, work_rx.clone(), result_tx.clone());
+                tokio::spawn(worker)
+            })
+            .collect();
+        // 所有工作任务结束后结果通道关闭
+        drop(result_tx);
 
-        // 为每个成功连接的IP创建凭据尝试任务
-        let mut ip_idx = 0;
-        for connect_task in connect_tasks {
-            let this_clone = Arc::new(self.clone());
-            this_clone.process_connect_task(
-                connect_task,
-                &semaphore,
-                &total_tasks,
-                &mut tasks,
-                ip_idx
-            ).await;
-            ip_idx += 1;
-        }
-
-        info!("All tasks created. Waiting for completion...");
-        // 等待所有任务完成并处理结果
-        let (total, successful, duration) = self.task_manager
-            .process_task_results(tasks, start_time, total_tasks)
+        self.task_manager
+            .process_task_results(result_rx, start_time)
             .await;
-        let _ = (total, successful, duration);
+
+        if let Err(e) = checker.await {
+            error!("Port checker failed: {:?}", e);
+        }
+        // 读取字典出错时生产者提前结束，已排队的尝试完成后返回错误
+        let produced = match producer.await {
+            Ok(produced) => produced,
+            Err(e) => {
+                error!("Work producer failed: {:?}", e);
+                Ok(())
+            }
+        };
+        for worker in workers {
+            if let Err(e) = worker.await {
+                error!("Worker task failed: {:?}", e);
+            }
+        }
 
         self.print_summary();
-        Ok(())
+        produced
     }
 
-    /// 处理单个连接任务的结果
-    async fn process_connect_task(
-        &self,
-        connect_task: tokio::task::JoinHandle<(IpPortAddr, bool)>,
-        semaphore: &Arc<Semaphore>,
-        total_tasks: &Arc<AtomicUsize>,
-        tasks: &mut Vec<tokio::task::JoinHandle<Result<Option<FoundCredential>, RtspError>>>,
-        ip_idx: usize
-    ) {
-        match connect_task.await {
-            // 只有成功连接的IP才创建任务
+    /// 端口检查的并发数
+    fn connect_concurrency(&self) -> usize {
+        self.connect_concurrency.unwrap_or(self.max_concurrent).max(1) as usize
+    }
 
-            Ok((ip, true)) => {
+    /// 端口检查阶段：并发检查目标是否可连接并为其选择流路径，准备好的目标按完成顺序
+    /// 发送给生产者，慢速或被过滤的目标以及路径探测都不会阻塞其后的目标
+    async fn check_hosts(&self, host_tx: mpsc::Sender<(IpPortAddr, String)>) {
+        let mut checked = futures::stream::iter(self.ip_iterator.clone())
+            .map(|ip| async move {
+                // 只有成功连接的IP才选择路径并生成凭据尝试
+                if !ip.try_connect().await {
+                    debug!("Failed to connect to {}. Skip credentials.", &ip);
+                    return None;
+                }
                 debug!("Successfully connected to {}", ip);
-                let cred_size = self.credential_iterator.clone().count();
-
-                total_tasks.fetch_add(cred_size, Ordering::Relaxed);
-                for (cred_idx, (username, password)) in self.credential_iterator.clone().enumerate() {
-                    // 提前获取信号量许可
-                    let permit = semaphore.clone().acquire_owned().await.unwrap();
-                    let this_clone = Arc::new((*self).clone());
-                    let ip_clone = ip.clone();
-                    let username_clone = username.clone();
-                    let password_clone = password.clone();
-                    let task_idx = ip_idx * self.credential_iterator.clone().count() + cred_idx;
-
-                    let task = tokio::spawn(async move {
-                        let _permit = permit;
-                        trace!(
-                            "Task {} started on thread {:?}",
-                            task_idx + 1,
-                            thread::current().id()
-                        );
-                        let result = this_clone
-                            .try_credentials(&username_clone, &password_clone, &ip_clone)
-                            .await;
-                        trace!("Task {} completed", task_idx + 1);
-                        result
-                    });
-                    tasks.push(task);
+                let path = self.select_path(&ip).await?;
+                Some((ip, path))
+            })
+            .buffer_unordered(self.connect_concurrency());
+
+        while let Some(target) = checked.next().await {
+            let target = match target {
+                Some(target) => target,
+                None => continue,
+            };
+            if host_tx.send(target).await.is_err() {
+                return;
+            }
+        }
+        debug!("All port checks completed");
+    }
+
+    /// 生产者：在已选择路径的目标之间轮流生成凭据尝试，队列满时等待。
+    /// 同时轮换的目标数量不超过端口检查的并发数，慢速或被限速的目标不会占满所有工作任务
+    async fn produce_work(
+        &self,
+        mut host_rx: mpsc::Receiver<(IpPortAddr, String)>,
+        work_tx: mpsc::Sender<WorkItem>,
+    ) -> Result<(), RtspError> {
+        let max_active = self.connect_concurrency();
+        let mut active: VecDeque<HostWork> = VecDeque::new();
+        let mut checking = true;
+        loop {
+            // 接收端口检查完成的目标，没有正在轮换的目标时等待
+            while checking && active.len() < max_active {
+                let target = if active.is_empty() {
+                    host_rx.recv().await
+                } else {
+                    match host_rx.try_recv() {
+                        Ok(target) => Some(target),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => None,
+                    }
+                };
+                match target {
+                    Some((ip, path)) => active.push_back(HostWork {
+                        lease: self.lease_host(&ip),
+                        token: self.host_token(&ip),
+                        ip,
+                        path,
+                        credentials: self.credential_iterator.clone(),
+                    }),
+                    None => checking = false,
                 }
-            },
-            Ok((ip, false)) => {
-                debug!("Failed to connect to {}. Skip credentials.", &ip);
-            },
-            Err(e) => {
-                error!("Connection task failed: {:?}", e);
+            }
+
+            let Some(mut host) = active.pop_front() else {
+                break;
+            };
+            // 目标的凭据已用完或已被取消时不再放回，剩余的尝试完成后释放目标的状态
+            let Some(item) = self.next_work(&mut host)? else {
+                continue;
+            };
+            if work_tx.send(item).await.is_err() {
+                return Ok(());
+            }
+            active.push_back(host);
+        }
+        debug!("All credential attempts queued");
+        Ok(())
+    }
+
+    /// 目标的下一个凭据尝试，目标已找到凭据、已被跳过或凭据已用完时返回None
+    fn next_work(&self, host: &mut HostWork) -> Result<Option<WorkItem>, RtspError> {
+        for credential in host.credentials.by_ref() {
+            let (username, password) = credential?;
+            // 目标已找到凭据或已被跳过时不再生成剩余的尝试
+            if host.token.is_cancelled() {
+                debug!("Stop queueing credentials for {}", host.ip);
+                return Ok(None);
+            }
+            // 用户名已找到密码时跳过其剩余的密码
+            if self.is_username_done(&host.ip, &username) {
+                continue;
+            }
+            return Ok(Some(WorkItem {
+                ip_port: host.ip.clone(),
+                path: host.path.clone(),
+                username,
+                password,
+                _lease: host.lease.clone(),
+            }));
+        }
+        Ok(None)
+    }
+
+    /// 工作任务：从队列中取出凭据尝试并执行，结果发送给结果处理
+    async fn run_worker(
+        self: Arc<Self>,
+        id: u32,
+        work_rx: Arc<tokio::sync::Mutex<mpsc::Receiver<WorkItem>>>,
+        result_tx: mpsc::Sender<Result<Option<FoundCredential>, RtspError>>,
+    ) {
+        loop {
+            let item = work_rx.lock().await.recv().await;
+            let item = match item {
+                Some(item) => item,
+                None => break,
+            };
+            trace!(
+                "Worker {} trying {}:{} on {} on thread {:?}",
+                id,
+                item.username,
+                item.password,
+                item.ip_port,
+                thread::current().id()
+            );
+            let result = self
+                .try_credentials(&item.username, &item.password, &item.ip_port, &item.path)
+                .await;
+            if result_tx.send(result).await.is_err() {
+                break;
             }
         }
+        trace!("Worker {} finished", id);
     }
 
     /// 打印暴力破解的总结信息
@@ -288,5 +1051,31 @@ impl BruteForcer {
                 println!("- {}", cred.to_colored_string());
             }
         }
+
+        let discovered_paths = self.discovered_paths.lock().unwrap();
+        if discovered_paths.values().any(|paths| !paths.is_empty()) {
+            println!("\n{}\n", "Discovered stream paths:".green());
+            for (ip_port, paths) in discovered_paths.iter() {
+                for discovered in paths {
+                    let location = ip_port.location(&discovered.path);
+                    debug!("{} ({})", location, discovered.status);
+                    println!(
+                        "- {} ({})",
+                        location.cyan(),
+                        discovered.status
+                    );
+                }
+            }
+        }
+
+        let skipped_hosts = self.skipped_hosts.lock().unwrap();
+        if !skipped_hosts.is_empty() {
+            info!("Total skipped hosts: {}", skipped_hosts.len());
+            println!("\n{}\n", "Skipped hosts:".yellow());
+            for (ip_port, outcome) in skipped_hosts.iter() {
+                debug!("{}: {}", ip_port, outcome);
+                println!("- {}: {}", ip_port.to_string().cyan(), outcome);
+            }
+        }
     }
 }
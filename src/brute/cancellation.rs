@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// 取消令牌 - 取消后正在等待的任务立即结束，克隆的令牌共享取消状态
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenState>,
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取消令牌并唤醒所有等待的任务，返回是否为第一次取消
+    pub fn cancel(&self) -> bool {
+        let first = !self.inner.cancelled.swap(true, Ordering::SeqCst);
+        if first {
+            self.inner.notify.notify_waiters();
+        }
+        first
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待令牌被取消
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        tokio::pin!(notified);
+        // 先注册等待再检查状态，避免错过检查之后的取消通知
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
@@ -1,7 +1,10 @@
 // 定义brute模块的子模块
 pub mod brute_forcer;
+pub mod cancellation;
 pub mod task_manager;
 
 // 重新导出子模块中的类型，方便外部使用
 pub use brute_forcer::BruteForcer;
 pub use brute_forcer::FoundCredential;
+pub use brute_forcer::StopPolicy;
+pub use cancellation::CancellationToken;
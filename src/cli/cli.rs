@@ -4,14 +4,16 @@ use std::error::Error;
 use std::sync::Arc;
 // use url::Url;  // 未使用的导入，已注释
 use crate::brute::brute_forcer::BruteForcer;
-use crate::config::config::Cli;
+use crate::config::config::{Cli, ScanOptions};
 use crate::iterator::credential_iterator::CredentialIterator;
 use crate::iterator::credential_reader::{CredentialReader, CredentialSource};
 use crate::iterator::ip_iterator::IpIterator;
 use crate::iterator::ip_reader::{IpReader, IpSource};
 
-// 解析Brute模式的命令行参数
-pub fn parse_brute_args(cli: Cli) -> Result<(IpIterator, CredentialIterator, u32), Box<dyn Error>> {
+// 解析Brute模式的命令行参数，域名目标在此异步解析
+pub async fn parse_brute_args(
+    cli: Cli,
+) -> Result<(IpIterator, CredentialIterator, u32, ScanOptions), Box<dyn Error>> {
     let Cli::Args {
         users_file,
         users_string,
@@ -21,6 +23,7 @@ pub fn parse_brute_args(cli: Cli) -> Result<(IpIterator, CredentialIterator, u32
         ips_string,
         max_concurrent,
         // delay,
+        options,
     } = cli;
 
     // ip迭代器，从文件或者命令行参数中获取Ip地址并解析
@@ -36,7 +39,7 @@ pub fn parse_brute_args(cli: Cli) -> Result<(IpIterator, CredentialIterator, u32
                 .into());
             }
         };
-        ip_reader.into_iterator()?
+        ip_reader.into_iterator().await?
     };
 
     // 凭证迭代器，从文件或命令行参数中获取（username，password）对
@@ -65,18 +68,23 @@ pub fn parse_brute_args(cli: Cli) -> Result<(IpIterator, CredentialIterator, u32
         credential_reader.into_iterator()?
     };
 
-    Ok((ip_iterator, cred_iterator, max_concurrent))
+    Ok((ip_iterator, cred_iterator, max_concurrent, options))
 }
 
 // 处理命令行参数并执行相应的操作
 pub async fn handle_cli(cli: Cli) -> Result<(), Box<dyn Error>> {
     // 从配置中获取ip，（用户名，密码）列表，接下来用于生成rtsp任务，验证ip端口和密码
-    let (ip_iterator, cred_iterator, max_concurrent) = parse_brute_args(cli)?;
+    let (ip_iterator, cred_iterator, max_concurrent, options) = parse_brute_args(cli).await?;
 
     // 从配置中获取ip，（用户名，密码）列表，接下来用于生成rtsp任务，验证ip端口和密码
     let brute_forcer = Arc::new(
         BruteForcer::new()
             .with_max_concurrent(max_concurrent)
+            .with_connect_concurrency(options.connect_concurrency)
+            .with_stop_policy(options.stop_policy.unwrap_or_default())
+            .with_client_options(options.client_options())
+            .with_stream_paths(options.stream_paths()?)
+            .with_evidence(options.evidence_options())
             .with_ip_iterator(ip_iterator)
             .with_cred_iterator(cred_iterator),
     );
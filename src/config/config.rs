@@ -1,7 +1,16 @@
-use clap::Parser;
+use crate::brute::brute_forcer::StopPolicy;
+use crate::errors::errors::RtspError;
+use crate::rtsp::auth::{AuthPolicy, NonceCounts};
+use crate::rtsp::challenge_cache::ChallengeCache;
+use crate::rtsp::client::{ClientOptions, CredentialTarget, ProxyCredentials};
+use crate::rtsp::evidence::EvidenceOptions;
+use crate::rtsp::path_discovery;
+use crate::rtsp::transport::TlsOptions;
+use clap::{Args, Parser};
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use toml;
+use std::time::Duration;
 
 /// 命令行参数枚举
 #[derive(Parser, Debug)]
@@ -33,9 +42,168 @@ pub enum Cli {
         // /// 尝试之间的延迟(毫秒)
         // #[arg(short, long, default_value_t = 100)]
         // delay: u64,
+        /// 扫描选项
+        #[command(flatten)]
+        options: ScanOptions,
     },
 }
 
+/// 扫描选项，命令行和配置文件共用，未设置的选项使用默认值。
+/// 开关选项可以写为--keep-alive或--keep-alive=false，命令行中的值覆盖配置文件
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// 认证方案选择策略
+    #[arg(long, value_enum)]
+    pub auth_policy: Option<AuthPolicy>,
+    /// 在同一RTSP连接上复用多次凭据尝试
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub keep_alive: Option<bool>,
+    /// 按目标缓存认证挑战，跳过每次尝试前的无认证探测请求
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub cache_challenge: Option<bool>,
+    /// 在尝试凭据之前探测常见的流路径
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub discover_paths: Option<bool>,
+    /// 包含流路径字典的文件路径，设置后使用该字典探测流路径
+    #[arg(long)]
+    pub paths_file: Option<String>,
+    /// 跟随同一主机上RTSP重定向的最大次数 (默认: 3，为0时不跟随)
+    #[arg(long)]
+    pub max_redirects: Option<u32>,
+    /// 找到凭据后执行SETUP/PLAY，验证是否真正收到媒体数据
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub verify_playback: Option<bool>,
+    /// 找到凭据后录制媒体流作为取证材料的目录，设置后启用取证录制
+    #[arg(long)]
+    pub evidence_dir: Option<String>,
+    /// 取证录制的时长(秒) (默认: 5)
+    #[arg(long)]
+    pub evidence_seconds: Option<u64>,
+    /// 接受RTSPS服务器的自签名或无效证书
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub accept_invalid_certs: Option<bool>,
+    /// 使用RTSPS连接的端口，以逗号分隔 (默认: 322,8322)
+    #[arg(long, value_delimiter = ',')]
+    pub tls_ports: Option<Vec<u16>>,
+    /// 通过RTSP over HTTP隧道连接的端口，以逗号分隔，如80,8080 (对所有主机生效，单个目标使用http://前缀)
+    #[arg(long, value_delimiter = ',')]
+    pub http_tunnel_ports: Option<Vec<u16>>,
+    /// RTSP代理要求认证(407)时使用的用户名
+    #[arg(long)]
+    pub proxy_username: Option<String>,
+    /// RTSP代理要求认证(407)时使用的密码
+    #[arg(long)]
+    pub proxy_password: Option<String>,
+    /// 枚举RTSP代理本身的凭据，而不是摄像头的凭据
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub brute_proxy: Option<bool>,
+    /// 端口检查的最大并发数 (默认: 与max_concurrent相同)
+    #[arg(long)]
+    pub connect_concurrency: Option<u32>,
+    /// 找到凭据后停止测试目标的策略 (默认: first-hit)
+    #[arg(long, value_enum)]
+    pub stop_policy: Option<StopPolicy>,
+}
+
+impl ScanOptions {
+    /// 合并选项，自身（命令行）优先，未设置时使用配置文件中的值
+    pub fn merge(self, fallback: &ScanOptions) -> Self {
+        ScanOptions {
+            auth_policy: self.auth_policy.or(fallback.auth_policy),
+            keep_alive: self.keep_alive.or(fallback.keep_alive),
+            cache_challenge: self.cache_challenge.or(fallback.cache_challenge),
+            discover_paths: self.discover_paths.or(fallback.discover_paths),
+            paths_file: self.paths_file.or(fallback.paths_file.clone()),
+            max_redirects: self.max_redirects.or(fallback.max_redirects),
+            verify_playback: self.verify_playback.or(fallback.verify_playback),
+            evidence_dir: self.evidence_dir.or(fallback.evidence_dir.clone()),
+            evidence_seconds: self.evidence_seconds.or(fallback.evidence_seconds),
+            accept_invalid_certs: self.accept_invalid_certs.or(fallback.accept_invalid_certs),
+            tls_ports: self.tls_ports.or(fallback.tls_ports.clone()),
+            http_tunnel_ports: self
+                .http_tunnel_ports
+                .or(fallback.http_tunnel_ports.clone()),
+            proxy_username: self.proxy_username.or(fallback.proxy_username.clone()),
+            proxy_password: self.proxy_password.or(fallback.proxy_password.clone()),
+            brute_proxy: self.brute_proxy.or(fallback.brute_proxy),
+            connect_concurrency: self.connect_concurrency.or(fallback.connect_concurrency),
+            stop_policy: self.stop_policy.or(fallback.stop_policy),
+        }
+    }
+
+    /// 读取路径探测使用的流路径字典，未启用路径探测时返回None
+    pub fn stream_paths(&self) -> Result<Option<Vec<String>>, RtspError> {
+        match (&self.paths_file, self.discover_paths.unwrap_or_default()) {
+            (Some(file), _) => path_discovery::load_stream_paths(file).map(Some),
+            (None, true) => Ok(Some(path_discovery::default_stream_paths())),
+            (None, false) => Ok(None),
+        }
+    }
+
+    /// 生成取证录制选项，未设置取证目录时返回None
+    pub fn evidence_options(&self) -> Option<EvidenceOptions> {
+        let duration = self
+            .evidence_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(EvidenceOptions::DEFAULT_DURATION);
+        self.evidence_dir
+            .as_deref()
+            .map(|directory| EvidenceOptions::new(directory, duration))
+    }
+
+    /// 生成TLS选项
+    pub fn tls_options(&self) -> TlsOptions {
+        let mut tls = TlsOptions {
+            accept_invalid_certs: self.accept_invalid_certs.unwrap_or_default(),
+            ..TlsOptions::default()
+        };
+        if let Some(ports) = &self.tls_ports {
+            tls.ports = ports.clone();
+        }
+        tls
+    }
+
+    /// 生成代理凭据，未设置代理用户名时返回None
+    pub fn proxy_credentials(&self) -> Option<ProxyCredentials> {
+        self.proxy_username
+            .as_ref()
+            .map(|username| ProxyCredentials {
+                username: username.clone(),
+                password: self.proxy_password.clone().unwrap_or_default(),
+            })
+    }
+
+    /// 生成RTSP客户端选项
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            auth_policy: self.auth_policy.unwrap_or_default(),
+            keep_alive: self.keep_alive.unwrap_or_default(),
+            challenge_cache: self
+                .cache_challenge
+                .unwrap_or_default()
+                .then(ChallengeCache::default),
+            nonce_counts: NonceCounts::default(),
+            max_redirects: self
+                .max_redirects
+                .unwrap_or(ClientOptions::DEFAULT_MAX_REDIRECTS),
+            verify_playback: self.verify_playback.unwrap_or_default(),
+            tls: self.tls_options(),
+            http_tunnel_ports: self.http_tunnel_ports.clone().unwrap_or_default(),
+            // 由暴力枚举器为每个带http://前缀的目标设置
+            http_tunnel_targets: HashSet::new(),
+            proxy_credentials: self.proxy_credentials(),
+            credential_target: if self.brute_proxy.unwrap_or_default() {
+                CredentialTarget::Proxy
+            } else {
+                CredentialTarget::Stream
+            },
+            // 由暴力枚举器为每个域名目标固定地址
+            pinned_addresses: HashMap::new(),
+        }
+    }
+}
+
 /// 从配置文件中读取的配置内容
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
@@ -57,6 +225,9 @@ pub struct AppConfig {
     pub max_concurrent: u32,
     // /// 尝试之间的延迟(毫秒)
     // pub delay: u64,
+    /// 扫描选项
+    #[serde(flatten, default)]
+    pub options: ScanOptions,
 }
 
 impl AppConfig {
@@ -78,6 +249,7 @@ impl AppConfig {
                 ips_file,
                 ips_string,
                 max_concurrent,
+                options,
             } => Ok(AppConfig {
                 mode: "brute".to_string(),
                 users_file,
@@ -87,6 +259,7 @@ impl AppConfig {
                 ips_file,
                 ips_string,
                 max_concurrent,
+                options,
             }),
         }
     }
@@ -106,6 +279,7 @@ impl AppConfig {
             ips_file: cli_config.ips_file.or(self.ips_file.clone()),
             ips_string: cli_config.ips_string.or(self.ips_string.clone()),
             max_concurrent: cli_config.max_concurrent,
+            options: cli_config.options.merge(&self.options),
         })
     }
 }
@@ -141,6 +315,7 @@ pub fn load_and_merge_config() -> Result<AppConfig, Box<dyn std::error::Error>>
                 ips_file: None,
                 ips_string: Some("127.0.0.1".to_string()),
                 max_concurrent: 5,
+                options: ScanOptions::default(),
             }
         }     
     };
@@ -164,6 +339,7 @@ pub async fn load_config_and_handle_cli(merged_config: AppConfig) -> Result<(),
         ips_file: merged_config.ips_file,
         ips_string: merged_config.ips_string,
         max_concurrent: merged_config.max_concurrent,
+        options: merged_config.options,
     })
     .await?;
 
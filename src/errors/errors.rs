@@ -1,6 +1,7 @@
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+use std::time::Duration;
 
 /// 定义结果类型别名
 pub type Result<T> = std::result::Result<T, ParseError>;
@@ -18,6 +19,8 @@ pub enum ParseError {
     InvalidCidrFormat(String),
     /// 无效的CIDR值
     InvalidCidrValue(String),
+    /// IPv6 CIDR范围过大
+    Ipv6CidrTooLarge(u8),
     /// 无效的IP格式
     InvalidIpFormat(String),
     /// 无效的端口范围格式
@@ -42,6 +45,12 @@ impl fmt::Display for ParseError {
             ParseError::EmptyIpSegment => write!(f, "Empty IP segment"),
             ParseError::InvalidCidrFormat(s) => write!(f, "Invalid CIDR format: {}", s),
             ParseError::InvalidCidrValue(s) => write!(f, "Invalid CIDR value: {}", s),
+            ParseError::Ipv6CidrTooLarge(prefix) => write!(
+                f,
+                "IPv6 CIDR /{} is too large, the prefix must be at least /{}",
+                prefix,
+                crate::iterator::ip_port_parser::MIN_IPV6_CIDR_PREFIX
+            ),
             ParseError::InvalidIpFormat(s) => write!(f, "Invalid IP format: {}", s),
             ParseError::InvalidPortRangeFormat(s) => write!(f, "Invalid port range format: {}", s),
             ParseError::InvalidPortNumber(s) => write!(f, "Invalid port number: {}", s),
@@ -59,7 +68,7 @@ impl Error for ParseError {}
 use std::error::Error;
 
 // 定义认证结果类型
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AuthenticationResult {
     // 认证成功
     Success,
@@ -67,6 +76,59 @@ pub enum AuthenticationResult {
     NoAuthenticationRequired,
     // 认证失败
     Failed,
+    // 禁止访问（403），通常是IP被封禁或账户被锁定
+    Forbidden,
+    // 请求的流路径不存在（404）
+    NotFound,
+    // RTSP代理要求认证（407），未配置代理凭据或代理凭据错误
+    ProxyAuthenticationRequired,
+    // 会话不存在（454）
+    SessionNotFound,
+    // 不支持的传输方式（461）
+    UnsupportedTransport,
+    // 服务不可用或被限速（503），可能携带Retry-After
+    ServiceUnavailable { retry_after: Option<Duration> },
+    // 重定向（3xx），可能携带Location
+    Redirect { status: u16, location: Option<String> },
+    // 要求通过代理访问（305），Location为代理地址
+    UseProxy { proxy: Option<String> },
+}
+
+impl fmt::Display for AuthenticationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthenticationResult::Success => write!(f, "Authentication succeeded"),
+            AuthenticationResult::NoAuthenticationRequired => {
+                write!(f, "No authentication required")
+            }
+            AuthenticationResult::Failed => write!(f, "Authentication failed"),
+            AuthenticationResult::Forbidden => write!(f, "Forbidden (403)"),
+            AuthenticationResult::NotFound => write!(f, "Stream path not found (404)"),
+            AuthenticationResult::ProxyAuthenticationRequired => {
+                write!(f, "Proxy authentication required (407)")
+            }
+            AuthenticationResult::SessionNotFound => write!(f, "Session not found (454)"),
+            AuthenticationResult::UnsupportedTransport => {
+                write!(f, "Unsupported transport (461)")
+            }
+            AuthenticationResult::ServiceUnavailable { retry_after } => match retry_after {
+                Some(retry_after) => write!(
+                    f,
+                    "Service unavailable (503), retry after {}s",
+                    retry_after.as_secs()
+                ),
+                None => write!(f, "Service unavailable (503)"),
+            },
+            AuthenticationResult::Redirect { status, location } => match location {
+                Some(location) => write!(f, "Redirected ({}) to {}", status, location),
+                None => write!(f, "Redirected ({})", status),
+            },
+            AuthenticationResult::UseProxy { proxy } => match proxy {
+                Some(proxy) => write!(f, "Use proxy (305) {}", proxy),
+                None => write!(f, "Use proxy (305)"),
+            },
+        }
+    }
 }
 
 // 定义RTSP错误类型
@@ -83,6 +145,8 @@ pub enum RtspError {
     AuthenticationError(String),
     ProtocolError(String),
     InvalidIpAddress(String),
+    // 域名解析错误
+    ResolveError(String),
     InvalidArgument(String),
 }
 
@@ -95,6 +159,7 @@ impl fmt::Display for RtspError {
             RtspError::AuthenticationError(e) => write!(f, "Authentication error: {}", e),
             RtspError::ProtocolError(e) => write!(f, "Protocol error: {}", e),
             RtspError::InvalidIpAddress(e) => write!(f, "Invalid IP address: {}", e),
+            RtspError::ResolveError(e) => write!(f, "Failed to resolve host: {}", e),
             RtspError::InvalidArgument(e) => write!(f, "Invalid argument: {}", e),
             RtspError::Cancelled => write!(f, "Operation cancelled"),
             RtspError::TimeoutError(e) => write!(f, "Timeout error: {}", e),
@@ -1,43 +1,128 @@
+use crate::errors::errors::RtspError;
+use crate::iterator::wordlist::{Wordlist, Words};
 use std::vec::Vec;
 
-// 凭据迭代器 - 用于生成用户名和密码的组合
-#[derive(Clone)]
+// 凭据迭代器 - 用于生成用户名和密码的组合，从字典中顺序读取，字典文件不会整体载入内存。
+// 每个用户名遍历一次密码字典，读取字典出错时返回错误并结束迭代
 pub struct CredentialIterator {
-    usernames: Vec<String>,
-    passwords: Vec<String>,
-    user_index: usize,
-    pass_index: usize,
+    usernames: Wordlist,
+    passwords: Wordlist,
+    // 已经生成的组合数量
+    position: usize,
+    // 用户名和当前用户名的密码的读取位置，克隆或第一次迭代时从position处打开
+    username_words: Option<Words>,
+    password_words: Option<Words>,
+    // 当前用户名
+    username: Option<String>,
+}
+
+// 克隆的迭代器从当前位置继续，字典在第一次迭代时重新打开
+impl Clone for CredentialIterator {
+    fn clone(&self) -> Self {
+        CredentialIterator {
+            usernames: self.usernames.clone(),
+            passwords: self.passwords.clone(),
+            position: self.position,
+            username_words: None,
+            password_words: None,
+            username: None,
+        }
+    }
 }
 
 impl Iterator for CredentialIterator {
-    type Item = (String, String);
+    type Item = Result<(String, String), RtspError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.user_index >= self.usernames.len() {
+        if self.position >= self.len_total() {
             return None;
         }
-
-        let current_user = self.usernames[self.user_index].clone();
-        let current_pass = self.passwords[self.pass_index].clone();
-
-        // 更新索引，准备下一次迭代
-        self.pass_index += 1;
-        if self.pass_index >= self.passwords.len() {
-            self.pass_index = 0;
-            self.user_index += 1;
+        match self.next_credential() {
+            Ok(credential) => {
+                self.position += 1;
+                Some(Ok(credential))
+            }
+            Err(e) => {
+                // 出错后不再生成剩余的组合
+                self.position = self.len_total();
+                Some(Err(e))
+            }
         }
+    }
 
-        Some((current_user, current_pass))
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len_total() - self.position;
+        (remaining, Some(remaining))
     }
 }
 
+impl ExactSizeIterator for CredentialIterator {}
+
 impl CredentialIterator {
     pub fn new(usernames: Vec<String>, passwords: Vec<String>) -> Self {
+        Self::from_wordlists(
+            Wordlist::from_words(usernames),
+            Wordlist::from_words(passwords),
+        )
+    }
+
+    pub fn from_wordlists(usernames: Wordlist, passwords: Wordlist) -> Self {
         CredentialIterator {
             usernames,
             passwords,
-            user_index: 0,
-            pass_index: 0,
+            position: 0,
+            username_words: None,
+            password_words: None,
+            username: None,
         }
     }
+
+    // 组合总数
+    fn len_total(&self) -> usize {
+        self.usernames.len() * self.passwords.len()
+    }
+
+    // 读取position处的组合，开始新的用户名时重新遍历密码字典
+    fn next_credential(&mut self) -> Result<(String, String), RtspError> {
+        let per_user = self.passwords.len();
+        let password_index = self.position % per_user;
+        if self.username.is_none() || password_index == 0 {
+            let usernames = match &mut self.username_words {
+                Some(words) => words,
+                None => self
+                    .username_words
+                    .insert(open_at(&self.usernames, self.position / per_user)?),
+            };
+            self.username = Some(next_word(usernames)?);
+            self.password_words = None;
+        }
+        let passwords = match &mut self.password_words {
+            Some(words) => words,
+            None => self
+                .password_words
+                .insert(open_at(&self.passwords, password_index)?),
+        };
+        let password = next_word(passwords)?;
+        let username = self.username.clone().unwrap_or_default();
+        Ok((username, password))
+    }
+}
+
+// 打开字典并跳过前skip个单词
+fn open_at(wordlist: &Wordlist, skip: usize) -> Result<Words, RtspError> {
+    let mut words = wordlist.words()?;
+    for _ in 0..skip {
+        next_word(&mut words)?;
+    }
+    Ok(words)
+}
+
+// 读取下一个单词，字典提前结束时返回错误
+fn next_word(words: &mut Words) -> Result<String, RtspError> {
+    words.next().unwrap_or_else(|| {
+        Err(RtspError::IoError(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "wordlist ended early",
+        )))
+    })
 }
@@ -1,17 +1,18 @@
 use crate::errors::errors::RtspError;
 use crate::iterator::credential_iterator::CredentialIterator;
+use crate::iterator::wordlist::Wordlist;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::vec::Vec;
 
 // 辅助函数：从文件读取非空行
-fn read_lines_from_file(file_path: &str) -> Result<Vec<String>, RtspError> {
-    let file = File::open(file_path).map_err(|e| RtspError::IoError(e))?;
+pub(crate) fn read_lines_from_file(file_path: &str) -> Result<Vec<String>, RtspError> {
+    let file = File::open(file_path).map_err(RtspError::IoError)?;
     let reader = BufReader::new(file);
     let mut lines = Vec::new();
 
     for line in reader.lines() {
-        let line = line.map_err(|e| RtspError::IoError(e))?;
+        let line = line.map_err(RtspError::IoError)?;
         if !line.trim().is_empty() {
             lines.push(line.trim().to_string());
         }
@@ -81,22 +82,26 @@ impl CredentialReader<CredentialSource> {
         }
     }
 
-    // 读取用户名列表
-    fn read_usernames(&self) -> Result<Vec<String>, RtspError> {
+    // 用户名字典，文件只统计单词数量，单词在迭代时读取
+    fn read_usernames(&self) -> Result<Wordlist, RtspError> {
         match &self.source {
             CredentialSource::UsernameAndPassword(username_source, _) => match username_source {
-                UsernameSource::FilePath(file_path) => read_lines_from_file(file_path),
-                UsernameSource::UsernameString(username) => Ok(vec![username.clone()]),
+                UsernameSource::FilePath(file_path) => Wordlist::from_file(file_path),
+                UsernameSource::UsernameString(username) => {
+                    Ok(Wordlist::from_words(vec![username.clone()]))
+                }
             },
         }
     }
 
-    // 读取密码列表
-    fn read_passwords(&self) -> Result<Vec<String>, RtspError> {
+    // 密码字典，文件只统计单词数量，单词在迭代时读取
+    fn read_passwords(&self) -> Result<Wordlist, RtspError> {
         match &self.source {
             CredentialSource::UsernameAndPassword(_, password_source) => match password_source {
-                PasswordSource::FilePath(file_path) => read_lines_from_file(file_path),
-                PasswordSource::PasswordString(password) => Ok(vec![password.clone()]),
+                PasswordSource::FilePath(file_path) => Wordlist::from_file(file_path),
+                PasswordSource::PasswordString(password) => {
+                    Ok(Wordlist::from_words(vec![password.clone()]))
+                }
             },
         }
     }
@@ -106,7 +111,7 @@ impl CredentialReader<CredentialSource> {
         let usernames = self.read_usernames()?;
         let passwords = self.read_passwords()?;
 
-        Ok(CredentialIterator::new(usernames, passwords))
+        Ok(CredentialIterator::from_wordlists(usernames, passwords))
     }
 }
 
@@ -1,22 +1,42 @@
 use tokio::net::TcpStream;
 
 use crate::errors::errors::RtspError;
+use crate::iterator::ip_port_parser::IpPortRange;
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::hash::{Hash, Hasher};
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::vec::Vec;
 
+// 未指定端口时使用的RTSP默认端口
+pub const DEFAULT_RTSP_PORT: u16 = 554;
+// HTTP隧道目标未指定端口时使用的默认端口
+pub const DEFAULT_HTTP_TUNNEL_PORT: u16 = 80;
+
 // 存储IP地址和端口信息的结构体
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct IpPortAddr {
     pub ip: IpAddr,
     pub port: u16,
+    // 目标为域名时的原始域名，URL和Host头中使用域名，连接使用ip
+    pub hostname: Option<Arc<str>>,
+    // 通过RTSP over HTTP隧道连接，目标带http://前缀时设置
+    pub http_tunnel: bool,
 }
 
+// IPv6地址带方括号，如[fe80::1]:554；域名目标带上连接的地址，如camera.local:554 (10.0.0.5)；
+// HTTP隧道目标带http://前缀
 impl Display for IpPortAddr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.ip, self.port)
+        if self.http_tunnel {
+            write!(f, "http://")?;
+        }
+        match &self.hostname {
+            Some(_) => write!(f, "{} ({})", self.authority(), self.ip),
+            None => write!(f, "{}", self.socket_addr()),
+        }
     }
 }
 
@@ -24,47 +44,66 @@ impl Hash for IpPortAddr {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.ip.hash(state);
         self.port.hash(state);
+        self.hostname.hash(state);
+        self.http_tunnel.hash(state);
     }
 }
 
 impl IpPortAddr {
     const TCP_TIMEOUT: u64 = 5;
     pub fn new(ip: IpAddr, port: u16) -> Self {
-        Self { ip, port }
-
+        Self {
+            ip,
+            port,
+            hostname: None,
+            http_tunnel: false,
+        }
     }
-    pub fn from_str(ip_str: &str) -> Result<Self, RtspError> {
-        let parts: Vec<&str> = ip_str.split(':').collect();
-        if parts.len() != 2 {   
-            return Err(RtspError::InvalidIpAddress(ip_str.to_string()));
+    // 设置目标的域名
+    pub fn with_hostname(mut self, hostname: &str) -> Self {
+        self.hostname = Some(Arc::from(hostname));
+        self
+    }
+    // 设置目标是否通过HTTP隧道连接
+    pub fn with_http_tunnel(mut self, http_tunnel: bool) -> Self {
+        self.http_tunnel = http_tunnel;
+        self
+    }
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port)
+    }
+    // URL和Host头中使用的主机部分，域名目标使用域名，IPv6地址带方括号
+    pub fn host(&self) -> String {
+        match (&self.hostname, self.ip) {
+            (Some(hostname), _) => hostname.to_string(),
+            (None, IpAddr::V4(ip)) => ip.to_string(),
+            (None, IpAddr::V6(ip)) => format!("[{}]", ip),
         }
-
-        let ip = parts[0].parse().map_err(|_| RtspError::InvalidIpAddress(ip_str.to_string()))?;
-        let port = parts[1].parse().map_err(|_| RtspError::InvalidIpAddress(ip_str.to_string()))?;
-        Ok(Self { ip, port })
     }
-
-    pub async fn try_connect(&self) -> bool {
-
-        let ip_str = self.ip.to_string();   
-        let port = self.port;
-        let addr = format!("{}:{}", ip_str, port);
-        match tokio::time::timeout(
-            std::time::Duration::from_secs(Self::TCP_TIMEOUT), 
-            tokio::net::TcpStream::connect(&addr)
-        ).await {
-            Ok(_) => true,
-            Err(_) => false,
+    // URL中的主机和端口，如camera.local:554、[fe80::1]:554
+    pub fn authority(&self) -> String {
+        format!("{}:{}", self.host(), self.port)
+    }
+    // 目标上的流地址，用于输出结果，域名目标带上连接的地址
+    pub fn location(&self, path: &str) -> String {
+        match &self.hostname {
+            Some(_) => format!("{}{} ({})", self.authority(), path, self.ip),
+            None => format!("{}{}", self.authority(), path),
         }
     }
+    pub async fn try_connect(&self) -> bool {
+        let addr = self.socket_addr();
+        tokio::time::timeout(
+            std::time::Duration::from_secs(Self::TCP_TIMEOUT),
+            tokio::net::TcpStream::connect(addr)
+        ).await.is_ok()
+    }
     pub async fn connect(&self) -> Result<TcpStream, RtspError> {
-        let ip_str = self.ip.to_string();
-        let port = self.port;
-        let addr = format!("{}:{}", ip_str, port);
-        
+        let addr = self.socket_addr();
+
         match tokio::time::timeout(
             std::time::Duration::from_secs(Self::TCP_TIMEOUT), 
-            tokio::net::TcpStream::connect(&addr)
+            tokio::net::TcpStream::connect(addr)
         ).await {
             Ok(stream) => match stream {
                 Ok(stream) => Ok(stream),
@@ -75,31 +114,221 @@ impl IpPortAddr {
         }
     }
 }
-// IP地址迭代器
+impl FromStr for IpPortAddr {
+    type Err = RtspError;
+
+    // 格式为ip:port，IPv6地址需要带方括号，如[fe80::1]:554
+    fn from_str(ip_str: &str) -> Result<Self, Self::Err> {
+        let addr: SocketAddr = ip_str
+            .trim()
+            .parse()
+            .map_err(|_| RtspError::InvalidIpAddress(ip_str.to_string()))?;
+        Ok(Self::new(addr.ip(), addr.port()))
+    }
+}
+
+// 未指定端口时的默认端口，HTTP隧道目标使用80
+pub fn default_port(http_tunnel: bool) -> u16 {
+    if http_tunnel {
+        DEFAULT_HTTP_TUNNEL_PORT
+    } else {
+        DEFAULT_RTSP_PORT
+    }
+}
+
+// 扫描目标：单个地址，或按需展开的IP端口范围（先按IP再按端口）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TargetSpec {
+    Addr(IpPortAddr),
+    Range(IpPortRange),
+}
+
+impl TargetSpec {
+    // 从解析出的范围创建目标，未指定端口时使用默认端口，只有一个地址时作为单个地址
+    pub fn from_range(mut range: IpPortRange) -> Self {
+        if range.ports.is_empty() {
+            range.ports.push(default_port(range.http_tunnel));
+        }
+        match (range.ips.len(), range.ports.as_slice()) {
+            (1, [port]) => match range.ips.get(0) {
+                Some(ip) => TargetSpec::Addr(
+                    IpPortAddr::new(ip, *port).with_http_tunnel(range.http_tunnel),
+                ),
+                None => TargetSpec::Range(range),
+            },
+            _ => TargetSpec::Range(range),
+        }
+    }
+
+    // 展开后的目标数量
+    pub fn len(&self) -> usize {
+        match self {
+            TargetSpec::Addr(_) => 1,
+            TargetSpec::Range(range) => range.ips.len() * range.ports.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 第index个目标
+    pub fn get(&self, index: usize) -> Option<IpPortAddr> {
+        match self {
+            TargetSpec::Addr(addr) => (index == 0).then(|| addr.clone()),
+            TargetSpec::Range(range) => {
+                let ports = range.ports.len();
+                let ip = range.ips.get(index.checked_div(ports)?)?;
+                let addr = IpPortAddr::new(ip, range.ports[index % ports]);
+                Some(addr.with_http_tunnel(range.http_tunnel))
+            }
+        }
+    }
+
+    pub fn contains(&self, addr: &IpPortAddr) -> bool {
+        match self {
+            TargetSpec::Addr(target) => target == addr,
+            TargetSpec::Range(range) => {
+                addr.hostname.is_none()
+                    && addr.http_tunnel == range.http_tunnel
+                    && range.ports.contains(&addr.port)
+                    && range.ips.contains(&addr.ip)
+            }
+        }
+    }
+
+    // 两个目标是否可能有重复的地址，范围之间按端口和地址边界判断
+    fn may_overlap(&self, other: &TargetSpec) -> bool {
+        match (self, other) {
+            (TargetSpec::Addr(addr), spec) | (spec, TargetSpec::Addr(addr)) => spec.contains(addr),
+            (TargetSpec::Range(a), TargetSpec::Range(b)) => {
+                a.http_tunnel == b.http_tunnel
+                    && a.ports.iter().any(|port| b.ports.contains(port))
+                    && a.ips.may_overlap(&b.ips)
+            }
+        }
+    }
+}
+
+// 去重后的目标列表，迭代器的克隆之间共享
+#[derive(Debug)]
+struct TargetList {
+    specs: Vec<TargetSpec>,
+    // 单个地址的位置
+    addrs: HashMap<IpPortAddr, usize>,
+    // 范围目标的位置
+    ranges: Vec<usize>,
+    // 目标数量的上限，范围之间有重叠时迭代时跳过的重复目标不计算在内
+    len: usize,
+    // 范围之间没有重叠，len是精确的目标数量
+    exact: bool,
+}
+
+impl TargetList {
+    // 创建时丢弃重复的或已包含在之前范围中的单个地址，目标数量为剩余规范长度的和，
+    // 减去包含在之后范围中的单个地址
+    fn new(specs: Vec<TargetSpec>) -> Self {
+        let mut kept: Vec<TargetSpec> = Vec::with_capacity(specs.len());
+        let mut addrs = HashMap::new();
+        let mut ranges = Vec::new();
+        for spec in specs {
+            match &spec {
+                TargetSpec::Addr(addr) => {
+                    let seen = addrs.contains_key(addr)
+                        || ranges.iter().any(|&range: &usize| kept[range].contains(addr));
+                    if seen {
+                        continue;
+                    }
+                    addrs.insert(addr.clone(), kept.len());
+                }
+                TargetSpec::Range(_) => ranges.push(kept.len()),
+            }
+            kept.push(spec);
+        }
+
+        let later_addrs = addrs
+            .iter()
+            .filter(|&(addr, &index)| {
+                ranges
+                    .iter()
+                    .any(|&range| range > index && kept[range].contains(addr))
+            })
+            .count();
+        let len = kept.iter().map(TargetSpec::len).sum::<usize>() - later_addrs;
+        let exact = ranges.iter().enumerate().all(|(i, &a)| {
+            ranges[..i]
+                .iter()
+                .all(|&b| !kept[a].may_overlap(&kept[b]))
+        });
+        TargetList {
+            specs: kept,
+            addrs,
+            ranges,
+            len,
+            exact,
+        }
+    }
+
+    // 目标是否已经在index之前的规范中出现
+    fn seen_before(&self, index: usize, addr: &IpPortAddr) -> bool {
+        self.addrs.get(addr).is_some_and(|&first| first < index)
+            || self
+                .ranges
+                .iter()
+                .take_while(|&&range| range < index)
+                .any(|&range| self.specs[range].contains(addr))
+    }
+}
+
+// IP地址迭代器，按需展开目标范围并跳过重复的目标，克隆时共享目标列表
 #[derive(Clone)]
 pub struct IpIterator {
-    ip_ports: Vec<IpPortAddr>,
-    index: usize,
+    targets: Arc<TargetList>,
+    spec: usize,
+    offset: usize,
+    remaining: usize,
 }
 
 impl Iterator for IpIterator {
     type Item = IpPortAddr;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.ip_ports.len() {
-            return None;
+        while let Some(spec) = self.targets.specs.get(self.spec) {
+            let Some(addr) = spec.get(self.offset) else {
+                self.spec += 1;
+                self.offset = 0;
+                continue;
+            };
+            self.offset += 1;
+            if !self.targets.seen_before(self.spec, &addr) {
+                self.remaining = self.remaining.saturating_sub(1);
+                return Some(addr);
+            }
         }
+        None
+    }
 
-        let current_ip_port = self.ip_ports[self.index].clone();
-        self.index += 1;
-
-        Some(current_ip_port)
+    // 范围之间有重叠时只能给出上限
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let lower = if self.targets.exact { self.remaining } else { 0 };
+        (lower, Some(self.remaining))
     }
 }
 
 impl IpIterator {
     pub fn new(ip_ports: Vec<IpPortAddr>) -> Self {
-        IpIterator { ip_ports, index: 0 }
+        Self::from_specs(ip_ports.into_iter().map(TargetSpec::Addr).collect())
+    }
+
+    // 从目标规范创建迭代器，重复的目标只保留第一次出现的
+    pub fn from_specs(specs: Vec<TargetSpec>) -> Self {
+        let targets = TargetList::new(specs);
+        IpIterator {
+            remaining: targets.len,
+            targets: Arc::new(targets),
+            spec: 0,
+            offset: 0,
+        }
     }
 
     // 从字符串列表解析IP地址和端口
@@ -107,21 +336,11 @@ impl IpIterator {
         let mut ip_ports = Vec::new();
 
         for ip_str in ip_strings {
-            // 处理带端口的IP地址
-            let parts: Vec<&str> = ip_str.trim().split(':').collect();
-            if parts.len() < 1 || parts.len() > 2 {
-                return Err(RtspError::InvalidIpAddress(format!(
-                    "Invalid IP address format: {}",
-                    ip_str
-                )));
-            }
-
-            let ip_part = parts[0].trim();
-            let port_part = if parts.len() == 2 {
-                parts[1].trim()
-            } else {
-                "554"
-            };
+            let (ip_part, port_part) = Self::split_ip_port(ip_str.trim()).ok_or_else(|| {
+                RtspError::InvalidIpAddress(format!("Invalid IP address format: {}", ip_str))
+            })?;
+            let default_port = DEFAULT_RTSP_PORT.to_string();
+            let port_part = port_part.unwrap_or(&default_port);
 
             // 解析IP地址
             let ip = match IpAddr::from_str(ip_part) {
@@ -145,9 +364,29 @@ impl IpIterator {
                 }
             };
 
-            ip_ports.push(IpPortAddr { ip, port });
+            ip_ports.push(IpPortAddr::new(ip, port));
         }
 
         Ok(IpIterator::new(ip_ports))
     }
+
+    // 拆分IP和端口：1.2.3.4、1.2.3.4:554、fe80::1、[fe80::1]、[fe80::1]:554
+    fn split_ip_port(ip_str: &str) -> Option<(&str, Option<&str>)> {
+        if let Some(rest) = ip_str.strip_prefix('[') {
+            let (ip, rest) = rest.split_once(']')?;
+            return match rest.strip_prefix(':') {
+                Some(port) => Some((ip, Some(port.trim()))),
+                None if rest.is_empty() => Some((ip, None)),
+                None => None,
+            };
+        }
+        match ip_str.matches(':').count() {
+            0 => Some((ip_str, None)),
+            1 => ip_str
+                .split_once(':')
+                .map(|(ip, port)| (ip.trim(), Some(port.trim()))),
+            // 不带方括号的IPv6地址，没有端口
+            _ => Some((ip_str, None)),
+        }
+    }
 }
@@ -1,10 +1,10 @@
-use std::{
-    collections::HashSet,
-    net::{IpAddr, Ipv4Addr},
-};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use crate::errors::errors::{ParseError, Result};
 
+/// IPv6 CIDR的最小前缀长度，即最多展开65536个地址
+pub const MIN_IPV6_CIDR_PREFIX: u8 = 112;
+
 /// 表示IP地址中的一个段
 #[derive(Debug, Clone)]
 enum IpSegment {
@@ -45,6 +45,110 @@ pub struct IpPort {
     pub ports: Vec<u16>,
 }
 
+/// IP地址集合，按下标计算地址，不展开到内存
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpSet {
+    /// IPv4地址：四个段取值（已去重）的笛卡尔积，host_bits不为0时每个组合是一个网络地址，
+    /// 展开为网络内的所有地址
+    V4 { octets: [Vec<u8>; 4], host_bits: u32 },
+    /// IPv6地址：从网络地址开始的连续地址
+    V6 { network: u128, count: usize },
+}
+
+impl IpSet {
+    /// 集合中的地址数量
+    pub fn len(&self) -> usize {
+        match self {
+            IpSet::V4 { octets, host_bits } => {
+                octets.iter().map(Vec::len).product::<usize>() << host_bits
+            }
+            IpSet::V6 { count, .. } => *count,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 第index个地址，顺序与逐段展开的顺序一致（最后一段变化最快，网络内的地址连续）
+    pub fn get(&self, index: usize) -> Option<IpAddr> {
+        if index >= self.len() {
+            return None;
+        }
+        match self {
+            IpSet::V4 { octets, host_bits } => {
+                let host = (index & ((1 << host_bits) - 1)) as u32;
+                let mut rest = index >> host_bits;
+                let mut network = [0u8; 4];
+                for (octet, values) in network.iter_mut().zip(octets).rev() {
+                    *octet = values[rest % values.len()];
+                    rest /= values.len();
+                }
+                Some(IpAddr::V4(Ipv4Addr::from(u32::from_be_bytes(network) + host)))
+            }
+            IpSet::V6 { network, .. } => Some(IpAddr::V6(Ipv6Addr::from(network + index as u128))),
+        }
+    }
+
+    /// 地址是否在集合中
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self, ip) {
+            (IpSet::V4 { octets, host_bits }, IpAddr::V4(ip)) => {
+                let network = (u32::from(*ip) & ipv4_mask(*host_bits)).to_be_bytes();
+                octets
+                    .iter()
+                    .zip(network)
+                    .all(|(values, octet)| values.contains(&octet))
+            }
+            (IpSet::V6 { network, count }, IpAddr::V6(ip)) => {
+                let ip = u128::from(*ip);
+                ip >= *network && ip - network < *count as u128
+            }
+            _ => false,
+        }
+    }
+
+    /// 两个集合是否可能有相同的地址，按最小和最大地址判断
+    pub fn may_overlap(&self, other: &IpSet) -> bool {
+        match (self.bounds(), other.bounds()) {
+            (Some((min, max)), Some((other_min, other_max))) => {
+                self.is_v4() == other.is_v4() && min <= other_max && other_min <= max
+            }
+            _ => false,
+        }
+    }
+
+    fn is_v4(&self) -> bool {
+        matches!(self, IpSet::V4 { .. })
+    }
+
+    /// 集合中最小和最大的地址
+    fn bounds(&self) -> Option<(u128, u128)> {
+        if self.is_empty() {
+            return None;
+        }
+        match self {
+            IpSet::V4 { octets, host_bits } => {
+                let min = octets.clone().map(|values| values.into_iter().min().unwrap_or(0));
+                let max = octets.clone().map(|values| values.into_iter().max().unwrap_or(0));
+                let min = u32::from_be_bytes(min) as u128;
+                let max = u32::from_be_bytes(max) as u128 + (1 << host_bits) - 1;
+                Some((min, max))
+            }
+            IpSet::V6 { network, count } => Some((*network, network + *count as u128 - 1)),
+        }
+    }
+}
+
+/// IP端口范围：地址集合和端口列表，按需展开
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpPortRange {
+    pub ips: IpSet,
+    pub ports: Vec<u16>,
+    /// 通过RTSP over HTTP隧道连接，目标带http://前缀时设置
+    pub http_tunnel: bool,
+}
+
 /// IP段解析器
 struct IpSegmentParser;
 
@@ -231,93 +335,121 @@ fn expand_port_spec(spec: &PortSpec) -> Vec<u16> {
     }
 }
 
-/// 从IP地址模式生成所有可能的IP地址
-fn generate_ips(pattern: &IpAddrPattern) -> Vec<IpAddr> {
-    // 展开所有段
-    let seg1 = expand_segment(&pattern.segments[0]);
-    let seg2 = expand_segment(&pattern.segments[1]);
-    let seg3 = expand_segment(&pattern.segments[2]);
-    let seg4 = expand_segment(&pattern.segments[3]);
-
-    // 生成所有组合
-    let mut ips = Vec::new();
-    for s1 in &seg1 {
-        for s2 in &seg2 {
-            for s3 in &seg3 {
-                for s4 in &seg4 {
-                    ips.push(IpAddr::V4(Ipv4Addr::new(*s1, *s2, *s3, *s4)));
-                }
-            }
-        }
-    }
+/// IPv4网络掩码，host_bits为主机位数
+fn ipv4_mask(host_bits: u32) -> u32 {
+    u32::MAX.checked_shl(host_bits).unwrap_or(0)
+}
+
+/// 段取值去重，保留第一次出现的顺序
+fn dedup_octets(values: impl IntoIterator<Item = u8>) -> Vec<u8> {
+    let mut seen = [false; 256];
+    values
+        .into_iter()
+        .filter(|value| !std::mem::replace(&mut seen[*value as usize], true))
+        .collect()
+}
 
-    ips
+/// 端口去重，保留第一次出现的顺序，使用65536位的位图记录已出现的端口
+fn dedup_ports(ports: Vec<u16>) -> Vec<u16> {
+    let mut seen = [0u64; 1024];
+    ports
+        .into_iter()
+        .filter(|port| {
+            let (word, bit) = (*port as usize / 64, 1u64 << (port % 64));
+            let first = seen[word] & bit == 0;
+            seen[word] |= bit;
+            first
+        })
+        .collect()
 }
 
-/// 应用CIDR掩码过滤IP地址或生成CIDR范围内的所有IP
-fn apply_cidr(ips: Vec<IpAddr>, cidr: u8) -> Result<Vec<IpAddr>> {
-    if ips.is_empty() {
-        return Ok(ips);
+/// 从IP地址模式生成地址集合，带CIDR时每个段先应用掩码，得到网络地址的组合
+fn ipv4_set(pattern: &IpAddrPattern) -> IpSet {
+    // 地址按需计算，任意长度的前缀都不会展开到内存
+    let host_bits = match pattern.cidr {
+        Some(cidr) => 32 - cidr as u32,
+        None => 0,
+    };
+    let mask = ipv4_mask(host_bits).to_be_bytes();
+    let mut octets: [Vec<u8>; 4] = Default::default();
+    for (i, segment) in pattern.segments.iter().enumerate() {
+        octets[i] = dedup_octets(expand_segment(segment).into_iter().map(|value| value & mask[i]));
     }
+    IpSet::V4 { octets, host_bits }
+}
 
-    // 检查所有IP是否为IPv4
-    for ip in &ips {
-        if !ip.is_ipv4() {
-            return Err(ParseError::InvalidIpFormat(
-                "Only IPv4 addresses are supported with CIDR".to_string(),
-            ));
-        }
+/// IPv6 CIDR范围内的地址集合
+fn ipv6_set(ip: Ipv6Addr, prefix: u8) -> Result<IpSet> {
+    if prefix > 128 {
+        return Err(ParseError::InvalidCidrValue(prefix.to_string()));
+    }
+    if prefix < MIN_IPV6_CIDR_PREFIX {
+        return Err(ParseError::Ipv6CidrTooLarge(prefix));
     }
+    let host_bits = 128 - prefix as u32;
+    Ok(IpSet::V6 {
+        network: u128::from(ip) & (u128::MAX << host_bits),
+        count: 1 << host_bits,
+    })
+}
 
-    assert!(
-        cidr >= 16,
-        "the cidr of ipv4 should greater than 16 cause the ipv4 address space."
-    );
-    // 创建CIDR掩码
-    let mask = if cidr == 0 {
-        0
-    } else {
-        u32::MAX << (32 - cidr)
+/// 解析IPv6地址，支持CIDR，带端口时地址需要放在方括号中
+/// 例如，"fe80::1"、"fe80::/120"、"[fe80::1]:554"、"[fe80::/120]:{80,554}"
+fn parse_ipv6_range(input: &str) -> Result<IpPortRange> {
+    // 1. 拆分方括号中的地址和端口部分
+    let (ip_part, port_part) = match input.strip_prefix('[') {
+        Some(rest) => {
+            let (ip_part, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| ParseError::InvalidIpPortFormat(input.to_string()))?;
+            let port_part = match rest {
+                "" => "",
+                _ => rest
+                    .strip_prefix(':')
+                    .ok_or_else(|| ParseError::InvalidIpPortFormat(input.to_string()))?,
+            };
+            (ip_part, port_part)
+        }
+        None => (input, ""),
     };
 
-    let mut network_addresses = HashSet::new();
-    for ip in ips {
-        // 获取第一个IP的网络地址
-        let first_ip = if let IpAddr::V4(ipv4) = ip {
-            ipv4
-        } else {
-            // 这里理论上不会发生，因为我们已经检查了所有IP都是IPv4
-            return Err(ParseError::InvalidIpFormat(
-                "Only IPv4 addresses are supported with CIDR".to_string(),
-            ));
-        };
-        let network_addr = u32::from(first_ip) & mask;
-        network_addresses.insert(network_addr);
-    }
-    // println!("{:?}", &network_addresses);
-
-    let mut cidr_ips_collection = Vec::new();
-    for network_addr in network_addresses {
-        // 如果输入只有一个IP，我们生成整个CIDR范围的IP
-
-        // 计算主机数量
-        let num_hosts = 1 << (32 - cidr);
-        let mut cidr_ips = Vec::with_capacity(num_hosts as usize);
-        for i in 0..num_hosts {
-            let ip_val = network_addr + i;
-            let ipv4 = Ipv4Addr::new(
-                ((ip_val >> 24) & 0xFF) as u8,
-                ((ip_val >> 16) & 0xFF) as u8,
-                ((ip_val >> 8) & 0xFF) as u8,
-                (ip_val & 0xFF) as u8,
-            );
-            cidr_ips.push(IpAddr::V4(ipv4));
+    // 2. 解析地址和CIDR
+    let (address, cidr) = match ip_part.split_once('/') {
+        Some((address, cidr)) => {
+            let cidr = cidr
+                .parse::<u8>()
+                .map_err(|_| ParseError::InvalidCidrValue(cidr.to_string()))?;
+            (address, Some(cidr))
         }
-        cidr_ips_collection.extend(cidr_ips);
-    }
-    // println!("{:?}", &cidr_ips_collection);
+        None => (ip_part, None),
+    };
+    let ip = address
+        .parse::<Ipv6Addr>()
+        .map_err(|_| ParseError::InvalidIpFormat(input.to_string()))?;
+    let ips = match cidr {
+        Some(prefix) => ipv6_set(ip, prefix)?,
+        None => IpSet::V6 {
+            network: u128::from(ip),
+            count: 1,
+        },
+    };
 
-    Ok(cidr_ips_collection)
+    // 3. 生成端口列表
+    let ports = if port_part.is_empty() {
+        vec![]
+    } else {
+        dedup_ports(expand_port_spec(&PortParser::parse(port_part)?))
+    };
+    Ok(IpPortRange {
+        ips,
+        ports,
+        http_tunnel: false,
+    })
+}
+
+/// 是否为IPv6地址：带方括号，或者包含多个冒号
+fn is_ipv6_input(input: &str) -> bool {
+    input.starts_with('[') || input.matches(':').count() > 1
 }
 
 /// 拆分IP部分和端口部分
@@ -333,45 +465,65 @@ fn split_ip_port(input: &str) -> Result<(&str, &str)> {
     }
 }
 
-/// 解析IP端口字符串
-/// 例如，"10.{1-2,{3-4}}.{{5-6},7}.8:{80,443}",
-pub fn parse_ip_port(input: &str) -> Result<Vec<IpPort>> {
-    // 1. 拆分IP部分和端口部分
-    let (ip_part, port_part) = split_ip_port(input)?;
+/// 解析端口规范，例如"554"、"{554,8554}"、"{8000-8010}"
+pub fn parse_ports(input: &str) -> Result<Vec<u16>> {
+    let spec = PortParser::parse(input.trim())?;
+    Ok(dedup_ports(expand_port_spec(&spec)))
+}
 
-    // 2. 解析IP部分（包含CIDR）
-    let ip_pattern = IpAddrPatternParser::parse(ip_part)?;
+/// 拆分目标的http://前缀，带前缀的目标通过RTSP over HTTP隧道连接，例如"http://10.0.0.1:80"
+pub fn split_http_tunnel(input: &str) -> (&str, bool) {
+    match input.get(..7) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("http://") => (&input[7..], true),
+        _ => (input, false),
+    }
+}
 
-    // 3. 解析端口部分
-    let port_spec = if !port_part.is_empty() {
-        Some(PortParser::parse(port_part)?)
+/// 解析IP端口字符串，返回按需展开的地址集合和端口列表
+/// 例如，"10.{1-2,{3-4}}.{{5-6},7}.8:{80,443}"、"http://10.0.0.{1-5}:80"
+pub fn parse_ip_range(input: &str) -> Result<IpPortRange> {
+    let (input, http_tunnel) = split_http_tunnel(input);
+    let mut range = if is_ipv6_input(input) {
+        parse_ipv6_range(input)?
     } else {
-        None
+        parse_ipv4_range(input)?
     };
+    range.http_tunnel = http_tunnel;
+    Ok(range)
+}
 
-    // 4. 生成IP地址
-    let mut ips = generate_ips(&ip_pattern);
-    // println!("{:?}", ips);
+/// 解析IPv4地址，支持花括号展开、CIDR和端口
+fn parse_ipv4_range(input: &str) -> Result<IpPortRange> {
+    // 1. 拆分IP部分和端口部分
+    let (ip_part, port_part) = split_ip_port(input)?;
 
-    // 5. 应用CIDR掩码（如果有）
-    if let Some(cidr) = ip_pattern.cidr {
-        ips = apply_cidr(ips, cidr)?;
-    }
-    // println!("{:?}", &ips.len());
+    // 2. 解析IP部分（包含CIDR）
+    let ip_pattern = IpAddrPatternParser::parse(ip_part)?;
 
-    // 6. 生成端口列表
-    let ports = if let Some(spec) = port_spec {
-        expand_port_spec(&spec)
+    // 3. 解析端口部分
+    let ports = if !port_part.is_empty() {
+        dedup_ports(expand_port_spec(&PortParser::parse(port_part)?))
     } else {
         vec![]
     };
 
-    // 7. 组合IP和端口
-    Ok(ips
-        .into_iter()
+    // 4. 生成地址集合（应用CIDR掩码）
+    Ok(IpPortRange {
+        ips: ipv4_set(&ip_pattern),
+        ports,
+        http_tunnel: false,
+    })
+}
+
+/// 解析IP端口字符串并展开所有IP
+/// 例如，"10.{1-2,{3-4}}.{{5-6},7}.8:{80,443}",
+pub fn parse_ip_port(input: &str) -> Result<Vec<IpPort>> {
+    let range = parse_ip_range(input)?;
+    Ok((0..range.ips.len())
+        .filter_map(|index| range.ips.get(index))
         .map(|ip| IpPort {
             ip,
-            ports: ports.clone(),
+            ports: range.ports.clone(),
         })
         .collect())
 }
@@ -1,8 +1,9 @@
 use crate::errors::errors::RtspError;
-use crate::iterator::ip_iterator::IpIterator;
+use crate::iterator::ip_iterator::{IpIterator, IpPortAddr, TargetSpec, default_port};
+use crate::iterator::ip_port_parser::{parse_ip_range, parse_ports, split_http_tunnel};
+use crate::iterator::resolver::DNS_CACHE;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::net::{ToSocketAddrs};
 use std::vec::Vec;
 
 // 定义IP数据源类型
@@ -33,83 +34,69 @@ impl IpReader<IpSource> {
         }
     }
 
-    fn parse_ips(&self, ips: Vec<String>) -> Result<Vec<String>, RtspError> {
-        // 使用ip_port_parser解析IP地址（支持带端口格式、CIDR和花括号展开）
-        let mut parsed_ips = Vec::new();
+    async fn parse_ips(&self, ips: Vec<String>) -> Result<Vec<TargetSpec>, RtspError> {
+        // 使用ip_port_parser解析IP地址（支持带端口格式、CIDR和花括号展开），范围在迭代时才展开
+        let mut specs = Vec::new();
         for ip in &ips {
-            // 首先尝试使用ip_port_parser解析
-            // println!("{}", &ip);
-
-            match super::ip_port_parser::parse_ip_port(ip) {
-                Ok(ip_ports) => {
-                    // 解析成功，转换为字符串形式
-                    // println!("{:?}", ip_ports);
-
-                    for ip_port in ip_ports {
-                        if ip_port.ports.is_empty() {
-                            parsed_ips.push(ip_port.ip.to_string());
-                        } else {
-                            for port in &ip_port.ports {
-                                parsed_ips.push(format!("{}:{}", ip_port.ip, port));
-                            }
-                        }
-                    }
-                }
+            match parse_ip_range(ip) {
+                Ok(range) => specs.push(TargetSpec::from_range(range)),
                 Err(parse_err) => {
-                    // 解析失败，尝试作为域名处理
-                    // 提取IP部分和端口部分
-                    let (ip_without_port, port) = if ip.contains(':') {
-                        let parts: Vec<&str> = ip.splitn(2, ':').collect();
-                        (parts[0].trim(), parts[1].trim())
-                    } else {
-                        (ip.trim(), "")
+                    // 解析失败，作为域名处理：保留域名，解析到的每个地址都作为单独的目标
+                    let (target, http_tunnel) = split_http_tunnel(ip);
+                    let (hostname, ports) = Self::split_hostname(target).ok_or_else(|| {
+                        RtspError::InvalidIpAddress(format!("{}. Parsing error: {}", ip, parse_err))
+                    })?;
+                    let ports = match ports {
+                        Some(ports) => parse_ports(ports).map_err(|e| {
+                            RtspError::InvalidIpAddress(format!("{}. Invalid port: {}", ip, e))
+                        })?,
+                        None => vec![default_port(http_tunnel)],
                     };
-
-                    match (ip_without_port, 0).to_socket_addrs() {
-                        Ok(mut addrs) => {
-                            if let Some(addr) = addrs.next() {
-                                let ip_with_port = if !port.is_empty() {
-                                    format!("{}:{}", addr.ip().to_string(), port)
-                                } else {
-                                    addr.ip().to_string()
-                                };
-                                parsed_ips.push(ip_with_port);
-                            } else {
-                                return Err(RtspError::InvalidIpAddress(format!(
-                                    "Invalid IP address: {}. Error: No address found for domain and parsing failed: {:?}",
-                                    ip, parse_err
-                                )));
-                            }
-                        }
-                        Err(dns_err) => {
-                            return Err(RtspError::InvalidIpAddress(format!(
-                                "Invalid IP address: {}. Parsing error: {:?}, DNS error: {:?}",
-                                ip, parse_err, dns_err
-                            )));
+                    let addrs = DNS_CACHE.resolve(hostname).await.map_err(|dns_err| {
+                        RtspError::InvalidIpAddress(format!(
+                            "{}. Parsing error: {}, DNS error: {}",
+                            ip, parse_err, dns_err
+                        ))
+                    })?;
+                    for port in ports {
+                        for addr in &addrs {
+                            let addr = IpPortAddr::new(*addr, port)
+                                .with_hostname(hostname)
+                                .with_http_tunnel(http_tunnel);
+                            specs.push(TargetSpec::Addr(addr));
                         }
                     }
                 }
             }
         }
-        let mut unique_ips = Vec::new();
-        for ip in parsed_ips {
-            if !unique_ips.contains(&ip) {
-                unique_ips.push(ip);
-            }
-        }
-        Ok(unique_ips)
+        // 重复的目标由IpIterator跳过
+        Ok(specs)
+    }
+
+    // 拆分域名和端口部分，如camera.local、camera.local:8554、camera.local:{554,8554}
+    fn split_hostname(input: &str) -> Option<(&str, Option<&str>)> {
+        let input = input.trim();
+        let (hostname, ports) = match input.split_once(':') {
+            Some((hostname, ports)) => (hostname.trim(), Some(ports.trim())),
+            None => (input, None),
+        };
+        let valid = !hostname.is_empty()
+            && hostname
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'));
+        valid.then_some((hostname, ports))
     }
 
     // 读取IP地址列表
-    fn read_ips(&self) -> Result<Vec<String>, RtspError> {
+    async fn read_ips(&self) -> Result<Vec<TargetSpec>, RtspError> {
         let ips = match &self.source {
             IpSource::FilePath(file_path) => {
-                let file = File::open(file_path).map_err(|e| RtspError::IoError(e))?;
+                let file = File::open(file_path).map_err(RtspError::IoError)?;
                 let reader = BufReader::new(file);
                 let mut ips = Vec::new();
 
                 for line in reader.lines() {
-                    let line = line.map_err(|e| RtspError::IoError(e))?;
+                    let line = line.map_err(RtspError::IoError)?;
                     let trimmed_line = line.trim();
                     if trimmed_line.is_empty() {
                         continue;
@@ -121,20 +108,16 @@ impl IpReader<IpSource> {
             }
             IpSource::IpString(ip_string) => {
                 let trimmed_line = ip_string.trim();
-                let mut ips = Vec::new();
-
-                ips.push(trimmed_line.to_string());
-                ips
+                vec![trimmed_line.to_string()]
             }
         };
 
-        self.parse_ips(ips)
+        self.parse_ips(ips).await
     }
 
-    // 创建IP地址迭代器
-    pub fn into_iterator(&self) -> Result<IpIterator, RtspError> {
-        let ip_strings = self.read_ips()?;
-        IpIterator::from_strings(ip_strings)
+    // 创建IP地址迭代器，域名目标异步解析
+    pub async fn into_iterator(&self) -> Result<IpIterator, RtspError> {
+        Ok(IpIterator::from_specs(self.read_ips().await?))
     }
 }
 
@@ -3,3 +3,5 @@ pub mod credential_reader;
 pub mod ip_iterator;
 pub mod ip_port_parser;
 pub mod ip_reader;
+pub mod resolver;
+pub mod wordlist;
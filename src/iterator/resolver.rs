@@ -0,0 +1,63 @@
+use crate::errors::errors::RtspError;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+// 域名解析结果缓存，同一域名的多个端口和多次请求只查询一次
+pub struct DnsCache {
+    entries: Mutex<HashMap<String, Vec<IpAddr>>>,
+}
+
+impl DnsCache {
+    pub fn new() -> Self {
+        DnsCache {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // 异步解析域名的所有A/AAAA地址，保持解析器返回的顺序并去重
+    // 解析失败的结果不缓存
+    pub async fn resolve(&self, hostname: &str) -> Result<Vec<IpAddr>, RtspError> {
+        let key = hostname.to_ascii_lowercase();
+        if let Some(addrs) = self.entries.lock().unwrap().get(&key) {
+            return Ok(addrs.clone());
+        }
+
+        let resolved = tokio::net::lookup_host((hostname, 0))
+            .await
+            .map_err(|e| RtspError::ResolveError(format!("{}: {}", hostname, e)))?;
+        let mut addrs = Vec::new();
+        for addr in resolved {
+            if !addrs.contains(&addr.ip()) {
+                addrs.push(addr.ip());
+            }
+        }
+        if addrs.is_empty() {
+            return Err(RtspError::ResolveError(format!(
+                "{}: no address found",
+                hostname
+            )));
+        }
+        log::debug!("Resolved {} to {:?}", hostname, addrs);
+
+        self.entries.lock().unwrap().insert(key, addrs.clone());
+        Ok(addrs)
+    }
+
+    // 清空缓存
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl Default for DnsCache {
+    fn default() -> Self {
+        DnsCache::new()
+    }
+}
+
+// 创建全局域名解析缓存
+lazy_static! {
+    pub static ref DNS_CACHE: DnsCache = DnsCache::default();
+}
@@ -0,0 +1,145 @@
+use crate::errors::errors::RtspError;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// 字典：命令行给出的单词，或字典文件中的非空行。
+// 文件只在创建时完整扫描一次统计单词数量，之后每次遍历都重新打开文件顺序读取，
+// 内存中不保留单词内容
+#[derive(Clone, Debug)]
+pub enum Wordlist {
+    Words(Arc<Vec<String>>),
+    File(Arc<WordlistFile>),
+}
+
+// 字典文件：路径和非空行（去除首尾空白后）的数量
+#[derive(Debug)]
+pub struct WordlistFile {
+    path: PathBuf,
+    len: usize,
+}
+
+impl Wordlist {
+    pub fn from_words(words: Vec<String>) -> Self {
+        Wordlist::Words(Arc::new(words))
+    }
+
+    // 打开字典文件并统计单词数量
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RtspError> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut len = 0;
+        let mut line = String::new();
+        while next_word(&mut reader, &mut line)?.is_some() {
+            len += 1;
+        }
+        Ok(Wordlist::File(Arc::new(WordlistFile {
+            path: path.to_path_buf(),
+            len,
+        })))
+    }
+
+    // 字典中的单词数量
+    pub fn len(&self) -> usize {
+        match self {
+            Wordlist::Words(words) => words.len(),
+            Wordlist::File(file) => file.len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // 从头顺序遍历字典中的单词，文件在这里重新打开
+    pub fn words(&self) -> Result<Words, RtspError> {
+        match self {
+            Wordlist::Words(words) => Ok(Words::List {
+                words: words.clone(),
+                index: 0,
+            }),
+            Wordlist::File(file) => Ok(Words::File {
+                path: file.path.clone(),
+                reader: BufReader::new(File::open(&file.path)?),
+                remaining: file.len,
+                line: String::new(),
+            }),
+        }
+    }
+}
+
+// 字典单词的顺序迭代器。读取文件出错，或文件比统计时短时返回错误，之后不再返回单词
+pub enum Words {
+    List {
+        words: Arc<Vec<String>>,
+        index: usize,
+    },
+    File {
+        path: PathBuf,
+        reader: BufReader<File>,
+        remaining: usize,
+        line: String,
+    },
+}
+
+impl Iterator for Words {
+    type Item = Result<String, RtspError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Words::List { words, index } => {
+                let word = words.get(*index)?.clone();
+                *index += 1;
+                Some(Ok(word))
+            }
+            Words::File {
+                path,
+                reader,
+                remaining,
+                line,
+            } => {
+                if *remaining == 0 {
+                    return None;
+                }
+                let result = match next_word(reader, line) {
+                    Ok(Some(word)) => {
+                        *remaining -= 1;
+                        return Some(Ok(word.to_string()));
+                    }
+                    Ok(None) => Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("wordlist {} was truncated while reading", path.display()),
+                    )),
+                    Err(e) => Err(io::Error::new(
+                        e.kind(),
+                        format!("failed to read wordlist {}: {}", path.display(), e),
+                    )),
+                };
+                *remaining = 0;
+                Some(result.map_err(RtspError::IoError))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self {
+            Words::List { words, index } => words.len().saturating_sub(*index),
+            Words::File { remaining, .. } => *remaining,
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+// 读取下一个非空行，返回去除首尾空白后的单词，文件结束时返回None
+fn next_word<'a>(reader: &mut impl BufRead, line: &'a mut String) -> io::Result<Option<&'a str>> {
+    loop {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            return Ok(Some(line.trim()));
+        }
+    }
+}
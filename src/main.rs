@@ -1,11 +1,9 @@
 use std::error::Error;
 extern crate lazy_static;
 use log::{debug};
-use log4rs;
 use rust_rtsp_bruter::config::config::{load_and_merge_config, load_config_and_handle_cli};
 use rust_rtsp_bruter::rtsp::rtsp_worker::RTSP_WORKER_MANAGER;
 use timing_macro::timing;
-use tokio;
 
 // 主函数
 #[tokio::main]
@@ -1,77 +1,435 @@
 use crate::errors::errors::RtspError;
+use crate::iterator::ip_iterator::IpPortAddr;
+use crate::rtsp::common::RtspResponse;
 use base64::Engine;
+use clap::ValueEnum;
 use md5::{Digest, Md5};
 use rand::Rng;
+use serde::Deserialize;
+use sha2::{Sha256, Sha512_256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 
 // RTSP认证类型
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AuthType {
     None,
-    Basic(()),
+    Basic(BasicAuthInfo),
     Digest(DigestAuthInfo),
 }
 
+impl AuthType {
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            AuthType::None => "None",
+            AuthType::Basic(_) => "Basic",
+            AuthType::Digest(_) => "Digest",
+        }
+    }
+}
+
+// 认证方案选择策略
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthPolicy {
+    // 同时提供时优先使用Digest
+    #[default]
+    PreferDigest,
+    // 同时提供时优先使用Basic
+    PreferBasic,
+    // 先尝试Digest再尝试Basic（即使服务器未声明Basic），用于检查服务器是否仍接受Basic
+    TryBoth,
+}
+
+// Basic认证信息
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct BasicAuthInfo {
+    pub realm: Option<String>,
+}
+
 // Digest认证信息
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
 pub struct DigestAuthInfo {
     pub realm: String,
     pub nonce: String,
     pub qop: Option<String>,
     pub algorithm: Option<String>,
     pub opaque: Option<String>,
+    pub userhash: bool,
+    // stale=true表示凭据可能正确，只是nonce已过期
+    pub stale: bool,
 }
 
-// 认证工具函数
-pub fn parse_auth_challenge(response: &str) -> Result<AuthType, RtspError> {
-    // 查找WWW-Authenticate头
-    for line in response.lines() {
-        if let Some(auth_header) = line.strip_prefix("WWW-Authenticate: ") {
-            let auth_str = auth_header.trim();
-            // 同时存在时，优先使用Digest认证
-
-            if auth_str.starts_with("Digest ") {
-                let digest_info = parse_digest_challenge(&auth_str["Digest ".len()..])?;
-                log::debug!(
-                    "Digest authentication required, realm: {}",
-                    digest_info.realm
-                );
-                return Ok(AuthType::Digest(digest_info));
-            } else if auth_str.starts_with("Basic ") {
-                log::debug!("Basic authentication required");
-                return Ok(AuthType::Basic(()));
+impl DigestAuthInfo {
+    // 挑战中声明的摘要算法，未声明时默认为MD5
+    pub fn digest_algorithm(&self) -> Option<DigestAlgorithm> {
+        match &self.algorithm {
+            Some(name) => DigestAlgorithm::parse(name),
+            None => Some(DigestAlgorithm::Md5),
+        }
+    }
+}
+
+// Digest摘要算法 (RFC 7616)
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DigestAlgorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+    Sha512_256,
+    Sha512_256Sess,
+}
+
+impl DigestAlgorithm {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_uppercase().as_str() {
+            "MD5" => Some(DigestAlgorithm::Md5),
+            "MD5-SESS" => Some(DigestAlgorithm::Md5Sess),
+            "SHA-256" => Some(DigestAlgorithm::Sha256),
+            "SHA-256-SESS" => Some(DigestAlgorithm::Sha256Sess),
+            "SHA-512-256" => Some(DigestAlgorithm::Sha512_256),
+            "SHA-512-256-SESS" => Some(DigestAlgorithm::Sha512_256Sess),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestAlgorithm::Md5 => "MD5",
+            DigestAlgorithm::Md5Sess => "MD5-sess",
+            DigestAlgorithm::Sha256 => "SHA-256",
+            DigestAlgorithm::Sha256Sess => "SHA-256-sess",
+            DigestAlgorithm::Sha512_256 => "SHA-512-256",
+            DigestAlgorithm::Sha512_256Sess => "SHA-512-256-sess",
+        }
+    }
+
+    // 是否为会话算法（HA1中混入nonce和cnonce）
+    pub fn is_session(&self) -> bool {
+        matches!(
+            self,
+            DigestAlgorithm::Md5Sess | DigestAlgorithm::Sha256Sess | DigestAlgorithm::Sha512_256Sess
+        )
+    }
+
+    // 算法强度，用于在多个挑战中选择最强的一个
+    pub fn strength(&self) -> u8 {
+        match self {
+            DigestAlgorithm::Md5 | DigestAlgorithm::Md5Sess => 1,
+            DigestAlgorithm::Sha256 | DigestAlgorithm::Sha256Sess => 2,
+            DigestAlgorithm::Sha512_256 | DigestAlgorithm::Sha512_256Sess => 3,
+        }
+    }
+
+    // 计算十六进制摘要
+    pub fn hash(&self, input: &str) -> String {
+        match self {
+            DigestAlgorithm::Md5 | DigestAlgorithm::Md5Sess => hex_digest::<Md5>(input.as_bytes()),
+            DigestAlgorithm::Sha256 | DigestAlgorithm::Sha256Sess => {
+                hex_digest::<Sha256>(input.as_bytes())
+            }
+            DigestAlgorithm::Sha512_256 | DigestAlgorithm::Sha512_256Sess => {
+                hex_digest::<Sha512_256>(input.as_bytes())
             }
         }
     }
 
-    Ok(AuthType::None)
+    fn hash_bytes(&self, input: &[u8]) -> String {
+        match self {
+            DigestAlgorithm::Md5 | DigestAlgorithm::Md5Sess => hex_digest::<Md5>(input),
+            DigestAlgorithm::Sha256 | DigestAlgorithm::Sha256Sess => hex_digest::<Sha256>(input),
+            DigestAlgorithm::Sha512_256 | DigestAlgorithm::Sha512_256Sess => {
+                hex_digest::<Sha512_256>(input)
+            }
+        }
+    }
 }
 
-// 解析Digest认证挑战
-pub fn parse_digest_challenge(challenge: &str) -> Result<DigestAuthInfo, RtspError> {
-    let mut realm = String::new();
-    let mut nonce = String::new();
-    let mut qop = None;
-    let mut algorithm = None;
-    let mut opaque = None;
-
-    for param in challenge.split(",") {
-        let parts: Vec<&str> = param.trim().splitn(2, "=").collect();
-        if parts.len() != 2 {
+fn hex_digest<D: Digest>(input: &[u8]) -> String {
+    D::digest(input)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// 认证挑战 - 一个认证方案及其参数 (RFC 7235)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AuthChallenge {
+    pub scheme: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl AuthChallenge {
+    // 获取参数值，参数名大小写不敏感
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_scheme(&self, scheme: &str) -> bool {
+        self.scheme.eq_ignore_ascii_case(scheme)
+    }
+}
+
+// 认证头分词器，处理带引号和转义的参数值
+struct ChallengeTokenizer<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ChallengeTokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        ChallengeTokenizer {
+            input: input.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n')) {
+            self.pos += 1;
+        }
+    }
+
+    // 跳过空白和逗号
+    fn skip_separators(&mut self) {
+        while matches!(
+            self.peek(),
+            Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') | Some(b',')
+        ) {
+            self.pos += 1;
+        }
+    }
+
+    // 读取token（方案名或参数名）
+    fn token(&mut self) -> String {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if byte.is_ascii_whitespace() || matches!(byte, b',' | b'=' | b'"') {
+                break;
+            }
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.input[start..self.pos]).to_string()
+    }
+
+    // 读取未加引号的参数值，值中允许出现'='
+    fn bare_value(&mut self) -> String {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if byte.is_ascii_whitespace() || byte == b',' {
+                break;
+            }
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.input[start..self.pos]).to_string()
+    }
+
+    // 读取带引号的参数值，处理反斜杠转义
+    fn quoted_value(&mut self) -> String {
+        // 跳过起始引号
+        self.pos += 1;
+        let mut value = Vec::new();
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    if let Some(escaped) = self.peek() {
+                        value.push(escaped);
+                        self.pos += 1;
+                    }
+                }
+                _ => value.push(byte),
+            }
+        }
+        String::from_utf8_lossy(&value).to_string()
+    }
+
+    // 读取参数列表，遇到下一个认证方案时停止
+    fn params(&mut self) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        loop {
+            self.skip_separators();
+            let mark = self.pos;
+            let name = self.token();
+            if name.is_empty() {
+                break;
+            }
+            self.skip_whitespace();
+            if self.peek() != Some(b'=') {
+                // 不是参数，而是下一个认证方案
+                self.pos = mark;
+                break;
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let value = if self.peek() == Some(b'"') {
+                self.quoted_value()
+            } else {
+                self.bare_value()
+            };
+            params.push((name.to_ascii_lowercase(), value));
+        }
+        params
+    }
+}
+
+// 解析一个认证头的值，返回其中的所有认证挑战
+pub fn parse_challenges(header_value: &str) -> Vec<AuthChallenge> {
+    let mut tokenizer = ChallengeTokenizer::new(header_value);
+    let mut challenges = Vec::new();
+    loop {
+        tokenizer.skip_separators();
+        let scheme = tokenizer.token();
+        if scheme.is_empty() {
+            if tokenizer.peek().is_none() {
+                break;
+            }
+            // 跳过无法识别的字符
+            tokenizer.pos += 1;
             continue;
         }
+        let params = tokenizer.params();
+        challenges.push(AuthChallenge { scheme, params });
+    }
+    challenges
+}
+
+// 认证工具函数 - 从原始响应文本解析认证挑战
+pub fn parse_auth_challenge(response: &str) -> Result<AuthType, RtspError> {
+    let response = RtspResponse::parse(response.as_bytes())?;
+    parse_response_challenge(&response)
+}
+
+// 从已解析的响应中解析首选的认证挑战
+pub fn parse_response_challenge(response: &RtspResponse) -> Result<AuthType, RtspError> {
+    let offered = parse_response_challenges(response)?;
+    Ok(select_auth_types(offered, AuthPolicy::PreferDigest)
+        .into_iter()
+        .next()
+        .unwrap_or(AuthType::None))
+}
+
+// 从响应中解析服务器提供的所有可用认证方式
+// Digest按算法强度从强到弱排列，Basic排在最后
+pub fn parse_response_challenges(response: &RtspResponse) -> Result<Vec<AuthType>, RtspError> {
+    parse_challenge_headers(response, "WWW-Authenticate")
+}
+
+// 从407响应的Proxy-Authenticate头中解析代理提供的认证方式，排列顺序同上
+pub fn parse_proxy_challenges(response: &RtspResponse) -> Result<Vec<AuthType>, RtspError> {
+    parse_challenge_headers(response, "Proxy-Authenticate")
+}
+
+fn parse_challenge_headers(
+    response: &RtspResponse,
+    header_name: &str,
+) -> Result<Vec<AuthType>, RtspError> {
+    let mut digests: Vec<(DigestAuthInfo, DigestAlgorithm)> = Vec::new();
+    let mut basic: Option<BasicAuthInfo> = None;
+    let mut last_error = None;
+
+    for header in response.headers.get_all(header_name) {
+        for challenge in parse_challenges(header) {
+            if challenge.is_scheme("Digest") {
+                let digest_info = match digest_info_from_challenge(&challenge) {
+                    Ok(digest_info) => digest_info,
+                    Err(e) => {
+                        log::debug!("Ignoring invalid Digest challenge: {}", e);
+                        last_error = Some(e);
+                        continue;
+                    }
+                };
+                match digest_info.digest_algorithm() {
+                    Some(algorithm) => digests.push((digest_info, algorithm)),
+                    None => log::debug!(
+                        "Ignoring Digest challenge with unsupported algorithm: {:?}",
+                        digest_info.algorithm
+                    ),
+                }
+            } else if challenge.is_scheme("Basic") {
+                if basic.is_none() {
+                    basic = Some(BasicAuthInfo {
+                        realm: challenge.param("realm").map(|realm| realm.to_string()),
+                    });
+                }
+            } else {
+                log::debug!("Ignoring unsupported authentication scheme: {}", challenge.scheme);
+            }
+        }
+    }
+
+    // 排序是稳定的，同等强度时保留服务器给出的顺序
+    digests.sort_by_key(|(_, algorithm)| std::cmp::Reverse(algorithm.strength()));
+
+    let mut offered: Vec<AuthType> = digests
+        .into_iter()
+        .map(|(digest_info, _)| AuthType::Digest(digest_info))
+        .collect();
+    if let Some(basic) = basic {
+        offered.push(AuthType::Basic(basic));
+    }
 
-        let key = parts[0].trim();
-        let value = parts[1].trim().trim_matches('"');
+    match (offered.is_empty(), last_error) {
+        (true, Some(e)) => Err(e),
+        _ => Ok(offered),
+    }
+}
 
-        match key {
-            "realm" => realm = value.to_string(),
-            "nonce" => nonce = value.to_string(),
-            "qop" => qop = Some(value.to_string()),
-            "algorithm" => algorithm = Some(value.to_string()),
-            "opaque" => opaque = Some(value.to_string()),
-            _ => {}
+// 根据策略从服务器提供的认证方式中选择要尝试的方式（按尝试顺序）
+pub fn select_auth_types(offered: Vec<AuthType>, policy: AuthPolicy) -> Vec<AuthType> {
+    let digest = offered
+        .iter()
+        .find(|auth_type| matches!(auth_type, AuthType::Digest(_)))
+        .cloned();
+    let basic = offered
+        .iter()
+        .find(|auth_type| matches!(auth_type, AuthType::Basic(_)))
+        .cloned();
+
+    let ordered = match policy {
+        AuthPolicy::PreferDigest => vec![digest.or(basic)],
+        AuthPolicy::PreferBasic => vec![basic.or(digest)],
+        AuthPolicy::TryBoth => {
+            let basic = basic.unwrap_or(AuthType::Basic(BasicAuthInfo::default()));
+            vec![digest, Some(basic)]
+        }
+    };
+    let selected: Vec<AuthType> = ordered.into_iter().flatten().collect();
+    for auth_type in &selected {
+        match auth_type {
+            AuthType::Digest(info) => log::debug!(
+                "Digest authentication required, realm: {}, algorithm: {}",
+                info.realm,
+                info.algorithm.as_deref().unwrap_or("MD5")
+            ),
+            AuthType::Basic(_) => log::debug!("Basic authentication required"),
+            AuthType::None => {}
         }
     }
+    selected
+}
+
+// 解析Digest认证挑战（不含"Digest "前缀的参数部分）
+pub fn parse_digest_challenge(challenge: &str) -> Result<DigestAuthInfo, RtspError> {
+    let challenge = AuthChallenge {
+        scheme: "Digest".to_string(),
+        params: ChallengeTokenizer::new(challenge).params(),
+    };
+    digest_info_from_challenge(&challenge)
+}
+
+fn digest_info_from_challenge(challenge: &AuthChallenge) -> Result<DigestAuthInfo, RtspError> {
+    let realm = challenge.param("realm").unwrap_or_default().to_string();
+    let nonce = challenge.param("nonce").unwrap_or_default().to_string();
 
     if realm.is_empty() || nonce.is_empty() {
         return Err(RtspError::AuthenticationError(
@@ -82,13 +440,21 @@ pub fn parse_digest_challenge(challenge: &str) -> Result<DigestAuthInfo, RtspErr
     Ok(DigestAuthInfo {
         realm,
         nonce,
-        qop,
-        algorithm,
-        opaque,
+        qop: challenge.param("qop").map(|qop| qop.to_string()),
+        algorithm: challenge.param("algorithm").map(|algorithm| algorithm.to_string()),
+        opaque: challenge.param("opaque").map(|opaque| opaque.to_string()),
+        userhash: challenge
+            .param("userhash")
+            .map(|userhash| userhash.eq_ignore_ascii_case("true"))
+            .unwrap_or(false),
+        stale: challenge
+            .param("stale")
+            .map(|stale| stale.eq_ignore_ascii_case("true"))
+            .unwrap_or(false),
     })
 }
 
-// 生成认证头
+// 生成认证头（无状态，nonce计数固定为1）
 pub fn generate_auth_header(
     auth_type: &AuthType,
     username: &str,
@@ -96,49 +462,257 @@ pub fn generate_auth_header(
     method: &str,
     path: &str,
 ) -> Result<String, RtspError> {
-    match auth_type {
-        AuthType::Basic(_) => {
-            // Basic认证: base64(username:password)
-            let credentials = format!("{}:{}", username, password);
-            let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
-            Ok(format!("Basic {}", encoded))
+    Authenticator::new(username, password).authorization(auth_type, method, path, b"")
+}
+
+// Digest保护质量
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DigestQop {
+    Auth,
+    AuthInt,
+}
+
+impl DigestQop {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestQop::Auth => "auth",
+            DigestQop::AuthInt => "auth-int",
         }
-        AuthType::Digest(info) => {
-            // Digest认证
-            // let algorithm = info.algorithm.as_deref().unwrap_or("MD5");
-            // let cnonce = generate_cnonce();
-            // let nc = "00000001";
-            // let qop = info.qop.as_deref().unwrap_or("auth");
+    }
 
-            // 计算HA1 = MD5(username:realm:password)
-            let ha1_input = format!("{}:{}:{}", username, info.realm, password);
-            let ha1 = format!("{:x}", Md5::digest(ha1_input.as_bytes()));
+    // 从服务器提供的qop列表中选择，优先使用auth
+    pub fn select(offered: &str) -> Option<Self> {
+        let options: Vec<String> = offered
+            .split(',')
+            .map(|option| option.trim().to_ascii_lowercase())
+            .collect();
+        if options.iter().any(|option| option == "auth") {
+            Some(DigestQop::Auth)
+        } else if options.iter().any(|option| option == "auth-int") {
+            Some(DigestQop::AuthInt)
+        } else {
+            None
+        }
+    }
+}
+
+// 按目标和nonce记录已使用的nonce计数，在连接和认证尝试之间共享
+// 复用连接或缓存的挑战时多次请求使用同一个nonce，nc必须递增，否则服务器会视为重放
+#[derive(Debug, Clone, Default)]
+pub struct NonceCounts {
+    entries: Arc<Mutex<HashMap<IpPortAddr, UsedNonces>>>,
+}
 
-            // 计算HA2 = MD5(method:path)
-            let ha2_input = format!("{}:{}", method, path);
-            let ha2 = format!("{:x}", Md5::digest(ha2_input.as_bytes()));
+// 目标上最近使用的nonce及其计数，按使用顺序排列
+type UsedNonces = VecDeque<(String, u32)>;
 
-            // 计算response = MD5(ha1:nonce:nc:cnonce:qop:ha2)
-            let response_input = format!("{}:{}:{}", ha1, info.nonce, ha2);
-            let response = format!("{:x}", Md5::digest(response_input.as_bytes()));
+impl NonceCounts {
+    // 每个目标保留的nonce数量，服务器更换nonce后旧nonce的计数很快不再需要
+    const MAX_NONCES_PER_TARGET: usize = 8;
 
-            // 构建Digest认证头
-            let digest_header = format!(
-                "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", response=\"{}\"",
-                username, info.realm, info.nonce, path, response
-            );
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 获取目标上nonce的下一个计数，第一次使用时为1
+    pub fn next(&self, target: &IpPortAddr, nonce: &str) -> u32 {
+        let mut entries = self.entries.lock().unwrap();
+        let nonces = entries.entry(target.clone()).or_default();
+        let count = match nonces.iter().position(|(used, _)| used == nonce) {
+            Some(index) => nonces.remove(index).map_or(1, |(_, count)| count + 1),
+            None => 1,
+        };
+        nonces.push_back((nonce.to_string(), count));
+        if nonces.len() > Self::MAX_NONCES_PER_TARGET {
+            nonces.pop_front();
+        }
+        count
+    }
+}
+
+// 认证器 - 生成认证头，并为每个nonce维护nonce计数
+// 设置共享计数后按目标计数，否则只在认证器内部计数
+#[derive(Debug, Clone)]
+pub struct Authenticator {
+    username: String,
+    password: String,
+    cnonce: String,
+    nonce: String,
+    nonce_count: u32,
+    shared_counts: Option<(NonceCounts, IpPortAddr)>,
+}
+
+impl Authenticator {
+    pub fn new(username: &str, password: &str) -> Self {
+        Authenticator {
+            username: username.to_string(),
+            password: password.to_string(),
+            cnonce: generate_cnonce(),
+            nonce: String::new(),
+            nonce_count: 0,
+            shared_counts: None,
+        }
+    }
+
+    // 使用目标上共享的nonce计数
+    pub fn with_nonce_counts(mut self, counts: NonceCounts, target: IpPortAddr) -> Self {
+        self.shared_counts = Some((counts, target));
+        self
+    }
+
+    // 使用指定的客户端随机数
+    pub fn with_cnonce(mut self, cnonce: &str) -> Self {
+        self.cnonce = cnonce.to_string();
+        self
+    }
+
+    // 获取下一个nonce计数，nonce变化时重新从1开始计数
+    pub fn next_nonce_count(&mut self, nonce: &str) -> u32 {
+        if let Some((counts, target)) = &self.shared_counts {
+            return counts.next(target, nonce);
+        }
+        if self.nonce != nonce {
+            self.nonce = nonce.to_string();
+            self.nonce_count = 0;
+        }
+        self.nonce_count += 1;
+        self.nonce_count
+    }
+
+    // 为请求生成Authorization头
+    pub fn authorization(
+        &mut self,
+        auth_type: &AuthType,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+    ) -> Result<String, RtspError> {
+        match auth_type {
+            AuthType::Basic(_) => {
+                // Basic认证: base64(username:password)
+                let credentials = format!("{}:{}", self.username, self.password);
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                Ok(format!("Basic {}", encoded))
+            }
+            AuthType::Digest(info) => self.digest_authorization(info, method, uri, body),
+            AuthType::None => Err(RtspError::AuthenticationError(
+                "No authentication type specified".to_string(),
+            )),
+        }
+    }
+
+    // 生成Digest认证头 (RFC 2617 / RFC 7616)
+    fn digest_authorization(
+        &mut self,
+        info: &DigestAuthInfo,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+    ) -> Result<String, RtspError> {
+        let algorithm = info.digest_algorithm().ok_or_else(|| {
+            RtspError::AuthenticationError(format!(
+                "Unsupported Digest algorithm: {}",
+                info.algorithm.as_deref().unwrap_or_default()
+            ))
+        })?;
+        let qop = info.qop.as_deref().and_then(DigestQop::select);
+
+        // 计算HA1 = H(username:realm:password)，会话算法为H(H(username:realm:password):nonce:cnonce)
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            self.username, info.realm, self.password
+        ));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, info.nonce, self.cnonce));
+        }
+
+        // 计算HA2 = H(method:uri)，auth-int时为H(method:uri:H(body))
+        let ha2 = match qop {
+            Some(DigestQop::AuthInt) => {
+                let body_hash = algorithm.hash_bytes(body);
+                algorithm.hash(&format!("{}:{}:{}", method, uri, body_hash))
+            }
+            _ => algorithm.hash(&format!("{}:{}", method, uri)),
+        };
+
+        // userhash=true时，username字段为H(username:realm)
+        let username = if info.userhash {
+            algorithm.hash(&format!("{}:{}", self.username, info.realm))
+        } else {
+            self.username.clone()
+        };
+
+        let mut digest_header = format!(
+            "Digest username={}, realm={}, nonce={}, uri={}",
+            quoted_string(&username),
+            quoted_string(&info.realm),
+            quoted_string(&info.nonce),
+            quoted_string(uri)
+        );
+
+        match qop {
+            Some(qop) => {
+                // response = H(HA1:nonce:nc:cnonce:qop:HA2)
+                let nc = format!("{:08x}", self.next_nonce_count(&info.nonce));
+                let response = algorithm.hash(&format!(
+                    "{}:{}:{}:{}:{}:{}",
+                    ha1,
+                    info.nonce,
+                    nc,
+                    self.cnonce,
+                    qop.as_str(),
+                    ha2
+                ));
+                digest_header += &format!(
+                    ", response=\"{}\", qop={}, nc={}, cnonce=\"{}\"",
+                    response,
+                    qop.as_str(),
+                    nc,
+                    self.cnonce
+                );
+            }
+            None => {
+                // 兼容RFC 2069: response = H(HA1:nonce:HA2)
+                let response = algorithm.hash(&format!("{}:{}:{}", ha1, info.nonce, ha2));
+                if algorithm.is_session() {
+                    // 会话算法的HA1依赖cnonce，需要回传给服务器
+                    digest_header += &format!(", cnonce=\"{}\"", self.cnonce);
+                }
+                digest_header += &format!(", response=\"{}\"", response);
+            }
+        }
+
+        if info.algorithm.is_some() {
+            digest_header += &format!(", algorithm={}", algorithm.as_str());
+        }
+        if let Some(opaque) = &info.opaque {
+            digest_header += &format!(", opaque={}", quoted_string(opaque));
+        }
+        if info.userhash {
+            digest_header += ", userhash=true";
+        }
+
+        Ok(digest_header)
+    }
+}
 
-            Ok(digest_header)
+// 生成带引号的参数值，转义其中的引号和反斜杠 (RFC 7616 §3.4)
+fn quoted_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
         }
-        AuthType::None => Err(RtspError::AuthenticationError(
-            "No authentication type specified".to_string(),
-        )),
+        quoted.push(c);
     }
+    quoted.push('"');
+    quoted
 }
 
 // 生成客户端随机数
 pub fn generate_cnonce() -> String {
     let mut rng = rand::thread_rng();
     let random_bytes: [u8; 16] = rng.r#gen();
-    format!("{:x}", Md5::digest(&random_bytes))
+    format!("{:x}", Md5::digest(random_bytes))
 }
@@ -0,0 +1,80 @@
+use crate::iterator::ip_iterator::IpPortAddr;
+use crate::rtsp::auth::AuthType;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 缓存的认证挑战
+#[derive(Debug, Clone)]
+struct CachedChallenge {
+    // 服务器提供的全部认证方式
+    offered: Vec<AuthType>,
+    fetched_at: Instant,
+}
+
+// 认证挑战缓存 - 按目标地址缓存服务器的认证挑战，省去每次尝试前的无认证探测请求
+#[derive(Debug, Clone)]
+pub struct ChallengeCache {
+    entries: Arc<Mutex<HashMap<IpPortAddr, CachedChallenge>>>,
+    // 缓存的有效期，超过后重新获取挑战（nonce通常有有效期）
+    ttl: Duration,
+}
+
+impl ChallengeCache {
+    const DEFAULT_TTL: u64 = 60;
+
+    pub fn new(ttl: Duration) -> Self {
+        ChallengeCache {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    // 获取未过期的挑战
+    pub fn get(&self, addr: &IpPortAddr) -> Option<Vec<AuthType>> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(addr) {
+            Some(entry) if entry.fetched_at.elapsed() < self.ttl => Some(entry.offered.clone()),
+            Some(_) => {
+                log::trace!("Cached challenge for {} expired", addr);
+                entries.remove(addr);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // 记录服务器最新的挑战
+    pub fn insert(&self, addr: IpPortAddr, offered: Vec<AuthType>) {
+        if offered.is_empty() {
+            return;
+        }
+        log::trace!("Caching authentication challenge for {}", addr);
+        self.entries.lock().unwrap().insert(
+            addr,
+            CachedChallenge {
+                offered,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    // 删除缓存的挑战
+    pub fn invalidate(&self, addr: &IpPortAddr) {
+        self.entries.lock().unwrap().remove(addr);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for ChallengeCache {
+    fn default() -> Self {
+        ChallengeCache::new(Duration::from_secs(Self::DEFAULT_TTL))
+    }
+}
@@ -1,17 +1,138 @@
 use crate::errors::errors::{AuthenticationResult, RtspError};
-use crate::iterator::ip_iterator::IpPortAddr;
-use crate::rtsp::auth;
-use crate::rtsp::common::{build_rtsp_request, parse_sdp_content, read_response, send_request};
+use crate::iterator::ip_iterator::{DEFAULT_RTSP_PORT, IpPortAddr};
+use crate::iterator::resolver::DNS_CACHE;
+use crate::rtsp::auth::{self, AuthPolicy, AuthType, Authenticator, NonceCounts};
+use crate::rtsp::challenge_cache::ChallengeCache;
+use crate::rtsp::common::{RtspResponse, build_rtsp_request, parse_sdp_content};
+use crate::rtsp::connection::{CONNECTION_POOL, RtspConnection};
+use crate::rtsp::evidence::{self, Evidence};
+use crate::rtsp::playback::{PlaybackStatus, PlaybackVerifier};
+use crate::rtsp::sdp::SessionDescription;
+use crate::rtsp::transport::{self, CertificateInfo, DEFAULT_RTSPS_PORT, TlsOptions, TransportKind};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
 use std::marker::Send;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
 use std::pin::Pin;
-use tokio;
-use tokio::net::TcpStream;
+use std::sync::Mutex;
+use std::time::Duration;
 use url::Url;
 
+// 凭据的验证对象
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CredentialTarget {
+    // 摄像头的流地址，凭据用于Authorization
+    #[default]
+    Stream,
+    // 目标前面的RTSP代理，凭据用于Proxy-Authorization
+    Proxy,
+}
+
+impl Display for CredentialTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialTarget::Stream => write!(f, "stream"),
+            CredentialTarget::Proxy => write!(f, "proxy"),
+        }
+    }
+}
+
+// RTSP代理的凭据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+// RTSP客户端选项
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    // 认证方案选择策略
+    pub auth_policy: AuthPolicy,
+    // 在同一连接上复用多次凭据尝试
+    pub keep_alive: bool,
+    // 按目标缓存的认证挑战，设置后跳过无认证的探测请求
+    pub challenge_cache: Option<ChallengeCache>,
+    // 按目标和nonce共享的Digest nonce计数
+    pub nonce_counts: NonceCounts,
+    // 跟随重定向的最大次数，为0时不跟随
+    pub max_redirects: u32,
+    // DESCRIBE成功后执行SETUP/PLAY，验证是否真正收到媒体数据
+    pub verify_playback: bool,
+    // rtsps:// URL使用的TLS选项
+    pub tls: TlsOptions,
+    // 通过RTSP over HTTP隧道连接的端口，对所有主机生效
+    pub http_tunnel_ports: Vec<u16>,
+    // 通过RTSP over HTTP隧道连接的目标地址，由带http://前缀的目标设置
+    pub http_tunnel_targets: HashSet<SocketAddr>,
+    // 代理返回407时使用的代理凭据
+    pub proxy_credentials: Option<ProxyCredentials>,
+    // 尝试的凭据用于摄像头还是RTSP代理
+    pub credential_target: CredentialTarget,
+    // 域名固定连接的地址（域名为小写），逐个测试域名解析到的地址时使用，未固定的域名异步解析
+    pub pinned_addresses: HashMap<String, IpAddr>,
+}
+
+impl ClientOptions {
+    pub const DEFAULT_MAX_REDIRECTS: u32 = 3;
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        ClientOptions {
+            auth_policy: AuthPolicy::default(),
+            keep_alive: false,
+            challenge_cache: None,
+            nonce_counts: NonceCounts::default(),
+            max_redirects: Self::DEFAULT_MAX_REDIRECTS,
+            verify_playback: false,
+            tls: TlsOptions::default(),
+            http_tunnel_ports: Vec::new(),
+            http_tunnel_targets: HashSet::new(),
+            proxy_credentials: None,
+            credential_target: CredentialTarget::default(),
+            pinned_addresses: HashMap::new(),
+        }
+    }
+}
+
+// DESCRIBE请求的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescribeOutcome {
+    pub result: AuthenticationResult,
+    // 跟随重定向后最终请求的URL
+    pub url: String,
+    // 成功时响应中的SDP会话描述
+    pub session: Option<SessionDescription>,
+    // 启用播放验证时SETUP/PLAY的结果，验证出错时为None
+    pub playback: Option<PlaybackStatus>,
+    // RTSPS连接的服务器证书
+    pub certificate: Option<CertificateInfo>,
+    // TryBoth策略下服务器接受的所有认证方式
+    pub accepted_schemes: Vec<&'static str>,
+}
+
+impl DescribeOutcome {
+    // 是否经过了重定向
+    pub fn redirected_from(&self, url: &str) -> bool {
+        self.url != url
+    }
+}
+
 // RTSP客户端
 pub struct RtspClient {
     username: String,
     password: String,
+    options: ClientOptions,
+    // 最近一次成功的DESCRIBE响应中的会话描述
+    session: Mutex<Option<SessionDescription>>,
+    // 最近一次TLS连接的服务器证书
+    certificate: Mutex<Option<CertificateInfo>>,
+    // 代理要求认证后选择的认证方式，之后的请求都携带Proxy-Authorization
+    proxy_authenticator: Mutex<Option<(AuthType, Authenticator)>>,
+    // TryBoth策略下最近一次认证中服务器接受的认证方式
+    accepted_schemes: Mutex<Vec<&'static str>>,
 }
 
 // RTSP响应类型枚举
@@ -19,7 +140,7 @@ pub struct RtspClient {
 enum RtspResponseType {
     Unauthorized,
     Ok,
-    Other(String),
+    Other(u16, String),
 }
 
 impl RtspClient {
@@ -27,41 +148,199 @@ impl RtspClient {
         RtspClient {
             username: username.to_string(),
             password: password.to_string(),
+            options: ClientOptions::default(),
+            session: Mutex::new(None),
+            certificate: Mutex::new(None),
+            proxy_authenticator: Mutex::new(None),
+            accepted_schemes: Mutex::new(Vec::new()),
         }
     }
 
-
+    // 设置客户端选项
+    pub fn with_options(mut self, options: ClientOptions) -> Self {
+        self.options = options;
+        self
+    }
 
     // 构建RTSP请求的辅助方法
     pub fn build_request(
         &self,
         method: &str,
+        full_url: &str,
         host: &str,
         port: u16,
-        path: &str,
         cseq: u32,
         auth_header: Option<&str>,
     ) -> String {
-        let full_url = format!("rtsp://{}:{}{}", host, port, path);
-        build_rtsp_request(method, &full_url, host, port, cseq, auth_header)
+        build_rtsp_request(method, full_url, host, port, cseq, auth_header, &[])
     }
 
-    // 解析RTSP响应类型
-    fn parse_response_type(&self, response: &str) -> RtspResponseType {
-        if response.contains("401 Unauthorized") {
-            RtspResponseType::Unauthorized
-        } else if response.contains("200 OK") {
-            RtspResponseType::Ok
-        } else {
-            RtspResponseType::Other(response.to_string())
+    // 生成请求的完整URL，协议与连接是否使用TLS一致
+    fn request_url(connection: &RtspConnection, host: &str, port: u16, path: &str) -> String {
+        format!("{}://{}:{}{}", connection.scheme(), host, port, path)
+    }
+
+    // 根据状态码解析RTSP响应类型
+    fn parse_response_type(&self, response: &RtspResponse) -> RtspResponseType {
+        match response.status_code {
+            401 => RtspResponseType::Unauthorized,
+            200..=299 => RtspResponseType::Ok,
+            code => RtspResponseType::Other(code, response.reason.clone()),
+        }
+    }
+
+    // 将200和401以外的响应归类为对应的认证结果，无法归类的状态码作为协议错误
+    fn classify_response(&self, response: &RtspResponse) -> Result<AuthenticationResult, RtspError> {
+        match response.status_code {
+            301 | 302 | 303 | 307 => Ok(AuthenticationResult::Redirect {
+                status: response.status_code,
+                location: response.headers.get("Location").map(|location| location.to_string()),
+            }),
+            // 305的Location是需要经过的代理而不是新的流地址，不作为重定向跟随
+            305 => Ok(AuthenticationResult::UseProxy {
+                proxy: response.headers.get("Location").map(|location| location.to_string()),
+            }),
+            403 => Ok(AuthenticationResult::Forbidden),
+            404 => Ok(AuthenticationResult::NotFound),
+            407 => Ok(AuthenticationResult::ProxyAuthenticationRequired),
+            454 => Ok(AuthenticationResult::SessionNotFound),
+            461 => Ok(AuthenticationResult::UnsupportedTransport),
+            503 => Ok(AuthenticationResult::ServiceUnavailable {
+                // 只支持秒数形式的Retry-After
+                retry_after: response
+                    .headers
+                    .get("Retry-After")
+                    .and_then(|retry_after| retry_after.trim().parse().ok())
+                    .map(Duration::from_secs),
+            }),
+            _ => Err(RtspError::ProtocolError(format!(
+                "Unexpected response: {}",
+                response.status_line()
+            ))),
+        }
+    }
+
+    // 记录成功响应中的SDP会话描述
+    fn record_session(
+        &self,
+        connection: &RtspConnection,
+        response: &RtspResponse,
+        host: &str,
+        port: u16,
+        path: &str,
+    ) {
+        let request_url = Self::request_url(connection, host, port, path);
+        *self.session.lock().unwrap() = parse_sdp_content(response, &request_url);
+    }
+
+    // 代理认证使用的凭据，验证代理凭据时使用客户端自身的凭据
+    fn proxy_credentials(&self) -> Option<ProxyCredentials> {
+        match self.options.credential_target {
+            CredentialTarget::Proxy => Some(ProxyCredentials {
+                username: self.username.clone(),
+                password: self.password.clone(),
+            }),
+            CredentialTarget::Stream => self.options.proxy_credentials.clone(),
+        }
+    }
+
+    // 生成请求的Proxy-Authorization头，代理未要求认证时返回None
+    fn proxy_authorization(&self, method: &str, uri: &str) -> Result<Option<String>, RtspError> {
+        match &mut *self.proxy_authenticator.lock().unwrap() {
+            Some((auth_type, authenticator)) => authenticator
+                .authorization(auth_type, method, uri, b"")
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // 是否已经向代理发送过认证
+    fn proxy_authenticated(&self) -> bool {
+        self.proxy_authenticator.lock().unwrap().is_some()
+    }
+
+    // 在连接上发送一个请求并读取响应，代理返回407时使用代理凭据重试一次
+    async fn exchange(
+        &self,
+        connection: &mut RtspConnection,
+        method: &str,
+        host: &str,
+        port: u16,
+        path: &str,
+        auth_header: Option<&str>,
+    ) -> Result<RtspResponse, RtspError> {
+        let response = self
+            .exchange_once(connection, method, host, port, path, auth_header)
+            .await?;
+        if response.status_code != 407 {
+            return Ok(response);
         }
+        let credentials = match self.proxy_credentials() {
+            Some(credentials) => credentials,
+            None => {
+                log::debug!("Proxy authentication required but no proxy credentials configured");
+                return Ok(response);
+            }
+        };
+        let offered = match auth::parse_proxy_challenges(&response) {
+            Ok(offered) => offered,
+            Err(e) => {
+                log::debug!("Invalid Proxy-Authenticate challenge: {}", e);
+                return Ok(response);
+            }
+        };
+        let auth_type = match auth::select_auth_types(offered, self.options.auth_policy)
+            .into_iter()
+            .next()
+        {
+            Some(auth_type) => auth_type,
+            None => return Ok(response),
+        };
+        log::debug!("Proxy requested {} authentication", auth_type.scheme());
+        let authenticator = Authenticator::new(&credentials.username, &credentials.password);
+        *self.proxy_authenticator.lock().unwrap() = Some((auth_type, authenticator));
+        self.exchange_once(connection, method, host, port, path, auth_header)
+            .await
+    }
+
+    async fn exchange_once(
+        &self,
+        connection: &mut RtspConnection,
+        method: &str,
+        host: &str,
+        port: u16,
+        path: &str,
+        auth_header: Option<&str>,
+    ) -> Result<RtspResponse, RtspError> {
+        // 服务器已关闭连接时透明地重新连接
+        connection.ensure_open().await?;
+        let cseq = connection.next_cseq();
+        let full_url = Self::request_url(connection, host, port, path);
+        let request = match self.proxy_authorization(method, &full_url)? {
+            Some(proxy_authorization) => build_rtsp_request(
+                method,
+                &full_url,
+                host,
+                port,
+                cseq,
+                auth_header,
+                &[("Proxy-Authorization", &proxy_authorization)],
+            ),
+            None => self.build_request(method, &full_url, host, port, cseq, auth_header),
+        };
+
+        // 发送请求
+        connection.send(&request).await?;
+
+        // 读取响应
+        connection.read_response().await
     }
 
     // 发送请求并处理响应的通用方法
     pub fn send_and_process_request<'a>(
         &'a self,
-        stream: &'a mut TcpStream,
-        request: &'a str,
+        connection: &'a mut RtspConnection,
+        method: &'a str,
         host: &'a str,
         port: u16,
         path: &'a str,
@@ -69,11 +348,9 @@ impl RtspClient {
     ) -> Pin<Box<dyn futures::Future<Output = Result<AuthenticationResult, RtspError>> + Send + 'a>>
     {
         Box::pin(async move {
-            // 发送请求
-            send_request(stream, request).await?;
-
-            // 读取响应
-            let response = read_response(stream).await?;
+            let response = self
+                .exchange(connection, method, host, port, path, auth_header)
+                .await?;
 
             match self.parse_response_type(&response) {
                 RtspResponseType::Unauthorized => {
@@ -84,10 +361,8 @@ impl RtspClient {
                     match auth_header {
                         // 无认证头（第一次），则需要根据响应进一步认证
                         None => {
-                            self.handle_auth(
-                                stream, &response, host, port, path, "DESCRIBE", 2,
-                            )
-                            .await
+                            self.handle_auth(connection, &response, host, port, path, method)
+                                .await
                         }
                         //有认证头，说明已经认证过一次了，直接返回失败
                         Some(_) => Ok(AuthenticationResult::Failed),
@@ -95,76 +370,233 @@ impl RtspClient {
                 }
                 RtspResponseType::Ok => {
                     log::debug!("Ok response received");
-                    parse_sdp_content(&response);
+                    self.record_session(connection, &response, host, port, path);
                     // 解析认证类型
                     match auth_header {
                         None => Ok(AuthenticationResult::NoAuthenticationRequired),
                         Some(_) => Ok(AuthenticationResult::Success),
                     }
                 }
-                RtspResponseType::Other(msg) => {
-                    log::debug!("Other response received: {}", msg);
-                    Err(RtspError::ProtocolError(msg))
+                RtspResponseType::Other(code, reason) => {
+                    log::debug!("Other response received: {} {}", code, reason);
+                    self.classify_response(&response)
                 }
             }
         })
     }
 
-    // 通用认证处理方法
+    // 通用认证处理方法 - 根据401响应中的挑战进行认证
     async fn handle_auth<'a>(
         &'a self,
-        stream: &'a mut TcpStream,
-        response: &'a str,
+        connection: &'a mut RtspConnection,
+        response: &'a RtspResponse,
         host: &'a str,
         port: u16,
         path: &'a str,
         method: &'a str,
-        cseq: u32,
     ) -> Result<AuthenticationResult, RtspError> {
         log::debug!("Handling authentication for {} request", method);
-        let auth_type = auth::parse_auth_challenge(response)?;
+        let offered = auth::parse_response_challenges(response)?;
+        if let Some(cache) = &self.options.challenge_cache {
+            cache.insert(connection.addr().clone(), offered.clone());
+        }
+        self.authenticate(connection, offered, host, port, path, method)
+            .await
+    }
+
+    // 使用服务器提供的认证方式依次发送认证请求
+    async fn authenticate(
+        &self,
+        connection: &mut RtspConnection,
+        offered: Vec<AuthType>,
+        host: &str,
+        port: u16,
+        path: &str,
+        method: &str,
+    ) -> Result<AuthenticationResult, RtspError> {
+        let auth_types = auth::select_auth_types(offered, self.options.auth_policy);
+        if auth_types.is_empty() {
+            return Err(RtspError::AuthenticationError(
+                "401 response without a supported authentication challenge".to_string(),
+            ));
+        }
         // 生成完整URL
-        let full_url = format!("rtsp://{}:{}{}", host, port, path);
-
-        // 生成认证头
-        let auth_header = auth::generate_auth_header(
-            &auth_type,
-            &self.username,
-            &self.password,
-            method,
-            &full_url,
-        )?;
-        log::debug!("Generated authentication header: {}", auth_header);
-
-        // 构建带认证的请求
-        let authenticated_request = build_rtsp_request(
-            method,
-            &full_url,
-            host,
-            port,
-            cseq,
-            Some(&auth_header),
-        );
+        let full_url = Self::request_url(connection, host, port, path);
+
+        // 同一目标上的nonce可能在多个连接和尝试中复用，nonce计数按目标共享
+        let mut authenticator = auth::Authenticator::new(&self.username, &self.password)
+            .with_nonce_counts(self.options.nonce_counts.clone(), connection.addr().clone());
+        // TryBoth策略下继续尝试其他认证方式，记录服务器接受的所有方式（如同时接受明文的Basic）
+        let try_all = self.options.auth_policy == AuthPolicy::TryBoth;
+        let mut accepted = Vec::new();
+        for auth_type in &auth_types {
+            let mut auth_type = auth_type.clone();
+            // 每种认证方式最多因为nonce变化重新认证一次
+            let mut refreshed = false;
+            loop {
+                // 生成认证头
+                let auth_header =
+                    authenticator.authorization(&auth_type, method, &full_url, b"")?;
+                log::debug!("Generated authentication header: {}", auth_header);
+
+                // 发送认证请求
+                let response = self
+                    .exchange(connection, method, host, port, path, Some(&auth_header))
+                    .await?;
+
+                match self.parse_response_type(&response) {
+                    RtspResponseType::Ok => {
+                        log::debug!("Ok response received");
+                        if accepted.is_empty() {
+                            self.record_session(connection, &response, host, port, path);
+                        }
+                        if !try_all {
+                            return Ok(AuthenticationResult::Success);
+                        }
+                        log::info!("{} accepted {} authentication", full_url, auth_type.scheme());
+                        accepted.push(auth_type.scheme());
+                        break;
+                    }
+                    RtspResponseType::Unauthorized => {
+                        let fresh = self.fresh_challenge(connection, &auth_type, &response)?;
+                        match fresh {
+                            Some(fresh) if !refreshed => {
+                                let stale = matches!(&fresh, AuthType::Digest(info) if info.stale);
+                                log::debug!(
+                                    "Server issued a new {} challenge (stale={}), re-authenticating",
+                                    fresh.scheme(),
+                                    stale
+                                );
+                                auth_type = fresh;
+                                refreshed = true;
+                            }
+                            _ => {
+                                log::debug!("{} authentication rejected", auth_type.scheme());
+                                break;
+                            }
+                        }
+                    }
+                    RtspResponseType::Other(code, reason) => {
+                        log::debug!("Other response received: {} {}", code, reason);
+                        // 已经有认证方式被接受时，其他方式的异常响应不影响结果
+                        if accepted.is_empty() {
+                            return self.classify_response(&response);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        if accepted.is_empty() {
+            return Ok(AuthenticationResult::Failed);
+        }
+        *self.accepted_schemes.lock().unwrap() = accepted;
+        Ok(AuthenticationResult::Success)
+    }
 
-        // 发送认证请求并处理响应
-        self.send_and_process_request(
-            stream,
-            &authenticated_request,
-            host,
-            port,
-            path,
-            Some(&auth_header),
-        )
-        .await
+    // 检查认证被拒绝的401响应是否携带了新的挑战（stale=true或nonce已更换）
+    fn fresh_challenge(
+        &self,
+        connection: &RtspConnection,
+        used: &AuthType,
+        response: &RtspResponse,
+    ) -> Result<Option<AuthType>, RtspError> {
+        let used_digest = match used {
+            AuthType::Digest(info) => info,
+            // Basic没有nonce，401即表示凭据错误
+            _ => return Ok(None),
+        };
+        let offered = match auth::parse_response_challenges(response) {
+            Ok(offered) => offered,
+            Err(_) => return Ok(None),
+        };
+        let fresh = offered.iter().find_map(|auth_type| match auth_type {
+            AuthType::Digest(info) if info.stale || info.nonce != used_digest.nonce => {
+                Some(auth_type.clone())
+            }
+            _ => None,
+        });
+        if let (Some(_), Some(cache)) = (&fresh, &self.options.challenge_cache) {
+            cache.insert(connection.addr().clone(), offered);
+        }
+        Ok(fresh)
     }
 
-    // 发送DESCRIBE请求，返回认证结果
-    pub async fn describe(&self, url: &str) -> Result<AuthenticationResult, RtspError> {
+    // 验证代理凭据：代理不再返回407即表示凭据正确，不对摄像头进行认证
+    async fn describe_proxy(
+        &self,
+        connection: &mut RtspConnection,
+        host: &str,
+        port: u16,
+        path: &str,
+    ) -> Result<AuthenticationResult, RtspError> {
+        let response = self
+            .exchange(connection, "DESCRIBE", host, port, path, None)
+            .await?;
+        let authenticated = self.proxy_authenticated();
+        match response.status_code {
+            407 if authenticated => Ok(AuthenticationResult::Failed),
+            407 => Ok(AuthenticationResult::ProxyAuthenticationRequired),
+            status => {
+                log::debug!("Proxy forwarded DESCRIBE, origin replied {}", status);
+                if (200..300).contains(&status) {
+                    self.record_session(connection, &response, host, port, path);
+                }
+                if authenticated {
+                    Ok(AuthenticationResult::Success)
+                } else {
+                    Ok(AuthenticationResult::NoAuthenticationRequired)
+                }
+            }
+        }
+    }
+
+    // 在指定连接上发送DESCRIBE请求
+    async fn describe_on(
+        &self,
+        connection: &mut RtspConnection,
+        host: &str,
+        port: u16,
+        path: &str,
+    ) -> Result<AuthenticationResult, RtspError> {
+        if self.options.credential_target == CredentialTarget::Proxy {
+            return self.describe_proxy(connection, host, port, path).await;
+        }
+
+        // 已缓存该目标的挑战时直接发送认证请求
+        let cached = self
+            .options
+            .challenge_cache
+            .as_ref()
+            .and_then(|cache| cache.get(connection.addr()));
+        if let Some(offered) = cached {
+            log::debug!("Using cached challenge for {}", connection.addr());
+            return self
+                .authenticate(connection, offered, host, port, path, "DESCRIBE")
+                .await;
+        }
+
+        // 第一次请求，无认证头。通过响应确定使用什么认证方式
+        self.send_and_process_request(connection, "DESCRIBE", host, port, path, None)
+            .await
+    }
+
+    // 解析RTSP URL，返回主机（域名或IP，IPv6地址带方括号）、端口和路径（包含查询参数）
+    fn parse_url(url: &str) -> Result<(String, u16, String), RtspError> {
         log::debug!("Parsing RTSP URL: {}", url);
         let parsed_url = Url::parse(url).map_err(|_| RtspError::UrlParseError)?;
         let host = parsed_url.host_str().ok_or(RtspError::UrlParseError)?;
-        let port = parsed_url.port().unwrap_or(554);
-        let path = parsed_url.path().to_string();
+        let default_port = if transport::is_tls_url(url) {
+            DEFAULT_RTSPS_PORT
+        } else {
+            DEFAULT_RTSP_PORT
+        };
+        let port = parsed_url.port().unwrap_or(default_port);
+        // 大华等设备的流路径带有查询参数，如/cam/realmonitor?channel=1&subtype=0
+        let path = match parsed_url.query() {
+            Some(query) => format!("{}?{}", parsed_url.path(), query),
+            None => parsed_url.path().to_string(),
+        };
 
         log::debug!(
             "Parsed URL - Host: {}, Port: {}, Path: {}",
@@ -173,31 +605,274 @@ impl RtspClient {
             path
         );
 
-        // 连接到RTSP服务器
-        let ip = host.parse().map_err(|_| RtspError::InvalidIpAddress(host.to_string()))?;
-        let addr = IpPortAddr::new(ip, port);
-        log::debug!("Connecting to RTSP server at {}", addr);
+        Ok((host.to_string(), port, path))
+    }
+
+    // 确定连接的目标地址：IP直接使用，域名优先使用固定的地址，否则异步解析（带缓存）后使用第一个地址
+    async fn target_addr(&self, host: &str, port: u16) -> Result<IpPortAddr, RtspError> {
+        if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse() {
+            return Ok(IpPortAddr::new(ip, port));
+        }
+        let ip = match self.options.pinned_addresses.get(&host.to_ascii_lowercase()) {
+            Some(ip) => *ip,
+            None => DNS_CACHE.resolve(host).await?[0],
+        };
+        Ok(IpPortAddr::new(ip, port).with_hostname(host))
+    }
 
-        let mut stream = addr.connect().await.map_err(|e| {
-            RtspError::ConnectionError(format!("Failed to connect to RTSP server: {}", e))
-        })?;
+    // 选择目标的传输方式：rtsps://使用TLS，隧道端口或隧道目标上使用HTTP隧道，其余使用TCP
+    fn transport_for(&self, url: &str, addr: &IpPortAddr, path: &str) -> TransportKind {
+        if transport::is_tls_url(url) {
+            TransportKind::Tls(self.options.tls.clone())
+        } else if self.options.http_tunnel_ports.contains(&addr.port)
+            || self.options.http_tunnel_targets.contains(&addr.socket_addr())
+        {
+            TransportKind::HttpTunnel(path.to_string())
+        } else {
+            TransportKind::Tcp
+        }
+    }
 
-        // 第一次请求，无认证头。通过响应确定使用什么认证方式
-        let auth_header: Option<&str> = None;
-        // 构建初始RTSP请求
-        let request = self.build_request("DESCRIBE", host, port, &path, 1, auth_header);
-
-        // 使用通用方法发送请求并处理响应
-        return self
-            .send_and_process_request(
-                &mut stream,
-                &request,
+    // 建立新连接，TLS连接时记录服务器证书
+    async fn connect(&self, url: &str) -> Result<RtspConnection, RtspError> {
+        let (host, port, path) = Self::parse_url(url)?;
+        let addr = self.target_addr(&host, port).await?;
+        let kind = self.transport_for(url, &addr, &path);
+        let connection = RtspConnection::connect_with(addr, &kind).await?;
+        self.record_certificate(&connection);
+        Ok(connection)
+    }
+
+    fn record_certificate(&self, connection: &RtspConnection) {
+        if let Some(certificate) = connection.certificate() {
+            *self.certificate.lock().unwrap() = Some(certificate.clone());
+        }
+    }
+
+    // 获取目标的连接，keep-alive模式下从连接池取出，返回连接以及是否为复用的连接
+    async fn acquire(&self, url: &str) -> Result<(RtspConnection, bool), RtspError> {
+        if !self.options.keep_alive {
+            // 每次尝试使用新的连接
+            return Ok((self.connect(url).await?, false));
+        }
+        let (host, port, path) = Self::parse_url(url)?;
+        let addr = self.target_addr(&host, port).await?;
+        let kind = self.transport_for(url, &addr, &path);
+        let (connection, reused) = CONNECTION_POOL.checkout(addr, &kind).await?;
+        self.record_certificate(&connection);
+        Ok((connection, reused))
+    }
+
+    // 复用的连接可能已被服务器关闭，此时需要重新连接并重试
+    fn should_retry<T>(reused: bool, result: &Result<T, RtspError>) -> bool {
+        reused
+            && matches!(
+                result,
+                Err(RtspError::ConnectionError(_)) | Err(RtspError::IoError(_))
+            )
+    }
+
+    // 归还连接，keep-alive模式下成功的连接放回连接池
+    async fn release(&self, connection: RtspConnection, success: bool) {
+        if self.options.keep_alive && success {
+            CONNECTION_POOL.checkin(connection).await;
+        }
+    }
+
+    // 发送DESCRIBE请求，返回认证结果
+    pub async fn describe(&self, url: &str) -> Result<AuthenticationResult, RtspError> {
+        self.describe_outcome(url).await.map(|outcome| outcome.result)
+    }
+
+    // 发送DESCRIBE请求并跟随同一主机上的重定向，返回认证结果和最终的URL
+    pub async fn describe_outcome(&self, url: &str) -> Result<DescribeOutcome, RtspError> {
+        let mut url = url.to_string();
+        let mut hops = 0;
+        loop {
+            let result = self.describe_once(&url).await?;
+            let location = match &result {
+                AuthenticationResult::Redirect {
+                    location: Some(location),
+                    ..
+                } if hops < self.options.max_redirects => location.clone(),
+                _ => return Ok(self.outcome(result, url).await),
+            };
+            match Self::redirect_target(&url, &location) {
+                Some(target) => {
+                    log::debug!("Following redirect from {} to {}", url, target);
+                    url = target;
+                    hops += 1;
+                }
+                None => {
+                    log::debug!("Not following redirect from {} to {}", url, location);
+                    return Ok(self.outcome(result, url).await);
+                }
+            }
+        }
+    }
+
+    // 生成DESCRIBE结果，成功时带上会话描述，启用播放验证时进行验证
+    async fn outcome(&self, result: AuthenticationResult, url: String) -> DescribeOutcome {
+        let success = matches!(
+            result,
+            AuthenticationResult::Success | AuthenticationResult::NoAuthenticationRequired
+        );
+        let session = if success {
+            self.session.lock().unwrap().take()
+        } else {
+            None
+        };
+        let mut result = result;
+        let mut playback = None;
+        if success && self.options.verify_playback {
+            match self.verify_playback(&url, session.as_ref()).await {
+                Ok((status, auth_required)) => {
+                    log::debug!("Playback verification for {}: {}", url, status);
+                    // DESCRIBE无需认证但SETUP/PLAY要求认证时，凭据由SETUP/PLAY验证
+                    if auth_required && result == AuthenticationResult::NoAuthenticationRequired {
+                        result = Self::playback_result(&status);
+                    }
+                    playback = Some(status);
+                }
+                Err(e) => log::debug!("Playback verification for {} failed: {:?}", url, e),
+            }
+        }
+        DescribeOutcome {
+            result,
+            url,
+            session,
+            playback,
+            certificate: self.certificate.lock().unwrap().take(),
+            accepted_schemes: std::mem::take(&mut *self.accepted_schemes.lock().unwrap()),
+        }
+    }
+
+    // 在新连接上执行SETUP/PLAY（RTP over TCP），等待第一个RTP数据包后TEARDOWN，
+    // 返回验证结果以及SETUP或PLAY是否要求认证。
+    // 部分设备只在SETUP或PLAY时才检查认证，DESCRIBE成功并不代表可以拉流
+    pub async fn verify_playback(
+        &self,
+        url: &str,
+        session: Option<&SessionDescription>,
+    ) -> Result<(PlaybackStatus, bool), RtspError> {
+        let (host, port, _) = Self::parse_url(url)?;
+        let connection = self.connect(url).await?;
+        let mut verifier = self.playback_verifier(connection, &host, port);
+        let status = verifier.verify(url, session).await?;
+        Ok((status, verifier.auth_required()))
+    }
+
+    // 创建SETUP/PLAY验证器。经过需要认证的代理时沿用DESCRIBE协商的代理认证方式；
+    // 验证代理凭据时客户端的凭据属于代理，不发送给摄像头
+    fn playback_verifier(
+        &self,
+        connection: RtspConnection,
+        host: &str,
+        port: u16,
+    ) -> PlaybackVerifier {
+        let verifier = match self.options.credential_target {
+            CredentialTarget::Stream => PlaybackVerifier::new(
+                connection,
                 host,
                 port,
-                &path,
-                auth_header,
-            )
+                &self.username,
+                &self.password,
+                self.options.auth_policy,
+            ),
+            CredentialTarget::Proxy => {
+                PlaybackVerifier::new(connection, host, port, "", "", self.options.auth_policy)
+                    .without_stream_auth()
+            }
+        };
+        let negotiated = self.proxy_authenticator.lock().unwrap().clone();
+        match (negotiated, self.proxy_credentials()) {
+            (Some((auth_type, authenticator)), _) => {
+                verifier.with_proxy_auth(authenticator, Some(auth_type))
+            }
+            (None, Some(credentials)) => verifier.with_proxy_auth(
+                Authenticator::new(&credentials.username, &credentials.password),
+                None,
+            ),
+            (None, None) => verifier,
+        }
+    }
+
+    // SETUP/PLAY要求认证时的认证结果，使用凭据后仍被拒绝为认证失败
+    fn playback_result(status: &PlaybackStatus) -> AuthenticationResult {
+        match status {
+            PlaybackStatus::Rejected {
+                status: 401 | 403, ..
+            } => AuthenticationResult::Failed,
+            _ => AuthenticationResult::Success,
+        }
+    }
+
+    // 解析重定向的目标URL，只允许同一主机上的RTSP地址（端口和路径可以不同）
+    fn redirect_target(url: &str, location: &str) -> Option<String> {
+        let current = Url::parse(url).ok()?;
+        // Location可能是相对路径
+        let target = current.join(location).ok()?;
+        if target.scheme() != current.scheme() || target.host_str() != current.host_str() {
+            return None;
+        }
+        Some(target.to_string())
+    }
+
+    // 录制一段媒体流并解包为H.264/H.265裸流或JPEG图片，写入取证目录
+    pub async fn capture_evidence(
+        &self,
+        url: &str,
+        session: &SessionDescription,
+        duration: Duration,
+        directory: &Path,
+    ) -> Result<Evidence, RtspError> {
+        let (host, port, _) = Self::parse_url(url)?;
+        let connection = self.connect(url).await?;
+        let tracks = self
+            .playback_verifier(connection, &host, port)
+            .capture(url, session, duration)
+        .await?;
+        evidence::write_evidence(directory, tracks).await
+    }
+
+    // 发送一次DESCRIBE请求，不跟随重定向
+    async fn describe_once(&self, url: &str) -> Result<AuthenticationResult, RtspError> {
+        let (host, port, path) = Self::parse_url(url)?;
+
+        let (mut connection, reused) = self.acquire(url).await?;
+        let mut result = self.describe_on(&mut connection, &host, port, &path).await;
+        if Self::should_retry(reused, &result) {
+            log::debug!(
+                "Reused connection to {} was closed, retrying",
+                connection.addr()
+            );
+            connection.reconnect().await?;
+            result = self.describe_on(&mut connection, &host, port, &path).await;
+        }
+        self.release(connection, result.is_ok()).await;
+        result
+    }
+
+    // 发送一个无认证头的DESCRIBE请求并返回原始响应，用于探测流路径是否存在
+    pub async fn probe(&self, url: &str) -> Result<RtspResponse, RtspError> {
+        let (host, port, path) = Self::parse_url(url)?;
+
+        let (mut connection, reused) = self.acquire(url).await?;
+        let mut result = self
+            .exchange(&mut connection, "DESCRIBE", &host, port, &path, None)
             .await;
+        if Self::should_retry(reused, &result) {
+            log::debug!(
+                "Reused connection to {} was closed, retrying",
+                connection.addr()
+            );
+            connection.reconnect().await?;
+            result = self
+                .exchange(&mut connection, "DESCRIBE", &host, port, &path, None)
+                .await;
+        }
+        self.release(connection, result.is_ok()).await;
+        result
     }
 }
 
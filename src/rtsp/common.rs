@@ -1,8 +1,10 @@
 use crate::errors::errors::RtspError;
+use crate::rtsp::sdp::SessionDescription;
 use chrono::Utc;
 use rand::Rng;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use std::borrow::Cow;
+use std::fmt::Display;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::time;
 
 
@@ -25,7 +27,7 @@ pub fn select_random_user_agent() -> &'static str {
     user_agent
 }
 
-// 构建RTSP请求
+// 构建RTSP请求，Transport、Session等方法相关的头部通过extra_headers传入
 pub fn build_rtsp_request(
     method: &str,
     url: &str,
@@ -33,25 +35,33 @@ pub fn build_rtsp_request(
     port: u16,
     cseq: u32,
     auth_header: Option<&str>,
+    extra_headers: &[(&str, &str)],
 ) -> String {
     let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
     let mut request = format!("{} {} RTSP/1.0\r\n", method, url)
         + &format!("CSeq: {}\r\n", cseq)
         + &format!("Host: {}:{}\r\n", host, port)
         + &format!("Date: {}\r\n", date)
-        + &format!("User-Agent: {}\r\n", select_random_user_agent())
-        + &format!("Accept: application/sdp\r\n")
-        + &format!("Transport: RTP/AVP;unicast;client_port=8000-8001\r\n");
+        + &format!("User-Agent: {}\r\n", select_random_user_agent());
+    if method == "DESCRIBE" {
+        request += "Accept: application/sdp\r\n";
+    }
+    for (name, value) in extra_headers {
+        request += &format!("{}: {}\r\n", name, value);
+    }
 
     if let Some(auth) = auth_header {
         request += &format!("Authorization: {}\r\n", auth);
     }
 
-    request + &format!("\r\n")
+    request + "\r\n"
 }
 
 // 发送RTSP请求
-pub async fn send_request(stream: &mut TcpStream, request: &str) -> Result<(), RtspError> {
+pub async fn send_request<S>(stream: &mut S, request: &str) -> Result<(), RtspError>
+where
+    S: AsyncWrite + Unpin,
+{
     log::trace!("Sending RTSP request:\n{}", request.replace("\r\n", "\n"));
     // 发送请求，设置10秒超时
     time::timeout(
@@ -60,7 +70,7 @@ pub async fn send_request(stream: &mut TcpStream, request: &str) -> Result<(), R
     )
     .await
     .map_err(|_| RtspError::ConnectionError("Write timeout".to_string()))?
-    .map_err(|e| RtspError::IoError(e))?;
+    .map_err(RtspError::IoError)?;
     log::debug!(
         "{} request sent",
         if request.contains("Authorization") {
@@ -72,38 +82,398 @@ pub async fn send_request(stream: &mut TcpStream, request: &str) -> Result<(), R
     Ok(())
 }
 
-// 读取RTSP响应
-pub async fn read_response(stream: &mut TcpStream) -> Result<String, RtspError> {
-    let mut buffer = [0; 4096];
-    log::debug!("Waiting for response from server");
-    let n = time::timeout(std::time::Duration::from_secs(10), stream.read(&mut buffer))
-        .await
-        .map_err(|_| RtspError::ConnectionError("Read timeout".to_string()))?
-        .map_err(|e| RtspError::IoError(e))?;
+// 解析响应中的SDP内容，控制URL相对于Content-Base（或请求URL）解析
+pub fn parse_sdp_content(response: &RtspResponse, request_url: &str) -> Option<SessionDescription> {
+    let is_sdp = response
+        .headers
+        .get("Content-Type")
+        .map(|content_type| content_type.to_ascii_lowercase().starts_with("application/sdp"))
+        .unwrap_or(false);
 
-    if n == 0 {
-        log::debug!("Received empty response (0 bytes) - server closed connection");
-        return Err(RtspError::ProtocolError(
-            "Empty response received from server".to_string(),
-        ));
+    if !is_sdp || response.body.is_empty() {
+        log::debug!(
+            "No SDP content found in response: {}",
+            response.status_line()
+        );
+        return None;
     }
 
-    let response = String::from_utf8_lossy(&buffer[..n]).to_string();
-    log::debug!(
-        "Received response ({} bytes):\n{}",
-        n,
-        response.replace("\r\n", "\n")
-    );
-    log::debug!("RTSP response received");
-    Ok(response)
+    log::debug!("Received SDP content:\n{}", response.body_text());
+    match SessionDescription::parse(&response.body_text()) {
+        Ok(mut session) => {
+            let base = response
+                .headers
+                .get("Content-Base")
+                .or_else(|| response.headers.get("Content-Location"))
+                .unwrap_or(request_url);
+            session.resolve_controls(base.trim());
+            Some(session)
+        }
+        Err(e) => {
+            log::debug!("Failed to parse SDP content: {}", e);
+            None
+        }
+    }
+}
+
+// RTSP头部集合 - 头部名称大小写不敏感，同名头部按出现顺序保留多个值
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RtspHeaders {
+    entries: Vec<(String, String)>,
+}
+
+impl RtspHeaders {
+    pub fn new() -> Self {
+        RtspHeaders {
+            entries: Vec::new(),
+        }
+    }
+
+    // 追加一个头部，不覆盖已有的同名头部
+    pub fn append(&mut self, name: &str, value: &str) {
+        self.entries
+            .push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    // 获取第一个同名头部的值
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    // 获取所有同名头部的值
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// RTSP响应 - 由状态行、头部和按Content-Length截取的消息体组成
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtspResponse {
+    pub version: String,
+    pub status_code: u16,
+    pub reason: String,
+    pub headers: RtspHeaders,
+    pub body: Vec<u8>,
+}
+
+impl Display for RtspResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.status_line())
+    }
+}
+
+impl RtspResponse {
+    // 查找头部结束位置，返回(头部长度, 分隔符长度)
+    pub fn find_header_end(data: &[u8]) -> Option<(usize, usize)> {
+        let crlf = data.windows(4).position(|window| window == b"\r\n\r\n");
+        let lf = data.windows(2).position(|window| window == b"\n\n");
+        match (crlf, lf) {
+            (Some(c), Some(l)) if l < c => Some((l, 2)),
+            (Some(c), _) => Some((c, 4)),
+            (None, Some(l)) => Some((l, 2)),
+            (None, None) => None,
+        }
+    }
+
+    // 仅解析状态行和头部，消息体为空
+    pub fn parse_head(head: &str) -> Result<Self, RtspError> {
+        let mut lines = head.lines();
+        let status_line = lines
+            .by_ref()
+            .find(|line| !line.trim().is_empty())
+            .ok_or_else(|| RtspError::ProtocolError("Empty RTSP response".to_string()))?;
+
+        let mut parts = status_line.trim().splitn(3, ' ');
+        let version = parts.next().unwrap_or_default().to_string();
+        if !version.starts_with("RTSP/") && !version.starts_with("HTTP/") {
+            return Err(RtspError::ProtocolError(format!(
+                "Invalid status line: {}",
+                status_line
+            )));
+        }
+        let status_code = parts
+            .next()
+            .and_then(|code| code.parse::<u16>().ok())
+            .filter(|code| (100..1000).contains(code))
+            .ok_or_else(|| {
+                RtspError::ProtocolError(format!("Invalid status code: {}", status_line))
+            })?;
+        let reason = parts.next().unwrap_or_default().trim().to_string();
+
+        let mut headers = RtspHeaders::new();
+        for line in lines {
+            if line.trim().is_empty() {
+                break;
+            }
+            // 以空白开头的行是上一个头部的折叠续行
+            if line.starts_with(' ') || line.starts_with('\t') {
+                if let Some((_, value)) = headers.entries.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            match line.split_once(':') {
+                Some((name, value)) => headers.append(name, value),
+                None => log::trace!("Ignoring malformed header line: {}", line),
+            }
+        }
+
+        Ok(RtspResponse {
+            version,
+            status_code,
+            reason,
+            headers,
+            body: Vec::new(),
+        })
+    }
+
+    // 从原始字节解析完整响应，消息体长度以Content-Length为准
+    pub fn parse(data: &[u8]) -> Result<Self, RtspError> {
+        let (head_len, separator_len) = Self::find_header_end(data).unwrap_or((data.len(), 0));
+        let head = String::from_utf8_lossy(&data[..head_len]);
+        let mut response = Self::parse_head(&head)?;
+
+        let rest = &data[head_len + separator_len..];
+        let body_len = match response.content_length() {
+            Some(length) => length.min(rest.len()),
+            // 没有Content-Length时，保留剩余的全部数据
+            None => rest.len(),
+        };
+        response.body = rest[..body_len].to_vec();
+        Ok(response)
+    }
+
+    pub fn status_line(&self) -> String {
+        format!("{} {} {}", self.version, self.status_code, self.reason)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    pub fn content_length(&self) -> Option<usize> {
+        self.headers
+            .get("Content-Length")
+            .and_then(|length| length.trim().parse::<usize>().ok())
+    }
+
+    pub fn cseq(&self) -> Option<u32> {
+        self.headers
+            .get("CSeq")
+            .and_then(|cseq| cseq.trim().parse::<u32>().ok())
+    }
+
+    // 服务器是否要求关闭连接
+    pub fn connection_close(&self) -> bool {
+        self.headers.get_all("Connection").any(|value| {
+            value
+                .split(',')
+                .any(|option| option.trim().eq_ignore_ascii_case("close"))
+        })
+    }
+
+    pub fn body_text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+}
+
+// RTSP over TCP 交织数据帧（'$' + 通道号 + 2字节长度 + 数据）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterleavedFrame {
+    pub channel: u8,
+    pub payload: Vec<u8>,
+}
+
+// 从连接中读取到的一条完整消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtspMessage {
+    Response(RtspResponse),
+    Interleaved(InterleavedFrame),
+}
+
+// RTSP响应读取器 - 缓存已读取但尚未消费的数据，支持跨多次读取拼接完整消息
+#[derive(Debug, Default)]
+pub struct RtspReader {
+    buffer: Vec<u8>,
 }
 
-// 解析SDP内容
-pub fn parse_sdp_content(response: &str) {
-    if let Some(sdp_start) = response.find("\r\n\r\n") {
-        let sdp_content = &response[sdp_start + 4..];
-        log::debug!("Received SDP content:\n{}", sdp_content);
-    } else {
-        log::debug!("No SDP content found in response. Response: {}", response);
+impl RtspReader {
+    // 头部最大长度，防止异常服务器无限发送数据
+    const MAX_HEADER_SIZE: usize = 64 * 1024;
+    // 消息体最大长度
+    const MAX_BODY_SIZE: usize = 4 * 1024 * 1024;
+    const READ_TIMEOUT: u64 = 10;
+
+    pub fn new() -> Self {
+        RtspReader { buffer: Vec::new() }
+    }
+
+    // 缓冲区中尚未消费的字节数
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    // 读取下一个RTSP响应，跳过其间的交织数据帧
+    pub async fn read_response<S>(&mut self, stream: &mut S) -> Result<RtspResponse, RtspError>
+    where
+        S: AsyncRead + Unpin,
+    {
+        log::debug!("Waiting for response from server");
+        loop {
+            match self.read_message(stream).await? {
+                RtspMessage::Response(response) => {
+                    log::debug!("RTSP response received: {}", response.status_line());
+                    return Ok(response);
+                }
+                RtspMessage::Interleaved(frame) => {
+                    log::trace!(
+                        "Skipping interleaved frame on channel {} ({} bytes)",
+                        frame.channel,
+                        frame.payload.len()
+                    );
+                }
+            }
+        }
+    }
+
+    // 读取下一条完整消息（响应或交织数据帧）
+    pub async fn read_message<S>(&mut self, stream: &mut S) -> Result<RtspMessage, RtspError>
+    where
+        S: AsyncRead + Unpin,
+    {
+        loop {
+            if let Some(message) = self.try_parse_message()? {
+                return Ok(message);
+            }
+            self.fill_buffer(stream).await?;
+        }
+    }
+
+    // 尝试从缓冲区中解析一条完整消息，数据不足时返回None
+    fn try_parse_message(&mut self) -> Result<Option<RtspMessage>, RtspError> {
+        // 跳过消息之间多余的换行
+        let leading = self
+            .buffer
+            .iter()
+            .take_while(|byte| **byte == b'\r' || **byte == b'\n')
+            .count();
+        if leading > 0 {
+            self.buffer.drain(..leading);
+        }
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        if self.buffer[0] == b'$' {
+            if self.buffer.len() < 4 {
+                return Ok(None);
+            }
+            let channel = self.buffer[1];
+            let length = u16::from_be_bytes([self.buffer[2], self.buffer[3]]) as usize;
+            if self.buffer.len() < 4 + length {
+                return Ok(None);
+            }
+            let payload = self.buffer[4..4 + length].to_vec();
+            self.buffer.drain(..4 + length);
+            return Ok(Some(RtspMessage::Interleaved(InterleavedFrame {
+                channel,
+                payload,
+            })));
+        }
+
+        let (head_len, separator_len) = match RtspResponse::find_header_end(&self.buffer) {
+            Some(end) => end,
+            None => {
+                if self.buffer.len() > Self::MAX_HEADER_SIZE {
+                    return Err(RtspError::ProtocolError(format!(
+                        "Response header exceeds {} bytes",
+                        Self::MAX_HEADER_SIZE
+                    )));
+                }
+                return Ok(None);
+            }
+        };
+
+        let head = String::from_utf8_lossy(&self.buffer[..head_len]).to_string();
+        let mut response = RtspResponse::parse_head(&head)?;
+        let body_len = response.content_length().unwrap_or(0);
+        if body_len > Self::MAX_BODY_SIZE {
+            return Err(RtspError::ProtocolError(format!(
+                "Content-Length {} exceeds {} bytes",
+                body_len,
+                Self::MAX_BODY_SIZE
+            )));
+        }
+
+        let body_start = head_len + separator_len;
+        if self.buffer.len() < body_start + body_len {
+            return Ok(None);
+        }
+        response.body = self.buffer[body_start..body_start + body_len].to_vec();
+        self.buffer.drain(..body_start + body_len);
+
+        log::debug!(
+            "Received response ({} bytes):\n{}{}",
+            body_start + body_len,
+            head.replace("\r\n", "\n"),
+            response.body_text()
+        );
+        Ok(Some(RtspMessage::Response(response)))
+    }
+
+    // 从连接中读取更多数据到缓冲区
+    async fn fill_buffer<S>(&mut self, stream: &mut S) -> Result<(), RtspError>
+    where
+        S: AsyncRead + Unpin,
+    {
+        let mut chunk = [0; 4096];
+        let n = time::timeout(
+            std::time::Duration::from_secs(Self::READ_TIMEOUT),
+            stream.read(&mut chunk),
+        )
+        .await
+        .map_err(|_| RtspError::ConnectionError("Read timeout".to_string()))?
+        .map_err(RtspError::IoError)?;
+
+        if n == 0 {
+            if self.buffer.is_empty() {
+                log::debug!("Received empty response (0 bytes) - server closed connection");
+                return Err(RtspError::ConnectionError(
+                    "Connection closed by server".to_string(),
+                ));
+            }
+            return Err(RtspError::ProtocolError(format!(
+                "Connection closed with {} bytes of incomplete response",
+                self.buffer.len()
+            )));
+        }
+
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(())
     }
 }
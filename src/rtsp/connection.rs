@@ -0,0 +1,213 @@
+use crate::errors::errors::RtspError;
+use crate::iterator::ip_iterator::IpPortAddr;
+use crate::rtsp::common::{RtspMessage, RtspReader, RtspResponse, send_request};
+use crate::rtsp::transport::{self, CertificateInfo, Transport, TransportKind};
+use lazy_static::lazy_static;
+use log::{debug, trace};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+// RTSP连接 - 持有传输层连接（TCP、TLS或HTTP隧道）、响应读取器和递增的CSeq，可在多次请求之间复用
+pub struct RtspConnection {
+    addr: IpPortAddr,
+    stream: Box<dyn Transport>,
+    reader: RtspReader,
+    cseq: u32,
+    closed: bool,
+    requests: u32,
+    kind: TransportKind,
+    certificate: Option<CertificateInfo>,
+}
+
+impl RtspConnection {
+    // 建立新的明文连接
+    pub async fn connect(addr: IpPortAddr) -> Result<Self, RtspError> {
+        RtspConnection::connect_with(addr, &TransportKind::Tcp).await
+    }
+
+    // 使用指定的传输方式建立新连接
+    pub async fn connect_with(addr: IpPortAddr, kind: &TransportKind) -> Result<Self, RtspError> {
+        debug!("Connecting to RTSP server at {}", addr);
+        let (stream, certificate) = transport::connect(&addr, kind).await.map_err(|e| {
+            RtspError::ConnectionError(format!("Failed to connect to RTSP server: {}", e))
+        })?;
+        Ok(RtspConnection {
+            addr,
+            stream,
+            reader: RtspReader::new(),
+            cseq: 0,
+            closed: false,
+            requests: 0,
+            kind: kind.clone(),
+            certificate,
+        })
+    }
+
+    pub fn addr(&self) -> &IpPortAddr {
+        &self.addr
+    }
+
+    // 连接使用的传输方式
+    pub fn kind(&self) -> &TransportKind {
+        &self.kind
+    }
+
+    // 是否为TLS连接
+    pub fn is_tls(&self) -> bool {
+        matches!(self.kind, TransportKind::Tls(_))
+    }
+
+    // 请求URL使用的协议
+    pub fn scheme(&self) -> &'static str {
+        if self.is_tls() { "rtsps" } else { "rtsp" }
+    }
+
+    // TLS服务器证书
+    pub fn certificate(&self) -> Option<&CertificateInfo> {
+        self.certificate.as_ref()
+    }
+
+    // 当前连接上已发送的请求数
+    pub fn requests(&self) -> u32 {
+        self.requests
+    }
+
+    // 获取下一个CSeq
+    pub fn next_cseq(&mut self) -> u32 {
+        self.cseq += 1;
+        self.cseq
+    }
+
+    // 连接是否还可以继续发送请求
+    pub fn is_reusable(&self) -> bool {
+        !self.closed
+    }
+
+    // 重新建立连接，CSeq继续递增
+    pub async fn reconnect(&mut self) -> Result<(), RtspError> {
+        debug!("Reconnecting to RTSP server at {}", self.addr);
+        let fresh = RtspConnection::connect_with(self.addr.clone(), &self.kind).await?;
+        self.stream = fresh.stream;
+        self.certificate = fresh.certificate;
+        self.reader = fresh.reader;
+        self.closed = false;
+        self.requests = 0;
+        Ok(())
+    }
+
+    // 服务器已关闭连接时重新连接
+    pub async fn ensure_open(&mut self) -> Result<(), RtspError> {
+        if self.closed {
+            self.reconnect().await?;
+        }
+        Ok(())
+    }
+
+    // 发送请求
+    pub async fn send(&mut self, request: &str) -> Result<(), RtspError> {
+        let result = send_request(&mut self.stream, request).await;
+        if result.is_err() {
+            self.closed = true;
+        }
+        self.requests += 1;
+        result
+    }
+
+    // 读取响应，服务器要求关闭连接时标记为不可复用
+    pub async fn read_response(&mut self) -> Result<RtspResponse, RtspError> {
+        match self.reader.read_response(&mut self.stream).await {
+            Ok(response) => {
+                if response.connection_close() {
+                    trace!("Server at {} requested connection close", self.addr);
+                    self.closed = true;
+                }
+                Ok(response)
+            }
+            Err(e) => {
+                self.closed = true;
+                Err(e)
+            }
+        }
+    }
+
+    // 读取下一条消息（响应或交织数据帧），用于PLAY之后接收媒体数据
+    pub async fn read_message(&mut self) -> Result<RtspMessage, RtspError> {
+        let result = self.reader.read_message(&mut self.stream).await;
+        if result.is_err() {
+            self.closed = true;
+        }
+        result
+    }
+}
+
+// 空闲连接池 - 按目标地址和传输方式缓存可复用的连接
+pub struct ConnectionPool {
+    idle: Mutex<HashMap<(IpPortAddr, TransportKind), Vec<RtspConnection>>>,
+    max_idle_per_host: usize,
+}
+
+impl ConnectionPool {
+    const DEFAULT_MAX_IDLE_PER_HOST: usize = 4;
+
+    pub fn new(max_idle_per_host: usize) -> Self {
+        ConnectionPool {
+            idle: Mutex::new(HashMap::new()),
+            max_idle_per_host,
+        }
+    }
+
+    // 取出一个空闲连接，没有时建立新连接。返回值中的bool表示是否为复用的连接
+    pub async fn checkout(
+        &self,
+        addr: IpPortAddr,
+        kind: &TransportKind,
+    ) -> Result<(RtspConnection, bool), RtspError> {
+        let idle = {
+            let mut pool = self.idle.lock().await;
+            pool.get_mut(&(addr.clone(), kind.clone()))
+                .and_then(|connections| connections.pop())
+        };
+        match idle {
+            Some(connection) => {
+                trace!("Reusing idle connection to {}", addr);
+                Ok((connection, true))
+            }
+            None => Ok((RtspConnection::connect_with(addr, kind).await?, false)),
+        }
+    }
+
+    // 归还连接，不可复用或空闲连接过多时直接丢弃
+    pub async fn checkin(&self, connection: RtspConnection) {
+        if !connection.is_reusable() {
+            return;
+        }
+        let mut pool = self.idle.lock().await;
+        let connections = pool
+            .entry((connection.addr().clone(), connection.kind().clone()))
+            .or_default();
+        if connections.len() < self.max_idle_per_host {
+            connections.push(connection);
+        }
+    }
+
+    // 丢弃某个目标的全部空闲连接
+    pub async fn evict(&self, addr: &IpPortAddr) {
+        self.idle.lock().await.retain(|(idle, _), _| idle != addr);
+    }
+
+    // 关闭所有空闲连接
+    pub async fn clear(&self) {
+        self.idle.lock().await.clear();
+    }
+}
+
+impl Default for ConnectionPool {
+    fn default() -> Self {
+        ConnectionPool::new(Self::DEFAULT_MAX_IDLE_PER_HOST)
+    }
+}
+
+// 创建全局连接池
+lazy_static! {
+    pub static ref CONNECTION_POOL: ConnectionPool = ConnectionPool::default();
+}
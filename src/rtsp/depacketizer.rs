@@ -0,0 +1,601 @@
+use crate::errors::errors::RtspError;
+use crate::rtsp::sdp::{Codec, MediaDescription};
+use base64::Engine;
+use std::collections::HashMap;
+
+// Annex-B起始码
+const START_CODE: &[u8] = &[0, 0, 0, 1];
+
+// RTP数据包（RFC 3550），负载引用原始数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtpPacket<'a> {
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+    pub payload: &'a [u8],
+}
+
+impl<'a> RtpPacket<'a> {
+    // 解析RTP数据包，跳过CSRC列表、扩展头和填充
+    pub fn parse(data: &'a [u8]) -> Result<Self, RtspError> {
+        if data.len() < 12 || data[0] >> 6 != 2 {
+            return Err(RtspError::ProtocolError("Invalid RTP packet".to_string()));
+        }
+        let mut offset = 12 + 4 * (data[0] & 0x0F) as usize;
+        if data[0] & 0x10 != 0 {
+            // 扩展头：2字节profile + 2字节长度（以4字节为单位）
+            let length = data
+                .get(offset + 2..offset + 4)
+                .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+                .ok_or_else(|| RtspError::ProtocolError("Truncated RTP extension".to_string()))?;
+            offset += 4 + 4 * length;
+        }
+        let padding = if data[0] & 0x20 != 0 {
+            data[data.len() - 1] as usize
+        } else {
+            0
+        };
+        if offset + padding > data.len() {
+            return Err(RtspError::ProtocolError("Truncated RTP packet".to_string()));
+        }
+        Ok(RtpPacket {
+            marker: data[1] & 0x80 != 0,
+            payload_type: data[1] & 0x7F,
+            sequence: u16::from_be_bytes([data[2], data[3]]),
+            timestamp: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            ssrc: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+            payload: &data[offset..data.len() - padding],
+        })
+    }
+}
+
+// 解包结果：H.264/H.265的Annex-B裸流，或MJPEG的JPEG图片列表
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepacketizedMedia {
+    AnnexB(Vec<u8>),
+    Jpeg(Vec<Vec<u8>>),
+}
+
+impl DepacketizedMedia {
+    pub fn is_empty(&self) -> bool {
+        match self {
+            DepacketizedMedia::AnnexB(data) => data.is_empty(),
+            DepacketizedMedia::Jpeg(frames) => frames.is_empty(),
+        }
+    }
+}
+
+// RTP解包器，检测到丢包时丢弃未完成的分片
+pub struct Depacketizer {
+    codec: Codec,
+    state: DepacketizerState,
+    last_sequence: Option<u16>,
+    packets: usize,
+}
+
+enum DepacketizerState {
+    H264(AnnexBStream),
+    // donl表示负载中带有解码顺序号（sprop-max-don-diff大于0）
+    H265 { stream: AnnexBStream, donl: bool },
+    Mjpeg(JpegAssembler),
+}
+
+impl Depacketizer {
+    // RFC 6184 H.264解包器，参数集（SPS/PPS）写在裸流开头
+    pub fn h264(parameter_sets: &[Vec<u8>]) -> Self {
+        Self::new(
+            Codec::H264,
+            DepacketizerState::H264(AnnexBStream::new(parameter_sets)),
+        )
+    }
+
+    // RFC 7798 H.265解包器，参数集（VPS/SPS/PPS）写在裸流开头
+    pub fn h265(parameter_sets: &[Vec<u8>], donl: bool) -> Self {
+        Self::new(
+            Codec::H265,
+            DepacketizerState::H265 {
+                stream: AnnexBStream::new(parameter_sets),
+                donl,
+            },
+        )
+    }
+
+    // RFC 2435 MJPEG解包器
+    pub fn mjpeg() -> Self {
+        Self::new(
+            Codec::Mjpeg,
+            DepacketizerState::Mjpeg(JpegAssembler::default()),
+        )
+    }
+
+    fn new(codec: Codec, state: DepacketizerState) -> Self {
+        Depacketizer {
+            codec,
+            state,
+            last_sequence: None,
+            packets: 0,
+        }
+    }
+
+    // 根据SDP中的媒体描述创建解包器，不支持的编码返回None
+    pub fn for_media(media: &MediaDescription) -> Option<Self> {
+        let format = media.primary_format()?;
+        match format.codec {
+            Codec::H264 => {
+                let parameter_sets: Vec<Vec<u8>> = format
+                    .fmtp_parameter("sprop-parameter-sets")
+                    .map(|sets| sets.split(',').filter_map(decode_parameter_set).collect())
+                    .unwrap_or_default();
+                Some(Self::h264(&parameter_sets))
+            }
+            Codec::H265 => {
+                let parameter_sets: Vec<Vec<u8>> = ["sprop-vps", "sprop-sps", "sprop-pps"]
+                    .iter()
+                    .filter_map(|name| format.fmtp_parameter(name))
+                    .filter_map(decode_parameter_set)
+                    .collect();
+                let donl = format
+                    .fmtp_parameter("sprop-max-don-diff")
+                    .and_then(|diff| diff.trim().parse::<u32>().ok())
+                    .is_some_and(|diff| diff > 0);
+                Some(Self::h265(&parameter_sets, donl))
+            }
+            Codec::Mjpeg => Some(Self::mjpeg()),
+            _ => None,
+        }
+    }
+
+    pub fn codec(&self) -> &Codec {
+        &self.codec
+    }
+
+    // 已处理的RTP数据包数
+    pub fn packets(&self) -> usize {
+        self.packets
+    }
+
+    // 处理一个RTP数据包
+    pub fn push(&mut self, packet: &RtpPacket) -> Result<(), RtspError> {
+        let lost = self
+            .last_sequence
+            .is_some_and(|last| packet.sequence != last.wrapping_add(1));
+        if lost {
+            log::debug!(
+                "RTP packet loss before sequence {}, dropping partial {} frame",
+                packet.sequence,
+                self.codec
+            );
+        }
+        self.last_sequence = Some(packet.sequence);
+        self.packets += 1;
+        match &mut self.state {
+            DepacketizerState::H264(stream) => push_h264(stream, packet.payload, lost),
+            DepacketizerState::H265 { stream, donl } => {
+                push_h265(stream, packet.payload, *donl, lost)
+            }
+            DepacketizerState::Mjpeg(assembler) => assembler.push(packet, lost),
+        }
+    }
+
+    // 结束解包，返回解包结果
+    pub fn finish(self) -> DepacketizedMedia {
+        match self.state {
+            DepacketizerState::H264(stream) | DepacketizerState::H265 { stream, .. } => {
+                DepacketizedMedia::AnnexB(stream.finish())
+            }
+            DepacketizerState::Mjpeg(assembler) => DepacketizedMedia::Jpeg(assembler.frames),
+        }
+    }
+}
+
+fn decode_parameter_set(encoded: &str) -> Option<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .filter(|nal| !nal.is_empty())
+}
+
+// Annex-B裸流 - 写入带起始码的NAL单元，并重组分片单元
+struct AnnexBStream {
+    data: Vec<u8>,
+    fragment: Option<Vec<u8>>,
+    // 参数集之后是否已写入媒体数据
+    has_media: bool,
+}
+
+impl AnnexBStream {
+    fn new(parameter_sets: &[Vec<u8>]) -> Self {
+        let mut stream = AnnexBStream {
+            data: Vec::new(),
+            fragment: None,
+            has_media: false,
+        };
+        for nal in parameter_sets {
+            stream.write_nal(nal);
+        }
+        stream
+    }
+
+    fn write_nal(&mut self, nal: &[u8]) {
+        if nal.is_empty() {
+            return;
+        }
+        self.data.extend_from_slice(START_CODE);
+        self.data.extend_from_slice(nal);
+    }
+
+    fn write_media_nal(&mut self, nal: &[u8]) {
+        self.has_media = true;
+        self.write_nal(nal);
+    }
+
+    // 处理一个分片，header为重建的NAL头
+    fn push_fragment(&mut self, header: &[u8], data: &[u8], start: bool, end: bool, lost: bool) {
+        if lost {
+            self.fragment = None;
+        }
+        if start {
+            let mut fragment = header.to_vec();
+            fragment.extend_from_slice(data);
+            self.fragment = Some(fragment);
+        } else if let Some(fragment) = &mut self.fragment {
+            fragment.extend_from_slice(data);
+        }
+        if end && let Some(fragment) = self.fragment.take() {
+            self.write_media_nal(&fragment);
+        }
+    }
+
+    // 处理聚合包中以2字节长度为前缀的NAL单元，skip为每个NAL单元之前需要跳过的字节数
+    fn push_aggregate(
+        &mut self,
+        mut data: &[u8],
+        mut skip: impl FnMut(usize) -> usize,
+    ) -> Result<(), RtspError> {
+        let mut index = 0;
+        while !data.is_empty() {
+            let skipped = skip(index);
+            if data.len() < skipped + 2 {
+                return Err(RtspError::ProtocolError(
+                    "Truncated aggregation packet".to_string(),
+                ));
+            }
+            data = &data[skipped..];
+            let size = u16::from_be_bytes([data[0], data[1]]) as usize;
+            let nal = data.get(2..2 + size).ok_or_else(|| {
+                RtspError::ProtocolError("Truncated aggregation packet".to_string())
+            })?;
+            self.write_media_nal(nal);
+            data = &data[2 + size..];
+            index += 1;
+        }
+        Ok(())
+    }
+
+    // 没有收到媒体数据时不输出只有参数集的裸流
+    fn finish(self) -> Vec<u8> {
+        if self.has_media {
+            self.data
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+// RFC 6184：单NAL单元包、STAP-A（24）和FU-A（28）
+fn push_h264(stream: &mut AnnexBStream, payload: &[u8], lost: bool) -> Result<(), RtspError> {
+    let header = *payload
+        .first()
+        .ok_or_else(|| RtspError::ProtocolError("Empty H.264 payload".to_string()))?;
+    match header & 0x1F {
+        1..=23 => {
+            stream.write_media_nal(payload);
+            Ok(())
+        }
+        24 => stream.push_aggregate(&payload[1..], |_| 0),
+        28 => {
+            let fu_header = *payload
+                .get(1)
+                .ok_or_else(|| RtspError::ProtocolError("Truncated H.264 FU-A".to_string()))?;
+            let nal_header = (header & 0xE0) | (fu_header & 0x1F);
+            stream.push_fragment(
+                &[nal_header],
+                &payload[2..],
+                fu_header & 0x80 != 0,
+                fu_header & 0x40 != 0,
+                lost,
+            );
+            Ok(())
+        }
+        nal_type => Err(RtspError::ProtocolError(format!(
+            "Unsupported H.264 packetization type {}",
+            nal_type
+        ))),
+    }
+}
+
+// RFC 7798：单NAL单元包、聚合包（48）和分片单元（49）
+fn push_h265(
+    stream: &mut AnnexBStream,
+    payload: &[u8],
+    donl: bool,
+    lost: bool,
+) -> Result<(), RtspError> {
+    if payload.len() < 2 {
+        return Err(RtspError::ProtocolError(
+            "Truncated H.265 payload".to_string(),
+        ));
+    }
+    match (payload[0] >> 1) & 0x3F {
+        0..=47 => {
+            stream.write_media_nal(payload);
+            Ok(())
+        }
+        // 第一个NAL单元之前是2字节的DONL，之后是1字节的DOND
+        48 => stream.push_aggregate(&payload[2..], |index| match (donl, index) {
+            (false, _) => 0,
+            (true, 0) => 2,
+            (true, _) => 1,
+        }),
+        49 => {
+            let fu_header = *payload
+                .get(2)
+                .ok_or_else(|| RtspError::ProtocolError("Truncated H.265 FU".to_string()))?;
+            let start = fu_header & 0x80 != 0;
+            let skip = if donl && start { 5 } else { 3 };
+            let data = payload
+                .get(skip..)
+                .ok_or_else(|| RtspError::ProtocolError("Truncated H.265 FU".to_string()))?;
+            let nal_header = [(payload[0] & 0x81) | ((fu_header & 0x3F) << 1), payload[1]];
+            stream.push_fragment(&nal_header, data, start, fu_header & 0x40 != 0, lost);
+            Ok(())
+        }
+        nal_type => Err(RtspError::ProtocolError(format!(
+            "Unsupported H.265 packetization type {}",
+            nal_type
+        ))),
+    }
+}
+
+// RFC 2435附录A的亮度和色度量化表（Z字形顺序）
+const JPEG_LUMA_QUANTIZER: [u8; 64] = [
+    16, 11, 12, 14, 12, 10, 16, 14, 13, 14, 18, 17, 16, 19, 24, 40, 26, 24, 22, 22, 24, 49, 35, 37,
+    29, 40, 58, 51, 61, 60, 57, 51, 56, 55, 64, 72, 92, 78, 64, 68, 87, 69, 55, 56, 80, 109, 81,
+    87, 95, 98, 103, 104, 103, 62, 77, 113, 121, 112, 100, 120, 92, 101, 103, 99,
+];
+const JPEG_CHROMA_QUANTIZER: [u8; 64] = [
+    17, 18, 18, 24, 21, 24, 47, 26, 26, 47, 99, 66, 56, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+// JPEG标准（ITU-T T.81附录K.3）的霍夫曼表
+const LUMA_DC_CODE_LENS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const LUMA_DC_SYMBOLS: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const LUMA_AC_CODE_LENS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+const LUMA_AC_SYMBOLS: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+const CHROMA_DC_CODE_LENS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const CHROMA_DC_SYMBOLS: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+const CHROMA_AC_CODE_LENS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const CHROMA_AC_SYMBOLS: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+    0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+    0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+    0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+// (表类别和ID, 各长度的码字数, 符号)
+const HUFFMAN_TABLES: [(u8, &[u8; 16], &[u8]); 4] = [
+    (0x00, &LUMA_DC_CODE_LENS, &LUMA_DC_SYMBOLS),
+    (0x10, &LUMA_AC_CODE_LENS, &LUMA_AC_SYMBOLS),
+    (0x01, &CHROMA_DC_CODE_LENS, &CHROMA_DC_SYMBOLS),
+    (0x11, &CHROMA_AC_CODE_LENS, &CHROMA_AC_SYMBOLS),
+];
+
+// 根据Q值计算量化表（RFC 2435附录A的MakeTables），返回亮度表和色度表
+pub fn make_quantization_tables(q: u8) -> Vec<u8> {
+    let factor = q.clamp(1, 99) as u32;
+    let scale = if factor < 50 {
+        5000 / factor
+    } else {
+        200 - factor * 2
+    };
+    JPEG_LUMA_QUANTIZER
+        .iter()
+        .chain(JPEG_CHROMA_QUANTIZER.iter())
+        .map(|value| ((*value as u32 * scale + 50) / 100).clamp(1, 255) as u8)
+        .collect()
+}
+
+// 正在重组的JPEG帧的参数
+struct JpegFrame {
+    jpeg_type: u8,
+    width: u16,
+    height: u16,
+    restart_interval: Option<u16>,
+    // 每个量化表的精度（0为8位，1为16位）和表数据
+    tables: Vec<(u8, Vec<u8>)>,
+    scan: Vec<u8>,
+}
+
+// RFC 2435 JPEG重组器 - 按分片偏移拼接扫描数据，遇到标记位时生成完整的JPEG文件
+#[derive(Default)]
+struct JpegAssembler {
+    frame: Option<JpegFrame>,
+    // Q值为128-255时带内传输的量化表，后续帧可能省略
+    cached_tables: HashMap<u8, Vec<(u8, Vec<u8>)>>,
+    frames: Vec<Vec<u8>>,
+}
+
+impl JpegAssembler {
+    fn push(&mut self, packet: &RtpPacket, lost: bool) -> Result<(), RtspError> {
+        let payload = packet.payload;
+        if payload.len() < 8 {
+            return Err(RtspError::ProtocolError(
+                "Truncated JPEG header".to_string(),
+            ));
+        }
+        let offset = u32::from_be_bytes([0, payload[1], payload[2], payload[3]]) as usize;
+        let jpeg_type = payload[4];
+        let q = payload[5];
+        let mut position = 8;
+
+        let mut restart_interval = None;
+        if (64..128).contains(&jpeg_type) {
+            let header = payload.get(position..position + 4).ok_or_else(|| {
+                RtspError::ProtocolError("Truncated restart marker header".to_string())
+            })?;
+            restart_interval = Some(u16::from_be_bytes([header[0], header[1]]));
+            position += 4;
+        }
+        if jpeg_type & 0x3F > 1 {
+            self.frame = None;
+            return Err(RtspError::ProtocolError(format!(
+                "Unsupported JPEG type {}",
+                jpeg_type
+            )));
+        }
+
+        if offset == 0 {
+            let tables = if q >= 128 {
+                let header = payload.get(position..position + 4).ok_or_else(|| {
+                    RtspError::ProtocolError("Truncated quantization table header".to_string())
+                })?;
+                let precision = header[1];
+                let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+                position += 4;
+                if length > 0 {
+                    let data = payload.get(position..position + length).ok_or_else(|| {
+                        RtspError::ProtocolError("Truncated quantization tables".to_string())
+                    })?;
+                    position += length;
+                    let tables = split_quantization_tables(precision, data);
+                    self.cached_tables.insert(q, tables.clone());
+                    tables
+                } else {
+                    self.cached_tables.get(&q).cloned().ok_or_else(|| {
+                        RtspError::ProtocolError(format!("Missing quantization tables for Q={}", q))
+                    })?
+                }
+            } else {
+                let tables = make_quantization_tables(q);
+                vec![(0, tables[..64].to_vec()), (0, tables[64..].to_vec())]
+            };
+            self.frame = Some(JpegFrame {
+                jpeg_type: jpeg_type & 0x3F,
+                width: payload[6] as u16 * 8,
+                height: payload[7] as u16 * 8,
+                restart_interval,
+                tables,
+                scan: Vec::new(),
+            });
+        } else if lost
+            || self
+                .frame
+                .as_ref()
+                .is_none_or(|frame| frame.scan.len() != offset)
+        {
+            // 丢失了分片，丢弃整帧
+            self.frame = None;
+            return Ok(());
+        }
+
+        if let Some(frame) = &mut self.frame {
+            frame.scan.extend_from_slice(&payload[position..]);
+            if packet.marker
+                && let Some(frame) = self.frame.take()
+            {
+                self.frames.push(build_jpeg(&frame));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 按精度位把带内量化表拆分为单独的表
+fn split_quantization_tables(precision: u8, mut data: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut tables = Vec::new();
+    while !data.is_empty() && tables.len() < 4 {
+        let table_precision = (precision >> tables.len()) & 1;
+        let size = if table_precision == 1 { 128 } else { 64 };
+        if data.len() < size {
+            break;
+        }
+        tables.push((table_precision, data[..size].to_vec()));
+        data = &data[size..];
+    }
+    tables
+}
+
+fn write_segment(jpeg: &mut Vec<u8>, marker: u8, content: &[u8]) {
+    jpeg.extend_from_slice(&[0xFF, marker]);
+    jpeg.extend_from_slice(&(content.len() as u16 + 2).to_be_bytes());
+    jpeg.extend_from_slice(content);
+}
+
+// 根据RTP/JPEG头部生成JFIF头部（RFC 2435附录B的MakeHeaders），拼接扫描数据
+fn build_jpeg(frame: &JpegFrame) -> Vec<u8> {
+    let mut jpeg = vec![0xFF, 0xD8];
+    for (id, (precision, table)) in frame.tables.iter().enumerate() {
+        let mut content = vec![(precision << 4) | id as u8];
+        content.extend_from_slice(table);
+        write_segment(&mut jpeg, 0xDB, &content);
+    }
+    if let Some(interval) = frame.restart_interval {
+        write_segment(&mut jpeg, 0xDD, &interval.to_be_bytes());
+    }
+
+    // 类型0为4:2:2，类型1为4:2:0；只有一个量化表时色度也使用该表
+    let luma_sampling = if frame.jpeg_type == 0 { 0x21 } else { 0x22 };
+    let chroma_table = if frame.tables.len() > 1 { 1 } else { 0 };
+    let mut sof = vec![8];
+    sof.extend_from_slice(&frame.height.to_be_bytes());
+    sof.extend_from_slice(&frame.width.to_be_bytes());
+    sof.extend_from_slice(&[
+        3,
+        0,
+        luma_sampling,
+        0,
+        1,
+        0x11,
+        chroma_table,
+        2,
+        0x11,
+        chroma_table,
+    ]);
+    write_segment(&mut jpeg, 0xC0, &sof);
+
+    for (class_id, code_lens, symbols) in HUFFMAN_TABLES {
+        let mut content = vec![class_id];
+        content.extend_from_slice(code_lens);
+        content.extend_from_slice(symbols);
+        write_segment(&mut jpeg, 0xC4, &content);
+    }
+
+    write_segment(&mut jpeg, 0xDA, &[3, 0, 0x00, 1, 0x11, 2, 0x11, 0, 63, 0]);
+    jpeg.extend_from_slice(&frame.scan);
+    if !frame.scan.ends_with(&[0xFF, 0xD9]) {
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+    }
+    jpeg
+}
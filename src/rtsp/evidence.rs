@@ -0,0 +1,106 @@
+use crate::errors::errors::RtspError;
+use crate::iterator::ip_iterator::IpPortAddr;
+use crate::rtsp::depacketizer::{DepacketizedMedia, Depacketizer};
+use crate::rtsp::sdp::Codec;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// 取证录制选项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvidenceOptions {
+    // 取证文件的根目录，每个目标使用单独的子目录
+    pub directory: PathBuf,
+    // 录制时长
+    pub duration: Duration,
+}
+
+impl EvidenceOptions {
+    pub const DEFAULT_DURATION: Duration = Duration::from_secs(5);
+
+    pub fn new(directory: &str, duration: Duration) -> Self {
+        EvidenceOptions {
+            directory: PathBuf::from(directory),
+            duration,
+        }
+    }
+
+    // 目标的取证目录，如evidence/192.168.1.10_554，域名目标为evidence/camera.local_192.168.1.10_554
+    pub fn host_directory(&self, ip_port: &IpPortAddr) -> PathBuf {
+        let ip = ip_port.ip.to_string().replace(':', "_");
+        match &ip_port.hostname {
+            Some(hostname) => self
+                .directory
+                .join(format!("{}_{}_{}", hostname, ip, ip_port.port)),
+            None => self.directory.join(format!("{}_{}", ip, ip_port.port)),
+        }
+    }
+}
+
+// 录制的取证材料
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Evidence {
+    pub directory: PathBuf,
+    // 写入的文件：H.264/H.265裸流或JPEG图片
+    pub files: Vec<PathBuf>,
+    // 收到的RTP数据包数
+    pub packets: usize,
+}
+
+impl Display for Evidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} files in {}",
+            self.files.len(),
+            self.directory.display()
+        )
+    }
+}
+
+// 把各轨道的解包结果写入取证目录，index为轨道在SDP中的序号
+pub async fn write_evidence(
+    directory: &Path,
+    tracks: Vec<(usize, Depacketizer)>,
+) -> Result<Evidence, RtspError> {
+    tokio::fs::create_dir_all(directory)
+        .await
+        .map_err(RtspError::IoError)?;
+
+    let mut files = Vec::new();
+    let mut packets = 0;
+    for (index, depacketizer) in tracks {
+        packets += depacketizer.packets();
+        let extension = match depacketizer.codec() {
+            Codec::H265 => "h265",
+            _ => "h264",
+        };
+        match depacketizer.finish() {
+            DepacketizedMedia::AnnexB(data) if !data.is_empty() => {
+                let file = directory.join(format!("track{}.{}", index + 1, extension));
+                tokio::fs::write(&file, data)
+                    .await
+                    .map_err(RtspError::IoError)?;
+                files.push(file);
+            }
+            DepacketizedMedia::Jpeg(frames) => {
+                for (number, frame) in frames.iter().enumerate() {
+                    let file = directory.join(format!("track{}_{:04}.jpg", index + 1, number + 1));
+                    tokio::fs::write(&file, frame)
+                        .await
+                        .map_err(RtspError::IoError)?;
+                    files.push(file);
+                }
+            }
+            DepacketizedMedia::AnnexB(_) => {
+                log::debug!("No media received for track {}", index + 1);
+            }
+        }
+    }
+
+    Ok(Evidence {
+        directory: directory.to_path_buf(),
+        files,
+        packets,
+    })
+}
@@ -0,0 +1,247 @@
+use crate::errors::errors::RtspError;
+use crate::iterator::ip_iterator::IpPortAddr;
+use crate::rtsp::common::select_random_user_agent;
+use crate::rtsp::transport::Transport;
+use base64::Engine;
+use rand::Rng;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::time;
+
+// HTTP响应头的最大长度
+const MAX_HEADER_LENGTH: usize = 8192;
+const TUNNEL_CONTENT_TYPE: &str = "application/x-rtsp-tunnelled";
+// POST请求声明的Content-Length，服务器可能在收到这么多数据后关闭POST连接
+const POST_CONTENT_LENGTH: usize = 32767;
+// 每个POST连接实际发送的base64数据上限，按4字节对齐使每个POST的数据都能单独解码
+const POST_BODY_LIMIT: usize = POST_CONTENT_LENGTH / 4 * 4;
+
+type PostFuture = Pin<Box<dyn Future<Output = Result<TcpStream, RtspError>> + Send>>;
+
+// RTSP over HTTP隧道（QuickTime/Apple隧道）
+// GET连接接收服务器的RTSP响应和交织数据，POST连接发送base64编码的RTSP请求，
+// 两个连接通过x-sessioncookie关联。POST连接发送的数据达到声明的Content-Length之前，
+// 使用相同的cookie重新建立POST连接
+pub struct HttpTunnel {
+    addr: IpPortAddr,
+    path: String,
+    cookie: String,
+    get: Box<dyn Transport>,
+    post: Box<dyn Transport>,
+    // 当前POST连接已发送的base64数据长度
+    post_sent: usize,
+    // 正在重新建立的POST连接
+    reopening: Option<PostFuture>,
+    // 当前请求的原始数据、编码后待发送的数据，以及已发送的长度
+    pending: Vec<u8>,
+    encoded: Vec<u8>,
+    written: usize,
+    // 写入在发送了部分数据后被取消，POST连接上的base64数据已不完整，隧道不能继续使用
+    broken: bool,
+}
+
+impl HttpTunnel {
+    // 建立隧道，path为GET和POST请求使用的HTTP路径
+    pub async fn connect(addr: &IpPortAddr, path: &str) -> Result<Self, RtspError> {
+        let cookie = generate_session_cookie();
+        let path = if path.is_empty() { "/" } else { path };
+        let host = addr.authority();
+        log::debug!("Opening RTSP over HTTP tunnel to {}{}", addr, path);
+
+        // 先建立GET连接，服务器确认后再建立POST连接
+        let mut get = addr.connect().await?;
+        let request = format!(
+            "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: {}\r\nx-sessioncookie: {}\r\n\
+Accept: {}\r\nPragma: no-cache\r\nCache-Control: no-cache\r\n\r\n",
+            path,
+            host,
+            select_random_user_agent(),
+            cookie,
+            TUNNEL_CONTENT_TYPE
+        );
+        write_all(&mut get, &request).await?;
+        let (status, reason) = read_http_status(&mut get).await?;
+        if status != 200 {
+            return Err(RtspError::ConnectionError(format!(
+                "HTTP tunnel rejected: {} {}",
+                status, reason
+            )));
+        }
+
+        let post = open_post(addr.clone(), path.to_string(), cookie.clone()).await?;
+
+        Ok(HttpTunnel {
+            addr: addr.clone(),
+            path: path.to_string(),
+            cookie,
+            get: Box::new(get),
+            post: Box::new(post),
+            post_sent: 0,
+            reopening: None,
+            pending: Vec::new(),
+            encoded: Vec::new(),
+            written: 0,
+            broken: false,
+        })
+    }
+}
+
+// 建立隧道的POST连接并发送请求头
+async fn open_post(addr: IpPortAddr, path: String, cookie: String) -> Result<TcpStream, RtspError> {
+    let mut post = addr.connect().await?;
+    let request = format!(
+        "POST {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: {}\r\nx-sessioncookie: {}\r\n\
+Content-Type: {}\r\nPragma: no-cache\r\nCache-Control: no-cache\r\n\
+Content-Length: {}\r\nExpires: Sun, 9 Jan 1972 00:00:00 GMT\r\n\r\n",
+        path,
+        addr.authority(),
+        select_random_user_agent(),
+        cookie,
+        TUNNEL_CONTENT_TYPE,
+        POST_CONTENT_LENGTH
+    );
+    write_all(&mut post, &request).await?;
+    Ok(post)
+}
+
+// 生成会话cookie，22个字母数字字符
+fn generate_session_cookie() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(22)
+        .map(char::from)
+        .collect()
+}
+
+async fn write_all<S>(stream: &mut S, request: &str) -> Result<(), RtspError>
+where
+    S: AsyncWrite + Unpin,
+{
+    time::timeout(
+        Duration::from_secs(10),
+        stream.write_all(request.as_bytes()),
+    )
+    .await
+    .map_err(|_| RtspError::ConnectionError("Write timeout".to_string()))?
+    .map_err(RtspError::IoError)
+}
+
+// 读取GET请求的HTTP响应头，返回状态码和原因短语。响应头之后是RTSP数据，逐字节读取避免多读
+async fn read_http_status<S>(stream: &mut S) -> Result<(u16, String), RtspError>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut header = Vec::new();
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_HEADER_LENGTH {
+            return Err(RtspError::ProtocolError(
+                "HTTP tunnel response header too long".to_string(),
+            ));
+        }
+        let byte = time::timeout(Duration::from_secs(10), stream.read_u8())
+            .await
+            .map_err(|_| RtspError::TimeoutError("HTTP tunnel response timeout".to_string()))?
+            .map_err(RtspError::IoError)?;
+        header.push(byte);
+    }
+
+    let header = String::from_utf8_lossy(&header);
+    log::trace!("HTTP tunnel response:\n{}", header.replace("\r\n", "\n"));
+    let status_line = header.lines().next().unwrap_or_default();
+    let mut parts = status_line.splitn(3, ' ');
+    match (
+        parts.next(),
+        parts.next().and_then(|code| code.parse().ok()),
+    ) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/") => {
+            Ok((status, parts.next().unwrap_or_default().to_string()))
+        }
+        _ => Err(RtspError::ProtocolError(format!(
+            "Invalid HTTP tunnel response: {}",
+            status_line
+        ))),
+    }
+}
+
+impl AsyncRead for HttpTunnel {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for HttpTunnel {
+    // 每次写入的数据单独进行base64编码，全部发送后才返回，调用方重试时传入相同的数据。
+    // 传入不同的数据说明之前的写入被取消（如超时），未发送的编码数据直接丢弃，
+    // 已经发送了一部分时隧道被标记为损坏，之后的写入都返回错误，连接不会被复用
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.encoded.is_empty() || this.pending != buf {
+            if this.written > 0 {
+                log::debug!("HTTP tunnel write was cancelled after a partial request");
+                this.broken = true;
+            }
+            this.pending = buf.to_vec();
+            this.encoded = base64::engine::general_purpose::STANDARD
+                .encode(buf)
+                .into_bytes();
+            this.written = 0;
+        }
+        if this.broken {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "HTTP tunnel is broken by a cancelled write",
+            )));
+        }
+        while this.written < this.encoded.len() {
+            if let Some(reopening) = &mut this.reopening {
+                let result = ready!(reopening.as_mut().poll(cx));
+                this.reopening = None;
+                let post = result.map_err(|e| io::Error::other(e.to_string()))?;
+                log::debug!("Reopened HTTP tunnel POST connection to {}", this.addr);
+                this.post = Box::new(post);
+                this.post_sent = 0;
+            }
+            // 当前POST连接的数据已达到上限，在4字节边界处切换到新的POST连接
+            let budget = POST_BODY_LIMIT - this.post_sent;
+            if budget == 0 {
+                let post = open_post(this.addr.clone(), this.path.clone(), this.cookie.clone());
+                this.reopening = Some(Box::pin(post));
+                continue;
+            }
+            let end = this.encoded.len().min(this.written + budget);
+            let written =
+                ready!(Pin::new(&mut this.post).poll_write(cx, &this.encoded[this.written..end]))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            this.written += written;
+            this.post_sent += written;
+        }
+        this.pending.clear();
+        this.encoded.clear();
+        this.written = 0;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.post).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(Pin::new(&mut self.post).poll_shutdown(cx))?;
+        Pin::new(&mut self.get).poll_shutdown(cx)
+    }
+}
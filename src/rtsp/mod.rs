@@ -1,5 +1,15 @@
 // 模块声明
 pub mod auth;
+pub mod challenge_cache;
 pub mod client;
 pub mod common;
+pub mod connection;
+pub mod depacketizer;
+pub mod evidence;
+pub mod http_tunnel;
+pub mod path_discovery;
+pub mod playback;
 pub mod rtsp_worker;
+pub mod sdp;
+pub mod sps;
+pub mod transport;
@@ -0,0 +1,127 @@
+use crate::errors::errors::{AuthenticationResult, RtspError};
+use crate::iterator::credential_reader::read_lines_from_file;
+use crate::rtsp::common::RtspResponse;
+use std::fmt::Display;
+
+// 常见摄像头厂商的RTSP流路径
+pub const DEFAULT_STREAM_PATHS: &[&str] = &[
+    "/",
+    // 海康威视
+    "/Streaming/Channels/101",
+    "/Streaming/Channels/102",
+    "/h264/ch1/main/av_stream",
+    // 大华
+    "/cam/realmonitor?channel=1&subtype=0",
+    "/cam/realmonitor?channel=1&subtype=1",
+    // 宇视
+    "/media/video1",
+    // Axis
+    "/axis-media/media.amp",
+    // 通用路径
+    "/live.sdp",
+    "/live",
+    "/live/ch00_0",
+    "/stream1",
+    "/11",
+    "/h264",
+    "/video1",
+    "/onvif1",
+];
+
+// 流路径的探测状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathStatus {
+    // 无需认证即可访问
+    Open,
+    // 路径存在但需要认证（未认证时返回401）
+    AuthRequired,
+    // 使用找到的凭据可以访问
+    Authorized,
+    // 路径不存在（404）
+    NotFound,
+}
+
+impl PathStatus {
+    // 根据无认证DESCRIBE请求的响应判断路径状态，其他状态码无法判断
+    pub fn from_response(response: &RtspResponse) -> Option<Self> {
+        match response.status_code {
+            200..=299 => Some(PathStatus::Open),
+            401 => Some(PathStatus::AuthRequired),
+            404 => Some(PathStatus::NotFound),
+            _ => None,
+        }
+    }
+
+    // 根据携带凭据的DESCRIBE请求的认证结果判断路径状态
+    pub fn from_authentication(result: &AuthenticationResult) -> Option<Self> {
+        match result {
+            AuthenticationResult::Success => Some(PathStatus::Authorized),
+            AuthenticationResult::NoAuthenticationRequired => Some(PathStatus::Open),
+            AuthenticationResult::NotFound => Some(PathStatus::NotFound),
+            _ => None,
+        }
+    }
+
+    // 路径是否存在
+    pub fn exists(&self) -> bool {
+        !matches!(self, PathStatus::NotFound)
+    }
+}
+
+impl Display for PathStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            PathStatus::Open => "open",
+            PathStatus::AuthRequired => "auth required",
+            PathStatus::Authorized => "authorized",
+            PathStatus::NotFound => "not found",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+// 探测到的流路径
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiscoveredPath {
+    pub path: String,
+    pub status: PathStatus,
+}
+
+// 规范化路径，确保以'/'开头
+pub fn normalize_path(path: &str) -> String {
+    let path = path.trim();
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+// 默认的流路径字典
+pub fn default_stream_paths() -> Vec<String> {
+    DEFAULT_STREAM_PATHS
+        .iter()
+        .map(|path| path.to_string())
+        .collect()
+}
+
+// 从字典文件读取流路径，忽略空行和'#'开头的注释，去除重复的路径
+pub fn load_stream_paths(file_path: &str) -> Result<Vec<String>, RtspError> {
+    let mut paths: Vec<String> = Vec::new();
+    for line in read_lines_from_file(file_path)? {
+        if line.starts_with('#') {
+            continue;
+        }
+        let path = normalize_path(&line);
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        return Err(RtspError::InvalidArgument(format!(
+            "No stream paths found in {}",
+            file_path
+        )));
+    }
+    Ok(paths)
+}
@@ -0,0 +1,507 @@
+use crate::errors::errors::RtspError;
+use crate::rtsp::auth::{self, AuthPolicy, AuthType, Authenticator};
+use crate::rtsp::common::{RtspMessage, RtspResponse, build_rtsp_request};
+use crate::rtsp::connection::RtspConnection;
+use crate::rtsp::depacketizer::{Depacketizer, RtpPacket};
+use crate::rtsp::sdp::SessionDescription;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::Duration;
+use tokio::time;
+
+// 默认的RTP over TCP交织通道（RTP使用0，RTCP使用1）
+const DEFAULT_INTERLEAVED: (u8, u8) = (0, 1);
+// PLAY之后等待第一个RTP数据包的时间
+pub const DEFAULT_MEDIA_TIMEOUT: Duration = Duration::from_secs(5);
+// TEARDOWN响应的等待时间，超时不影响验证结果
+const TEARDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+// 录制的最大轨道数，每个轨道占用一对交织通道，通道号不超过255
+const MAX_CAPTURE_TRACKS: usize = 128;
+// 录制时缓存的媒体数据上限，达到上限后提前结束录制
+pub const DEFAULT_MAX_CAPTURE_BYTES: usize = 64 * 1024 * 1024;
+
+// SETUP/PLAY验证的结果
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PlaybackStatus {
+    // 收到了RTP数据包，媒体流可以访问
+    Streaming,
+    // PLAY成功但超时时间内没有收到RTP数据包
+    NoMedia,
+    // SETUP或PLAY请求被拒绝
+    Rejected {
+        method: String,
+        status: u16,
+        reason: String,
+    },
+}
+
+impl PlaybackStatus {
+    // 是否真正收到了媒体数据
+    pub fn media_flowed(&self) -> bool {
+        matches!(self, PlaybackStatus::Streaming)
+    }
+}
+
+impl Display for PlaybackStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlaybackStatus::Streaming => write!(f, "media verified"),
+            PlaybackStatus::NoMedia => write!(f, "no media received"),
+            PlaybackStatus::Rejected {
+                method,
+                status,
+                reason,
+            } => write!(f, "{} rejected ({} {})", method, status, reason),
+        }
+    }
+}
+
+// 生成使用RTP over TCP交织传输的Transport头
+pub fn interleaved_transport(rtp_channel: u8, rtcp_channel: u8) -> String {
+    format!(
+        "RTP/AVP/TCP;unicast;interleaved={}-{}",
+        rtp_channel, rtcp_channel
+    )
+}
+
+// 解析Transport头中的交织通道，如"RTP/AVP/TCP;unicast;interleaved=2-3"
+pub fn parse_interleaved(transport: &str) -> Option<(u8, u8)> {
+    let channels = transport
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("interleaved="))?;
+    match channels.split_once('-') {
+        Some((rtp, rtcp)) => Some((rtp.trim().parse().ok()?, rtcp.trim().parse().ok()?)),
+        None => {
+            let rtp: u8 = channels.trim().parse().ok()?;
+            Some((rtp, rtp.wrapping_add(1)))
+        }
+    }
+}
+
+// 解析Session头中的会话ID，去除";timeout=60"等参数
+pub fn parse_session_id(session: &str) -> &str {
+    session.split(';').next().unwrap_or_default().trim()
+}
+
+// 检查是否为RTP数据包（版本号为2且长度不小于固定头部）
+pub fn is_rtp_packet(payload: &[u8]) -> bool {
+    payload.len() >= 12 && payload[0] >> 6 == 2
+}
+
+// SETUP/PLAY验证 - 在独立的连接上进行，连接上的认证方式在多个请求之间复用
+pub struct PlaybackVerifier {
+    connection: RtspConnection,
+    host: String,
+    port: u16,
+    authenticator: Authenticator,
+    auth_policy: AuthPolicy,
+    auth_type: Option<AuthType>,
+    // SETUP或PLAY是否返回过401
+    auth_required: bool,
+    // 是否对摄像头进行认证，验证代理凭据时不发送摄像头的凭据
+    stream_auth: bool,
+    // 经过需要认证的RTSP代理时使用的代理认证器，以及代理要求的认证方式
+    proxy_authenticator: Option<Authenticator>,
+    proxy_auth_type: Option<AuthType>,
+    media_timeout: Duration,
+    max_capture_bytes: usize,
+}
+
+impl PlaybackVerifier {
+    pub fn new(
+        connection: RtspConnection,
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        auth_policy: AuthPolicy,
+    ) -> Self {
+        PlaybackVerifier {
+            connection,
+            host: host.to_string(),
+            port,
+            authenticator: Authenticator::new(username, password),
+            auth_policy,
+            auth_type: None,
+            auth_required: false,
+            stream_auth: true,
+            proxy_authenticator: None,
+            proxy_auth_type: None,
+            media_timeout: DEFAULT_MEDIA_TIMEOUT,
+            max_capture_bytes: DEFAULT_MAX_CAPTURE_BYTES,
+        }
+    }
+
+    // 设置等待第一个RTP数据包的时间
+    pub fn with_media_timeout(mut self, media_timeout: Duration) -> Self {
+        self.media_timeout = media_timeout;
+        self
+    }
+
+    // 设置录制时缓存的媒体数据上限
+    pub fn with_max_capture_bytes(mut self, max_capture_bytes: usize) -> Self {
+        self.max_capture_bytes = max_capture_bytes;
+        self
+    }
+
+    // 设置代理认证器，已知代理的认证方式时每个请求都携带Proxy-Authorization，否则在407后使用
+    pub fn with_proxy_auth(
+        mut self,
+        authenticator: Authenticator,
+        auth_type: Option<AuthType>,
+    ) -> Self {
+        self.proxy_authenticator = Some(authenticator);
+        self.proxy_auth_type = auth_type;
+        self
+    }
+
+    // 不对摄像头进行认证，401作为拒绝结果返回
+    pub fn without_stream_auth(mut self) -> Self {
+        self.stream_auth = false;
+        self
+    }
+
+    // SETUP或PLAY是否要求认证，此时验证结果取决于使用的凭据
+    pub fn auth_required(&self) -> bool {
+        self.auth_required
+    }
+
+    // 对第一个视频轨道（没有视频时使用第一个轨道）执行SETUP和PLAY，等待第一个RTP数据包后TEARDOWN
+    pub async fn verify(
+        &mut self,
+        url: &str,
+        session: Option<&SessionDescription>,
+    ) -> Result<PlaybackStatus, RtspError> {
+        let media =
+            session.and_then(|session| session.video().next().or_else(|| session.media.first()));
+        let track_url = media
+            .and_then(|media| media.control.clone())
+            .unwrap_or_else(|| url.to_string());
+        let aggregate_url = aggregate_url(url, session);
+
+        let transport = interleaved_transport(DEFAULT_INTERLEAVED.0, DEFAULT_INTERLEAVED.1);
+        let response = self
+            .request("SETUP", &track_url, &[("Transport", &transport)])
+            .await?;
+        if !response.is_success() {
+            return Ok(Self::rejected("SETUP", &response));
+        }
+        let session_id = match response.headers.get("Session") {
+            Some(session) => parse_session_id(session).to_string(),
+            None => {
+                return Err(RtspError::ProtocolError(
+                    "SETUP response without a Session header".to_string(),
+                ));
+            }
+        };
+        // 服务器可能分配了不同的交织通道
+        let (rtp_channel, _) = response
+            .headers
+            .get("Transport")
+            .and_then(parse_interleaved)
+            .unwrap_or(DEFAULT_INTERLEAVED);
+
+        let response = self
+            .request(
+                "PLAY",
+                &aggregate_url,
+                &[("Session", &session_id), ("Range", "npt=0.000-")],
+            )
+            .await?;
+        if !response.is_success() {
+            self.teardown(&aggregate_url, &session_id).await;
+            return Ok(Self::rejected("PLAY", &response));
+        }
+
+        let status = self.wait_for_media(rtp_channel).await;
+        self.teardown(&aggregate_url, &session_id).await;
+        Ok(status)
+    }
+
+    // 对每个可以解包的轨道执行SETUP，PLAY后在指定时间内接收交织的RTP数据包并解包，返回轨道序号和解包器。
+    // 轨道数和缓存的数据量都有上限，SDP和媒体流来自不可信的设备
+    pub async fn capture(
+        mut self,
+        url: &str,
+        session: &SessionDescription,
+        duration: Duration,
+    ) -> Result<Vec<(usize, Depacketizer)>, RtspError> {
+        let mut tracks: Vec<(usize, Depacketizer)> = session
+            .media
+            .iter()
+            .enumerate()
+            .filter_map(|(index, media)| Depacketizer::for_media(media).map(|d| (index, d)))
+            .collect();
+        if tracks.is_empty() {
+            return Err(RtspError::ProtocolError(
+                "No tracks with a supported codec".to_string(),
+            ));
+        }
+        if tracks.len() > MAX_CAPTURE_TRACKS {
+            log::debug!(
+                "Capturing only the first {} of {} tracks",
+                MAX_CAPTURE_TRACKS,
+                tracks.len()
+            );
+            tracks.truncate(MAX_CAPTURE_TRACKS);
+        }
+        let aggregate_url = aggregate_url(url, Some(session));
+
+        // 每个轨道使用一对交织通道，RTP通道号映射到tracks中的位置
+        let mut channels = HashMap::new();
+        let mut session_id: Option<String> = None;
+        for (position, (index, _)) in tracks.iter().enumerate() {
+            let track_url = session.media[*index]
+                .control
+                .clone()
+                .unwrap_or_else(|| url.to_string());
+            let rtp = (position * 2) as u8;
+            let requested = (rtp, rtp + 1);
+            let transport = interleaved_transport(requested.0, requested.1);
+            let mut headers = vec![("Transport", transport.as_str())];
+            if let Some(session_id) = &session_id {
+                headers.push(("Session", session_id.as_str()));
+            }
+            let response = self.request("SETUP", &track_url, &headers).await?;
+            if !response.is_success() {
+                return Err(RtspError::ProtocolError(format!(
+                    "SETUP {} rejected: {}",
+                    track_url,
+                    response.status_line()
+                )));
+            }
+            if session_id.is_none() {
+                session_id = response
+                    .headers
+                    .get("Session")
+                    .map(|session| parse_session_id(session).to_string());
+            }
+            let (rtp_channel, _) = response
+                .headers
+                .get("Transport")
+                .and_then(parse_interleaved)
+                .unwrap_or(requested);
+            channels.insert(rtp_channel, position);
+        }
+        let session_id = session_id.ok_or_else(|| {
+            RtspError::ProtocolError("SETUP response without a Session header".to_string())
+        })?;
+
+        let response = self
+            .request(
+                "PLAY",
+                &aggregate_url,
+                &[("Session", &session_id), ("Range", "npt=0.000-")],
+            )
+            .await?;
+        if !response.is_success() {
+            self.teardown(&aggregate_url, &session_id).await;
+            return Err(RtspError::ProtocolError(format!(
+                "PLAY {} rejected: {}",
+                aggregate_url,
+                response.status_line()
+            )));
+        }
+
+        let deadline = time::Instant::now() + duration;
+        let mut captured = 0;
+        loop {
+            let message = match time::timeout_at(deadline, self.connection.read_message()).await {
+                Ok(Ok(message)) => message,
+                Ok(Err(e)) => {
+                    log::debug!("Media capture from {} ended early: {:?}", aggregate_url, e);
+                    break;
+                }
+                // 录制时间结束
+                Err(_) => break,
+            };
+            if let RtspMessage::Interleaved(frame) = message
+                && let Some(position) = channels.get(&frame.channel)
+            {
+                captured += frame.payload.len();
+                if captured > self.max_capture_bytes {
+                    log::debug!(
+                        "Media capture from {} reached the {} byte limit",
+                        aggregate_url,
+                        self.max_capture_bytes
+                    );
+                    break;
+                }
+                let result = RtpPacket::parse(&frame.payload)
+                    .and_then(|packet| tracks[*position].1.push(&packet));
+                if let Err(e) = result {
+                    log::trace!("Skipping RTP packet on channel {}: {}", frame.channel, e);
+                }
+            }
+        }
+        self.teardown(&aggregate_url, &session_id).await;
+        Ok(tracks)
+    }
+
+    fn rejected(method: &str, response: &RtspResponse) -> PlaybackStatus {
+        log::debug!("{} rejected: {}", method, response.status_line());
+        PlaybackStatus::Rejected {
+            method: method.to_string(),
+            status: response.status_code,
+            reason: response.reason.clone(),
+        }
+    }
+
+    // 发送请求，收到401时根据挑战认证后重发一次，之后的请求直接携带认证头
+    async fn request(
+        &mut self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<RtspResponse, RtspError> {
+        let response = self.proxy_request(method, url, headers).await?;
+        if response.status_code != 401 || !self.stream_auth {
+            return Ok(response);
+        }
+        self.auth_required = true;
+        let offered = auth::parse_response_challenges(&response)?;
+        match auth::select_auth_types(offered, self.auth_policy)
+            .into_iter()
+            .next()
+        {
+            Some(auth_type) => {
+                log::debug!("{} requires {} authentication", method, auth_type.scheme());
+                self.auth_type = Some(auth_type);
+                self.proxy_request(method, url, headers).await
+            }
+            None => Ok(response),
+        }
+    }
+
+    // 发送请求，代理返回407时根据代理挑战认证后重发一次，之后的请求都携带Proxy-Authorization
+    async fn proxy_request(
+        &mut self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<RtspResponse, RtspError> {
+        let response = self.exchange(method, url, headers).await?;
+        if response.status_code != 407 || self.proxy_authenticator.is_none() {
+            return Ok(response);
+        }
+        let offered = match auth::parse_proxy_challenges(&response) {
+            Ok(offered) => offered,
+            Err(e) => {
+                log::debug!("Invalid Proxy-Authenticate challenge: {}", e);
+                return Ok(response);
+            }
+        };
+        match auth::select_auth_types(offered, self.auth_policy)
+            .into_iter()
+            .next()
+        {
+            Some(auth_type) => {
+                log::debug!(
+                    "Proxy requested {} authentication for {}",
+                    auth_type.scheme(),
+                    method
+                );
+                self.proxy_auth_type = Some(auth_type);
+                self.exchange(method, url, headers).await
+            }
+            None => Ok(response),
+        }
+    }
+
+    // 在连接上发送一个请求并读取响应
+    async fn exchange(
+        &mut self,
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<RtspResponse, RtspError> {
+        let auth_header = match &self.auth_type {
+            Some(auth_type) => Some(
+                self.authenticator
+                    .authorization(auth_type, method, url, b"")?,
+            ),
+            None => None,
+        };
+        let proxy_header = match (&mut self.proxy_authenticator, &self.proxy_auth_type) {
+            (Some(authenticator), Some(auth_type)) => {
+                Some(authenticator.authorization(auth_type, method, url, b"")?)
+            }
+            _ => None,
+        };
+        let mut headers = headers.to_vec();
+        if let Some(proxy_header) = &proxy_header {
+            headers.push(("Proxy-Authorization", proxy_header));
+        }
+        let cseq = self.connection.next_cseq();
+        let request = build_rtsp_request(
+            method,
+            url,
+            &self.host,
+            self.port,
+            cseq,
+            auth_header.as_deref(),
+            &headers,
+        );
+        self.connection.send(&request).await?;
+        self.connection.read_response().await
+    }
+
+    // 等待RTP通道上的第一个数据包
+    async fn wait_for_media(&mut self, rtp_channel: u8) -> PlaybackStatus {
+        let connection = &mut self.connection;
+        let wait = async {
+            loop {
+                match connection.read_message().await? {
+                    RtspMessage::Interleaved(frame)
+                        if frame.channel == rtp_channel && is_rtp_packet(&frame.payload) =>
+                    {
+                        return Ok::<_, RtspError>(frame.payload.len());
+                    }
+                    RtspMessage::Interleaved(frame) => log::trace!(
+                        "Skipping interleaved frame on channel {} ({} bytes)",
+                        frame.channel,
+                        frame.payload.len()
+                    ),
+                    RtspMessage::Response(response) => {
+                        log::trace!("Skipping unexpected response: {}", response.status_line())
+                    }
+                }
+            }
+        };
+        match time::timeout(self.media_timeout, wait).await {
+            Ok(Ok(length)) => {
+                log::debug!("Received first RTP packet ({} bytes)", length);
+                PlaybackStatus::Streaming
+            }
+            Ok(Err(e)) => {
+                log::debug!("Failed to receive media: {:?}", e);
+                PlaybackStatus::NoMedia
+            }
+            Err(_) => {
+                log::debug!("No RTP packet received within {:?}", self.media_timeout);
+                PlaybackStatus::NoMedia
+            }
+        }
+    }
+
+    // 结束会话，忽略TEARDOWN的结果
+    async fn teardown(&mut self, url: &str, session_id: &str) {
+        if !self.connection.is_reusable() {
+            return;
+        }
+        let headers = [("Session", session_id)];
+        let teardown = self.exchange("TEARDOWN", url, &headers);
+        match time::timeout(TEARDOWN_TIMEOUT, teardown).await {
+            Ok(Ok(response)) => log::debug!("TEARDOWN response: {}", response.status_line()),
+            Ok(Err(e)) => log::debug!("TEARDOWN failed: {:?}", e),
+            Err(_) => log::debug!("TEARDOWN timed out"),
+        }
+    }
+}
+
+// 聚合控制URL，为'*'时已被解析为基础URL，没有时使用请求URL
+fn aggregate_url(url: &str, session: Option<&SessionDescription>) -> String {
+    session
+        .and_then(|session| session.control.clone())
+        .unwrap_or_else(|| url.to_string())
+}
@@ -1,5 +1,7 @@
-use crate::errors::errors::{AuthenticationResult, RtspError};
-use crate::rtsp::client::RtspClient;
+use crate::errors::errors::RtspError;
+use crate::rtsp::client::{ClientOptions, DescribeOutcome, RtspClient};
+use crate::rtsp::common::RtspResponse;
+use crate::rtsp::connection::CONNECTION_POOL;
 use lazy_static::lazy_static;
 use log::{debug, error, trace};
 use std::sync::Arc;
@@ -13,7 +15,14 @@ pub enum RtspMessage {
         username: String,
         password: String,
         rtsp_url: String,
-        response_tx: mpsc::Sender<Result<AuthenticationResult, RtspError>>,
+        options: ClientOptions,
+        response_tx: mpsc::Sender<Result<DescribeOutcome, RtspError>>,
+    },
+    // 流路径探测请求
+    ProbeRequest {
+        rtsp_url: String,
+        options: ClientOptions,
+        response_tx: mpsc::Sender<Result<RtspResponse, RtspError>>,
     },
     // 停止工作线程
     Stop,
@@ -53,6 +62,7 @@ impl RtspWorker {
                         username,
                         password,
                         rtsp_url,
+                        options,
                         response_tx,
                     } => {
                         let start_time = Instant::now();
@@ -60,12 +70,15 @@ impl RtspWorker {
                             "Worker {} processing auth request for {}@{}:123",
                             id, username, rtsp_url
                         );
-                        // 执行认证
-                        let result = async {
-                            let client = RtspClient::new(&username, &password);
-                            client.describe(&rtsp_url).await
-                        }
-                        .await;
+                        // 执行认证，请求方取消时放弃正在进行的认证
+                        let client = RtspClient::new(&username, &password).with_options(options);
+                        let result = tokio::select! {
+                            result = client.describe_outcome(&rtsp_url) => result,
+                            _ = response_tx.closed() => {
+                                debug!("Worker {} dropped cancelled auth request for {}", id, rtsp_url);
+                                continue;
+                            }
+                        };
                         let duration = start_time.elapsed();
                         trace!("Worker {} completed auth request in {:?}", id, duration);
 
@@ -74,6 +87,20 @@ impl RtspWorker {
                             error!("Failed to send authentication result: {:?}", e);
                         }
                     }
+                    RtspMessage::ProbeRequest {
+                        rtsp_url,
+                        options,
+                        response_tx,
+                    } => {
+                        debug!("Worker {} processing probe request for {}", id, rtsp_url);
+                        let client = RtspClient::new("", "").with_options(options);
+                        let result = client.probe(&rtsp_url).await;
+
+                        // 发送结果
+                        if let Err(e) = response_tx.send(result).await {
+                            error!("Failed to send probe result: {:?}", e);
+                        }
+                    }
                     RtspMessage::Stop => {
                         debug!("RTSP worker {} stopping", id);
                         break;
@@ -147,10 +174,8 @@ impl RtspWorkerManager {
         }
 
         let mut workers = self.workers.lock().await;
-        for worker in workers.iter_mut() {
-            if let Some(w) = worker {
-                w.start();
-            }
+        for w in workers.iter_mut().flatten() {
+            w.start();
         }
 
         *running = true;
@@ -170,33 +195,34 @@ impl RtspWorkerManager {
         current
     }
 
-    // 发送认证请求 - 使用轮询方式分发到工作线程
-    pub async fn auth_request(
+    // 轮询选择下一个工作线程的发送器
+    async fn next_sender(&self, rtsp_url: &str) -> Result<mpsc::Sender<RtspMessage>, RtspError> {
+        // 注意，以下代码在异步上下文中运行，不能直接使用阻塞的锁操作
+        // 也不能使用mutex的lock方法，因为它会阻塞当前线程
+        // 所以这里使用了senders的生命周期，确保在函数中完成对sender的获取。
+
+        // 获取下一个工作线程的索引
+        let index = self.get_next_worker_index().await;
+        // 获取对应的发送器
+        let senders = self.senders.lock().await;
+        let sender = senders
+            .get(index)
+            .ok_or_else(|| RtspError::ProtocolError("No available workers".to_string()))?;
+        debug!("RTSP worker {} selected for request to {}", index, rtsp_url);
+        Ok(sender.clone())
+    }
+
+    // 发送DESCRIBE请求 - 使用轮询方式分发到工作线程，返回原始的认证结果和最终的URL
+    pub async fn describe_request(
         &self,
         username: &str,
         password: &str,
         rtsp_url: &str,
-    ) -> Result<Option<(String, String)>, RtspError> {
+        options: &ClientOptions,
+    ) -> Result<DescribeOutcome, RtspError> {
         let (response_tx, mut response_rx) = mpsc::channel(10);
+        let sender = self.next_sender(rtsp_url).await?;
 
-        let sender = {
-            // 注意，以下代码在异步上下文中运行，不能直接使用阻塞的锁操作
-            // 也不能使用mutex的lock方法，因为它会阻塞当前线程
-            // 所以这里使用了senders的生命周期，确保在{}中完成对sender的获取。
-
-            // 获取下一个工作线程的索引
-            let index = self.get_next_worker_index().await;
-            // 获取对应的发送器
-            let senders = self.senders.lock().await;
-            let sender = senders
-                .get(index)
-                .ok_or_else(|| RtspError::ProtocolError("No available workers".to_string()))?;
-            debug!(
-                "RTSP worker {} selected for auth request to {}",
-                index, rtsp_url
-            );
-            sender.clone()
-        };
         // 发送认证请求
         // 发送给后台的worker进行处理
         sender
@@ -204,6 +230,7 @@ impl RtspWorkerManager {
                 username: username.to_string(),
                 password: password.to_string(),
                 rtsp_url: rtsp_url.to_string(),
+                options: options.clone(),
                 response_tx, //用于将验证结果传回
             })
             .await
@@ -212,24 +239,34 @@ impl RtspWorkerManager {
             })?;
 
         // 等待响应
-        match response_rx.recv().await {
-            Some(result) => match result {
-                Ok(AuthenticationResult::Success) => {
-                    Ok(Some((username.to_string(), password.to_string())))
-                }
-                Ok(AuthenticationResult::NoAuthenticationRequired) => {
-                    Ok(Some(("".to_string(), "".to_string())))
-                }
-                Ok(AuthenticationResult::Failed) => Ok(None),
-                Err(e) => Err(RtspError::AuthenticationError(format!(
-                    "Authentication failed: {:?} for {}",
-                    e, rtsp_url
-                ))),
-            },
-            None => Err(RtspError::ProtocolError(
-                "No response from RTSP worker".to_string(),
-            )),
-        }
+        response_rx.recv().await.ok_or_else(|| {
+            RtspError::ProtocolError("No response from RTSP worker".to_string())
+        })?
+    }
+
+    // 发送流路径探测请求，返回无认证DESCRIBE请求的响应
+    pub async fn probe_request(
+        &self,
+        rtsp_url: &str,
+        options: &ClientOptions,
+    ) -> Result<RtspResponse, RtspError> {
+        let (response_tx, mut response_rx) = mpsc::channel(10);
+        let sender = self.next_sender(rtsp_url).await?;
+
+        sender
+            .send(RtspMessage::ProbeRequest {
+                rtsp_url: rtsp_url.to_string(),
+                options: options.clone(),
+                response_tx,
+            })
+            .await
+            .map_err(|e| {
+                RtspError::ProtocolError(format!("Failed to send probe request: {:?}", e))
+            })?;
+
+        response_rx.recv().await.ok_or_else(|| {
+            RtspError::ProtocolError("No response from RTSP worker".to_string())
+        })?
     }
 
     // 停止所有工作线程
@@ -250,6 +287,9 @@ impl RtspWorkerManager {
             }
         }
 
+        // 关闭所有复用的空闲连接
+        CONNECTION_POOL.clear().await;
+
         *self.is_running.lock().await = false;
         debug!("RTSP worker pool stopped");
     }
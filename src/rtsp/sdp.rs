@@ -0,0 +1,389 @@
+use crate::errors::errors::RtspError;
+use crate::rtsp::sps::{self, VideoInfo};
+use std::fmt::Display;
+
+// 媒体编码
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Codec {
+    H264,
+    H265,
+    Mjpeg,
+    Mpeg4,
+    Aac,
+    Pcmu,
+    Pcma,
+    G726,
+    Opus,
+    Other(String),
+}
+
+impl Codec {
+    // 根据rtpmap中的编码名称识别编码
+    pub fn from_encoding(encoding: &str) -> Self {
+        let upper = encoding.to_ascii_uppercase();
+        match upper.as_str() {
+            "H264" => Codec::H264,
+            "H265" | "HEVC" => Codec::H265,
+            "JPEG" => Codec::Mjpeg,
+            "MP4V-ES" => Codec::Mpeg4,
+            "MPEG4-GENERIC" | "MP4A-LATM" => Codec::Aac,
+            "PCMU" => Codec::Pcmu,
+            "PCMA" => Codec::Pcma,
+            "OPUS" => Codec::Opus,
+            _ if upper.starts_with("G726") => Codec::G726,
+            _ => Codec::Other(encoding.to_string()),
+        }
+    }
+
+    // RFC 3551中的静态负载类型，这些类型可以不出现在rtpmap中
+    pub fn from_static_payload_type(payload_type: u8) -> Option<(Self, u32)> {
+        match payload_type {
+            0 => Some((Codec::Pcmu, 8000)),
+            8 => Some((Codec::Pcma, 8000)),
+            26 => Some((Codec::Mjpeg, 90000)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Codec::H264 => write!(f, "H264"),
+            Codec::H265 => write!(f, "H265"),
+            Codec::Mjpeg => write!(f, "MJPEG"),
+            Codec::Mpeg4 => write!(f, "MPEG4"),
+            Codec::Aac => write!(f, "AAC"),
+            Codec::Pcmu => write!(f, "PCMU"),
+            Codec::Pcma => write!(f, "PCMA"),
+            Codec::G726 => write!(f, "G726"),
+            Codec::Opus => write!(f, "OPUS"),
+            Codec::Other(encoding) => write!(f, "{}", encoding),
+        }
+    }
+}
+
+// o=行
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Origin {
+    pub username: String,
+    pub session_id: String,
+    pub session_version: String,
+    pub network_type: String,
+    pub address_type: String,
+    pub address: String,
+}
+
+// 媒体的一种负载格式（m=行中的一个负载类型及其rtpmap、fmtp属性）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MediaFormat {
+    pub payload_type: u8,
+    pub codec: Codec,
+    pub clock_rate: u32,
+    pub channels: Option<u16>,
+    // fmtp属性的原始参数，如"packetization-mode=1;sprop-parameter-sets=..."
+    pub fmtp: Option<String>,
+}
+
+impl MediaFormat {
+    // 获取fmtp中的参数值，参数名大小写不敏感
+    pub fn fmtp_parameter(&self, name: &str) -> Option<&str> {
+        self.fmtp.as_deref()?.split(';').find_map(|parameter| {
+            let (key, value) = parameter.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then_some(value.trim())
+        })
+    }
+}
+
+// m=行及其属性
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MediaDescription {
+    // video、audio、application等
+    pub media_type: String,
+    pub port: u16,
+    pub protocol: String,
+    pub formats: Vec<MediaFormat>,
+    pub connection: Option<String>,
+    // 解析后的轨道控制URL，解析Content-Base前为原始值
+    pub control: Option<String>,
+    pub attributes: Vec<(String, Option<String>)>,
+    // 从fmtp中的SPS解析出的分辨率、配置和级别
+    pub video_info: Option<VideoInfo>,
+}
+
+impl MediaDescription {
+    pub fn is_video(&self) -> bool {
+        self.media_type.eq_ignore_ascii_case("video")
+    }
+
+    pub fn is_audio(&self) -> bool {
+        self.media_type.eq_ignore_ascii_case("audio")
+    }
+
+    // 第一个负载格式，摄像头通常每个媒体只有一种格式
+    pub fn primary_format(&self) -> Option<&MediaFormat> {
+        self.formats.first()
+    }
+
+    pub fn codec(&self) -> Option<&Codec> {
+        self.primary_format().map(|format| &format.codec)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        find_attribute(&self.attributes, name)
+    }
+}
+
+impl Display for MediaDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.primary_format() {
+            Some(format) => write!(
+                f,
+                "{} {}/{}",
+                self.media_type, format.codec, format.clock_rate
+            )?,
+            None => write!(f, "{}", self.media_type)?,
+        }
+        if let Some(video_info) = &self.video_info {
+            write!(f, " {}", video_info)?;
+        }
+        Ok(())
+    }
+}
+
+// SDP会话描述 (RFC 4566)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SessionDescription {
+    pub version: u32,
+    pub origin: Option<Origin>,
+    pub session_name: String,
+    pub connection: Option<String>,
+    pub timing: Option<(u64, u64)>,
+    // 会话级别的控制URL
+    pub control: Option<String>,
+    pub attributes: Vec<(String, Option<String>)>,
+    pub media: Vec<MediaDescription>,
+}
+
+impl SessionDescription {
+    // 解析SDP文本，无法识别的行被忽略
+    pub fn parse(text: &str) -> Result<Self, RtspError> {
+        let mut session = SessionDescription::default();
+        let mut has_version = false;
+
+        for line in text.lines() {
+            let line = line.trim_end_matches('\r');
+            let (kind, value) = match line.split_once('=') {
+                Some((kind, value)) if kind.len() == 1 => (kind, value.trim()),
+                _ => continue,
+            };
+
+            // m=行之后的属性属于该媒体
+            if let Some(media) = session.media.last_mut() {
+                match kind {
+                    "c" => media.connection = Some(value.to_string()),
+                    "a" => parse_media_attribute(media, value),
+                    "m" => session.media.push(parse_media(value)?),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match kind {
+                "v" => {
+                    session.version = value.parse().map_err(|_| {
+                        RtspError::ProtocolError(format!("Invalid SDP version: {}", value))
+                    })?;
+                    has_version = true;
+                }
+                "o" => session.origin = parse_origin(value),
+                "s" => session.session_name = value.to_string(),
+                "c" => session.connection = Some(value.to_string()),
+                "t" => {
+                    let mut times = value.split_whitespace().map(|time| time.parse().ok());
+                    if let (Some(Some(start)), Some(Some(stop))) = (times.next(), times.next()) {
+                        session.timing = Some((start, stop));
+                    }
+                }
+                "a" => {
+                    let (name, attribute) = split_attribute(value);
+                    if name == "control" {
+                        session.control = attribute.clone();
+                    }
+                    session.attributes.push((name, attribute));
+                }
+                "m" => session.media.push(parse_media(value)?),
+                _ => {}
+            }
+        }
+
+        if !has_version {
+            return Err(RtspError::ProtocolError(
+                "Invalid SDP: missing version line".to_string(),
+            ));
+        }
+        for media in &mut session.media {
+            media.video_info = media.primary_format().and_then(sps::video_info_from_format);
+        }
+        Ok(session)
+    }
+
+    // 根据Content-Base（或请求URL）解析会话和轨道的控制URL (RFC 2326 C.1.1)
+    pub fn resolve_controls(&mut self, base: &str) {
+        for media in &mut self.media {
+            if let Some(control) = &media.control {
+                media.control = Some(resolve_control(base, control));
+            }
+        }
+        if let Some(control) = &self.control {
+            self.control = Some(resolve_control(base, control));
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        find_attribute(&self.attributes, name)
+    }
+
+    pub fn video(&self) -> impl Iterator<Item = &MediaDescription> {
+        self.media.iter().filter(|media| media.is_video())
+    }
+
+    pub fn audio(&self) -> impl Iterator<Item = &MediaDescription> {
+        self.media.iter().filter(|media| media.is_audio())
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.audio().next().is_some()
+    }
+}
+
+// 解析控制URL：'*'表示基础URL本身，绝对URL直接使用，其余视为相对于基础URL的路径
+fn resolve_control(base: &str, control: &str) -> String {
+    if control == "*" {
+        return base.to_string();
+    }
+    if control.to_ascii_lowercase().starts_with("rtsp://")
+        || control.to_ascii_lowercase().starts_with("rtsps://")
+    {
+        return control.to_string();
+    }
+    // 大华等设备的基础URL带有查询参数，直接拼接而不是按URL规则替换最后一段
+    if base.ends_with('/') {
+        format!("{}{}", base, control.trim_start_matches('/'))
+    } else {
+        format!("{}/{}", base, control.trim_start_matches('/'))
+    }
+}
+
+fn find_attribute<'a>(attributes: &'a [(String, Option<String>)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| value.as_deref())
+}
+
+// 将"name:value"形式的属性拆分，没有值的属性（如recvonly）返回None
+fn split_attribute(value: &str) -> (String, Option<String>) {
+    match value.split_once(':') {
+        Some((name, attribute)) => (name.trim().to_string(), Some(attribute.trim().to_string())),
+        None => (value.to_string(), None),
+    }
+}
+
+fn parse_origin(value: &str) -> Option<Origin> {
+    let fields: Vec<&str> = value.split_whitespace().collect();
+    if fields.len() != 6 {
+        return None;
+    }
+    Some(Origin {
+        username: fields[0].to_string(),
+        session_id: fields[1].to_string(),
+        session_version: fields[2].to_string(),
+        network_type: fields[3].to_string(),
+        address_type: fields[4].to_string(),
+        address: fields[5].to_string(),
+    })
+}
+
+// 解析m=行：<media> <port>[/<number of ports>] <proto> <fmt> ...
+fn parse_media(value: &str) -> Result<MediaDescription, RtspError> {
+    let mut fields = value.split_whitespace();
+    let invalid = || RtspError::ProtocolError(format!("Invalid SDP media line: {}", value));
+    let media_type = fields.next().ok_or_else(invalid)?.to_string();
+    let port = fields
+        .next()
+        .and_then(|port| port.split('/').next())
+        .and_then(|port| port.parse().ok())
+        .ok_or_else(invalid)?;
+    let protocol = fields.next().ok_or_else(invalid)?.to_string();
+
+    let formats = fields
+        .filter_map(|format| format.parse::<u8>().ok())
+        .map(|payload_type| {
+            let (codec, clock_rate) = Codec::from_static_payload_type(payload_type)
+                .unwrap_or((Codec::Other(payload_type.to_string()), 0));
+            MediaFormat {
+                payload_type,
+                codec,
+                clock_rate,
+                channels: None,
+                fmtp: None,
+            }
+        })
+        .collect();
+
+    Ok(MediaDescription {
+        media_type,
+        port,
+        protocol,
+        formats,
+        connection: None,
+        control: None,
+        attributes: Vec::new(),
+        video_info: None,
+    })
+}
+
+fn parse_media_attribute(media: &mut MediaDescription, value: &str) {
+    let (name, attribute) = split_attribute(value);
+    match (name.as_str(), attribute.as_deref()) {
+        // a=rtpmap:<payload type> <encoding name>/<clock rate>[/<channels>]
+        ("rtpmap", Some(rtpmap)) => {
+            if let Some((payload_type, encoding)) = rtpmap.split_once(char::is_whitespace) {
+                let mut parts = encoding.trim().split('/');
+                let codec = Codec::from_encoding(parts.next().unwrap_or_default());
+                let clock_rate = parts.next().and_then(|rate| rate.parse().ok()).unwrap_or(0);
+                let channels = parts.next().and_then(|channels| channels.parse().ok());
+                if let Some(format) = format_mut(media, payload_type) {
+                    format.codec = codec;
+                    format.clock_rate = clock_rate;
+                    format.channels = channels;
+                }
+            }
+        }
+        // a=fmtp:<payload type> <parameters>
+        ("fmtp", Some(fmtp)) => {
+            if let Some((payload_type, parameters)) = fmtp.split_once(char::is_whitespace)
+                && let Some(format) = format_mut(media, payload_type)
+            {
+                format.fmtp = Some(parameters.trim().to_string());
+            }
+        }
+        ("control", Some(control)) => media.control = Some(control.to_string()),
+        _ => {}
+    }
+    media.attributes.push((name, attribute));
+}
+
+fn format_mut<'a>(
+    media: &'a mut MediaDescription,
+    payload_type: &str,
+) -> Option<&'a mut MediaFormat> {
+    let payload_type: u8 = payload_type.trim().parse().ok()?;
+    media
+        .formats
+        .iter_mut()
+        .find(|format| format.payload_type == payload_type)
+}
@@ -0,0 +1,381 @@
+use crate::errors::errors::RtspError;
+use crate::rtsp::sdp::{Codec, MediaFormat};
+use base64::Engine;
+use std::fmt::Display;
+
+// 从SPS中解析出的视频参数
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VideoInfo {
+    pub codec: Codec,
+    pub width: u32,
+    pub height: u32,
+    pub profile_idc: u8,
+    pub level_idc: u8,
+    // H.264的constraint_set标志，用于区分Constrained Baseline等配置
+    pub constraint_flags: u8,
+}
+
+impl VideoInfo {
+    // 配置名称
+    pub fn profile(&self) -> String {
+        let name = match (&self.codec, self.profile_idc) {
+            (Codec::H264, 66) if self.constraint_flags & 0x40 != 0 => "Constrained Baseline",
+            (Codec::H264, 66) => "Baseline",
+            (Codec::H264, 77) => "Main",
+            (Codec::H264, 88) => "Extended",
+            (Codec::H264, 100) => "High",
+            (Codec::H264, 110) => "High 10",
+            (Codec::H264, 122) => "High 4:2:2",
+            (Codec::H264, 244) => "High 4:4:4",
+            (Codec::H265, 1) => "Main",
+            (Codec::H265, 2) => "Main 10",
+            (Codec::H265, 3) => "Main Still Picture",
+            (Codec::H265, 4) => "Range Extensions",
+            (_, profile_idc) => return format!("Profile {}", profile_idc),
+        };
+        name.to_string()
+    }
+
+    // 级别，H.264的level_idc为级别的10倍，H.265的general_level_idc为级别的30倍
+    pub fn level(&self) -> String {
+        match self.codec {
+            Codec::H265 => {
+                let level = self.level_idc as u32;
+                match (level % 30) / 3 {
+                    0 => format!("{}", level / 30),
+                    minor => format!("{}.{}", level / 30, minor),
+                }
+            }
+            // level_idc为11且constraint_set3为1时表示级别1b
+            _ if self.level_idc == 11 && self.constraint_flags & 0x10 != 0 => "1b".to_string(),
+            _ => format!("{}.{}", self.level_idc / 10, self.level_idc % 10),
+        }
+    }
+}
+
+impl Display for VideoInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{} {}@{}",
+            self.width,
+            self.height,
+            self.profile(),
+            self.level()
+        )
+    }
+}
+
+// 从SDP的fmtp参数中解析视频参数，H.264使用sprop-parameter-sets，H.265使用sprop-sps
+pub fn video_info_from_format(format: &MediaFormat) -> Option<VideoInfo> {
+    let result = match format.codec {
+        Codec::H264 => {
+            let sps = format
+                .fmtp_parameter("sprop-parameter-sets")?
+                .split(',')
+                .next()?;
+            decode_parameter_set(sps).and_then(|nal| parse_h264_sps(&nal))
+        }
+        Codec::H265 => decode_parameter_set(format.fmtp_parameter("sprop-sps")?)
+            .and_then(|nal| parse_h265_sps(&nal)),
+        _ => return None,
+    };
+    match result {
+        Ok(info) => Some(info),
+        Err(e) => {
+            log::debug!("Failed to parse SPS: {}", e);
+            None
+        }
+    }
+}
+
+fn decode_parameter_set(encoded: &str) -> Result<Vec<u8>, RtspError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| RtspError::ProtocolError(format!("Invalid parameter set encoding: {}", e)))
+}
+
+// 解析H.264的SPS NAL单元（包含1字节的NAL头）
+pub fn parse_h264_sps(nal: &[u8]) -> Result<VideoInfo, RtspError> {
+    if nal.first().map(|header| header & 0x1F) != Some(7) {
+        return Err(RtspError::ProtocolError(
+            "Not an H.264 SPS NAL unit".to_string(),
+        ));
+    }
+    let rbsp = remove_emulation_prevention(&nal[1..]);
+    let mut reader = BitReader::new(&rbsp);
+
+    let profile_idc = reader.read_bits(8)? as u8;
+    let constraint_flags = reader.read_bits(8)? as u8;
+    let level_idc = reader.read_bits(8)? as u8;
+    reader.read_ue()?; // seq_parameter_set_id
+
+    let mut chroma_format_idc = 1;
+    let mut separate_colour_plane = false;
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format_idc = reader.read_ue()?;
+        if chroma_format_idc == 3 {
+            separate_colour_plane = reader.read_bit()?;
+        }
+        reader.read_ue()?; // bit_depth_luma_minus8
+        reader.read_ue()?; // bit_depth_chroma_minus8
+        reader.read_bit()?; // qpprime_y_zero_transform_bypass_flag
+        if reader.read_bit()? {
+            // seq_scaling_matrix_present_flag
+            let count = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..count {
+                if reader.read_bit()? {
+                    skip_scaling_list(&mut reader, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+
+    reader.read_ue()?; // log2_max_frame_num_minus4
+    match reader.read_ue()? {
+        // pic_order_cnt_type
+        0 => {
+            reader.read_ue()?; // log2_max_pic_order_cnt_lsb_minus4
+        }
+        1 => {
+            reader.read_bit()?; // delta_pic_order_always_zero_flag
+            reader.read_se()?; // offset_for_non_ref_pic
+            reader.read_se()?; // offset_for_top_to_bottom_field
+            for _ in 0..reader.read_ue()? {
+                reader.read_se()?; // offset_for_ref_frame
+            }
+        }
+        _ => {}
+    }
+    reader.read_ue()?; // max_num_ref_frames
+    reader.read_bit()?; // gaps_in_frame_num_value_allowed_flag
+    let width_in_mbs = reader.read_ue()? + 1;
+    let height_in_map_units = reader.read_ue()? + 1;
+    let frame_mbs_only = reader.read_bit()?;
+    if !frame_mbs_only {
+        reader.read_bit()?; // mb_adaptive_frame_field_flag
+    }
+    reader.read_bit()?; // direct_8x8_inference_flag
+
+    let field_factor = if frame_mbs_only { 1 } else { 2 };
+    let mut width = checked_size(width_in_mbs.checked_mul(16))?;
+    let mut height = checked_size(
+        height_in_map_units
+            .checked_mul(16)
+            .and_then(|height| height.checked_mul(field_factor)),
+    )?;
+    if reader.read_bit()? {
+        // frame_cropping_flag，裁剪单位取决于色度格式
+        let (crop_unit_x, crop_unit_y) = if separate_colour_plane || chroma_format_idc == 0 {
+            (1, field_factor)
+        } else {
+            let (sub_width, sub_height) = chroma_subsampling(chroma_format_idc);
+            (sub_width, sub_height * field_factor)
+        };
+        let left = reader.read_ue()?;
+        let right = reader.read_ue()?;
+        let top = reader.read_ue()?;
+        let bottom = reader.read_ue()?;
+        width = width.saturating_sub(crop_size(left, right, crop_unit_x)?);
+        height = height.saturating_sub(crop_size(top, bottom, crop_unit_y)?);
+    }
+
+    Ok(VideoInfo {
+        codec: Codec::H264,
+        width,
+        height,
+        profile_idc,
+        level_idc,
+        constraint_flags,
+    })
+}
+
+// 解析H.265的SPS NAL单元（包含2字节的NAL头）
+pub fn parse_h265_sps(nal: &[u8]) -> Result<VideoInfo, RtspError> {
+    if nal.len() < 2 || (nal[0] >> 1) & 0x3F != 33 {
+        return Err(RtspError::ProtocolError(
+            "Not an H.265 SPS NAL unit".to_string(),
+        ));
+    }
+    let rbsp = remove_emulation_prevention(&nal[2..]);
+    let mut reader = BitReader::new(&rbsp);
+
+    reader.read_bits(4)?; // sps_video_parameter_set_id
+    let max_sub_layers_minus1 = reader.read_bits(3)?;
+    reader.read_bit()?; // sps_temporal_id_nesting_flag
+
+    // profile_tier_level
+    reader.read_bits(2)?; // general_profile_space
+    reader.read_bit()?; // general_tier_flag
+    let profile_idc = reader.read_bits(5)? as u8;
+    reader.skip_bits(32)?; // general_profile_compatibility_flags
+    reader.skip_bits(48)?; // general_progressive_source_flag等约束标志
+    let level_idc = reader.read_bits(8)? as u8;
+    let mut sub_layers = Vec::new();
+    for _ in 0..max_sub_layers_minus1 {
+        let profile_present = reader.read_bit()?;
+        let level_present = reader.read_bit()?;
+        sub_layers.push((profile_present, level_present));
+    }
+    if max_sub_layers_minus1 > 0 {
+        for _ in max_sub_layers_minus1..8 {
+            reader.read_bits(2)?; // reserved_zero_2bits
+        }
+    }
+    for (profile_present, level_present) in sub_layers {
+        if profile_present {
+            reader.skip_bits(88)?;
+        }
+        if level_present {
+            reader.skip_bits(8)?;
+        }
+    }
+
+    reader.read_ue()?; // sps_seq_parameter_set_id
+    let chroma_format_idc = reader.read_ue()?;
+    let mut separate_colour_plane = false;
+    if chroma_format_idc == 3 {
+        separate_colour_plane = reader.read_bit()?;
+    }
+    let mut width = reader.read_ue()?;
+    let mut height = reader.read_ue()?;
+    if reader.read_bit()? {
+        // conformance_window_flag
+        let (sub_width, sub_height) = if separate_colour_plane {
+            (1, 1)
+        } else {
+            chroma_subsampling(chroma_format_idc)
+        };
+        let left = reader.read_ue()?;
+        let right = reader.read_ue()?;
+        let top = reader.read_ue()?;
+        let bottom = reader.read_ue()?;
+        width = width.saturating_sub(crop_size(left, right, sub_width)?);
+        height = height.saturating_sub(crop_size(top, bottom, sub_height)?);
+    }
+
+    Ok(VideoInfo {
+        codec: Codec::H265,
+        width,
+        height,
+        profile_idc,
+        level_idc,
+        constraint_flags: 0,
+    })
+}
+
+// 尺寸计算结果，SPS来自不可信的设备，构造的极大值会使计算溢出
+fn checked_size(size: Option<u32>) -> Result<u32, RtspError> {
+    size.ok_or_else(|| RtspError::ProtocolError("SPS picture size overflows".to_string()))
+}
+
+// 两侧裁剪的总像素数：(start + end) * unit
+fn crop_size(start: u32, end: u32, unit: u32) -> Result<u32, RtspError> {
+    checked_size(start.checked_add(end).and_then(|crop| crop.checked_mul(unit)))
+}
+
+// 色度格式对应的水平和垂直子采样系数
+fn chroma_subsampling(chroma_format_idc: u32) -> (u32, u32) {
+    match chroma_format_idc {
+        1 => (2, 2),
+        2 => (2, 1),
+        _ => (1, 1),
+    }
+}
+
+fn skip_scaling_list(reader: &mut BitReader, size: usize) -> Result<(), RtspError> {
+    let mut last_scale = 8i64;
+    let mut next_scale = 8i64;
+    for _ in 0..size {
+        if next_scale != 0 {
+            let delta_scale = reader.read_se()?;
+            next_scale = (last_scale + delta_scale + 256) % 256;
+        }
+        if next_scale != 0 {
+            last_scale = next_scale;
+        }
+    }
+    Ok(())
+}
+
+// 去除防竞争字节（00 00 03中的03），得到RBSP
+pub fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &byte in data {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        rbsp.push(byte);
+    }
+    rbsp
+}
+
+// 按位读取器，支持指数哥伦布编码
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader { data, position: 0 }
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, RtspError> {
+        let byte = self.data.get(self.position / 8).ok_or_else(|| {
+            RtspError::ProtocolError("Unexpected end of parameter set".to_string())
+        })?;
+        let bit = (byte >> (7 - self.position % 8)) & 1;
+        self.position += 1;
+        Ok(bit == 1)
+    }
+
+    pub fn read_bits(&mut self, count: u32) -> Result<u32, RtspError> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit()? as u32;
+        }
+        Ok(value)
+    }
+
+    pub fn skip_bits(&mut self, count: usize) -> Result<(), RtspError> {
+        if self.position + count > self.data.len() * 8 {
+            return Err(RtspError::ProtocolError(
+                "Unexpected end of parameter set".to_string(),
+            ));
+        }
+        self.position += count;
+        Ok(())
+    }
+
+    // 无符号指数哥伦布编码 ue(v)
+    pub fn read_ue(&mut self) -> Result<u32, RtspError> {
+        let mut leading_zeros = 0;
+        while !self.read_bit()? {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return Err(RtspError::ProtocolError(
+                    "Invalid Exp-Golomb code".to_string(),
+                ));
+            }
+        }
+        let suffix = self.read_bits(leading_zeros)?;
+        Ok(((1u64 << leading_zeros) - 1 + suffix as u64) as u32)
+    }
+
+    // 有符号指数哥伦布编码 se(v)
+    pub fn read_se(&mut self) -> Result<i64, RtspError> {
+        let code = self.read_ue()? as i64;
+        Ok(if code % 2 == 1 {
+            (code + 1) / 2
+        } else {
+            -(code / 2)
+        })
+    }
+}
@@ -0,0 +1,236 @@
+use crate::errors::errors::RtspError;
+use crate::iterator::ip_iterator::IpPortAddr;
+use crate::rtsp::http_tunnel::HttpTunnel;
+use lazy_static::lazy_static;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::TlsConnector;
+
+// RTSPS的默认端口
+pub const DEFAULT_RTSPS_PORT: u16 = 322;
+// 默认使用TLS连接的端口
+pub const DEFAULT_TLS_PORTS: &[u16] = &[322, 8322];
+
+// RTSP传输层 - 明文TCP或TLS，连接只依赖异步读写
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+// 连接目标使用的传输方式
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TransportKind {
+    Tcp,
+    Tls(TlsOptions),
+    // RTSP over HTTP隧道，参数为隧道使用的HTTP路径
+    HttpTunnel(String),
+}
+
+// TLS选项
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TlsOptions {
+    // 接受自签名或无效的服务器证书，摄像头通常使用自签名证书
+    pub accept_invalid_certs: bool,
+    // 这些端口上的目标使用rtsps://
+    pub ports: Vec<u16>,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        TlsOptions {
+            accept_invalid_certs: false,
+            ports: DEFAULT_TLS_PORTS.to_vec(),
+        }
+    }
+}
+
+impl TlsOptions {
+    // 目标使用的URL协议
+    pub fn scheme(&self, addr: &IpPortAddr) -> &'static str {
+        if self.ports.contains(&addr.port) {
+            "rtsps"
+        } else {
+            "rtsp"
+        }
+    }
+}
+
+// URL是否使用TLS
+pub fn is_tls_url(url: &str) -> bool {
+    url.get(..8)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("rtsps://"))
+}
+
+// TLS服务器证书信息
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CertificateInfo {
+    pub subject: String,
+    // SHA-256指纹，以冒号分隔的大写十六进制
+    pub fingerprint: String,
+}
+
+impl CertificateInfo {
+    // 从DER编码的证书中解析主题并计算指纹
+    pub fn from_der(der: &[u8]) -> Self {
+        let subject = match x509_parser::parse_x509_certificate(der) {
+            Ok((_, certificate)) => certificate.subject().to_string(),
+            Err(e) => {
+                log::debug!("Failed to parse server certificate: {}", e);
+                String::new()
+            }
+        };
+        let fingerprint = Sha256::digest(der)
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect::<Vec<_>>()
+            .join(":");
+        CertificateInfo {
+            subject,
+            fingerprint,
+        }
+    }
+}
+
+impl Display for CertificateInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (SHA256 {})", self.subject, self.fingerprint)
+    }
+}
+
+// 不验证服务器证书，仍然检查握手签名
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn build_tls_config(accept_invalid_certs: bool) -> Arc<ClientConfig> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("ring provider supports the default TLS versions");
+    let config = if accept_invalid_certs {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth()
+    } else {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+    Arc::new(config)
+}
+
+// 创建全局TLS配置，分别用于验证和不验证服务器证书
+lazy_static! {
+    static ref VERIFYING_TLS_CONFIG: Arc<ClientConfig> = build_tls_config(false);
+    static ref ACCEPTING_TLS_CONFIG: Arc<ClientConfig> = build_tls_config(true);
+}
+
+// 按传输方式建立连接，TLS连接同时返回服务器证书信息
+pub async fn connect(
+    addr: &IpPortAddr,
+    kind: &TransportKind,
+) -> Result<(Box<dyn Transport>, Option<CertificateInfo>), RtspError> {
+    match kind {
+        TransportKind::Tcp => Ok((Box::new(addr.connect().await?), None)),
+        TransportKind::Tls(tls) => connect_tls(addr, tls).await,
+        TransportKind::HttpTunnel(path) => {
+            Ok((Box::new(HttpTunnel::connect(addr, path).await?), None))
+        }
+    }
+}
+
+// 建立TLS连接
+async fn connect_tls(
+    addr: &IpPortAddr,
+    tls: &TlsOptions,
+) -> Result<(Box<dyn Transport>, Option<CertificateInfo>), RtspError> {
+    let stream = addr.connect().await?;
+
+    let config = if tls.accept_invalid_certs {
+        ACCEPTING_TLS_CONFIG.clone()
+    } else {
+        VERIFYING_TLS_CONFIG.clone()
+    };
+    // 域名目标使用域名作为SNI并校验证书中的域名
+    let server_name = match addr
+        .hostname
+        .as_deref()
+        .and_then(|hostname| ServerName::try_from(hostname.to_string()).ok())
+    {
+        Some(server_name) => server_name,
+        None => ServerName::IpAddress(addr.ip.into()),
+    };
+    let stream = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        TlsConnector::from(config).connect(server_name, stream),
+    )
+    .await
+    .map_err(|_| RtspError::ConnectionError("TLS handshake timeout".to_string()))?
+    .map_err(|e| RtspError::ConnectionError(format!("TLS handshake failed: {}", e)))?;
+
+    let certificate = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .map(|certificate| CertificateInfo::from_der(certificate));
+    if let Some(certificate) = &certificate {
+        log::debug!(
+            "TLS connection to {} established, certificate: {}",
+            addr,
+            certificate
+        );
+    }
+    Ok((Box::new(stream), certificate))
+}
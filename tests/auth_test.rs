@@ -1,7 +1,10 @@
 use md5::Digest;
+use rstest::rstest;
 use rust_rtsp_bruter::rtsp::auth::{
-    AuthType, DigestAuthInfo, generate_auth_header, parse_auth_challenge,
+    AuthPolicy, AuthType, Authenticator, DigestAlgorithm, DigestAuthInfo, generate_auth_header,
+    parse_auth_challenge, parse_challenges, parse_response_challenges, select_auth_types,
 };
+use rust_rtsp_bruter::rtsp::common::RtspResponse;
 
 // 测试Digest认证响应生成
 #[test]
@@ -37,6 +40,8 @@ fn test_digest_auth_response_generation() {
         qop: None,
         algorithm: None,
         opaque: None,
+        userhash: false,
+        stale: false,
     });
 
     // 生成认证头
@@ -89,3 +94,277 @@ fn test_full_auth_flow_parsing() {
     assert!(auth_header.contains(&format!("uri=\"{}\"", path)));
     assert!(auth_header.contains("response=\""));
 }
+
+// 测试RFC 2617中qop=auth的示例
+#[test]
+fn test_digest_qop_auth_rfc2617_example() {
+    let auth_type = AuthType::Digest(DigestAuthInfo {
+        realm: "testrealm@host.com".to_string(),
+        nonce: "dcd98b7102dd2f0e8b11d0f600bfb0c093".to_string(),
+        qop: Some("auth,auth-int".to_string()),
+        algorithm: None,
+        opaque: Some("5ccc069c403ebaf9f0171e9517f40e41".to_string()),
+        userhash: false,
+        stale: false,
+    });
+
+    let mut authenticator =
+        Authenticator::new("Mufasa", "Circle Of Life").with_cnonce("0a4f113b");
+    let auth_header = authenticator
+        .authorization(&auth_type, "GET", "/dir/index.html", b"")
+        .unwrap();
+
+    assert!(auth_header.contains("response=\"6629fae49393a05397450978507c4ef1\""));
+    assert!(auth_header.contains("qop=auth,"));
+    assert!(auth_header.contains("nc=00000001"));
+    assert!(auth_header.contains("cnonce=\"0a4f113b\""));
+    assert!(auth_header.contains("opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""));
+}
+
+// 测试同一连接上nonce计数递增，nonce变化后重置
+#[test]
+fn test_digest_nonce_count_tracking() {
+    let digest = |nonce: &str| {
+        AuthType::Digest(DigestAuthInfo {
+            realm: "RTSP SERVER".to_string(),
+            nonce: nonce.to_string(),
+            qop: Some("auth".to_string()),
+            algorithm: None,
+            opaque: None,
+            userhash: false,
+            stale: false,
+        })
+    };
+
+    let mut authenticator = Authenticator::new("admin", "123456");
+    let first = authenticator
+        .authorization(&digest("n1"), "DESCRIBE", "rtsp://10.0.0.1:554/", b"")
+        .unwrap();
+    let second = authenticator
+        .authorization(&digest("n1"), "DESCRIBE", "rtsp://10.0.0.1:554/", b"")
+        .unwrap();
+    let third = authenticator
+        .authorization(&digest("n2"), "DESCRIBE", "rtsp://10.0.0.1:554/", b"")
+        .unwrap();
+
+    assert!(first.contains("nc=00000001"));
+    assert!(second.contains("nc=00000002"));
+    assert!(third.contains("nc=00000001"));
+}
+
+// 测试仅提供auth-int时的认证头
+#[test]
+fn test_digest_qop_auth_int() {
+    let auth_type = AuthType::Digest(DigestAuthInfo {
+        realm: "RTSP SERVER".to_string(),
+        nonce: "abcdef".to_string(),
+        qop: Some("auth-int".to_string()),
+        algorithm: None,
+        opaque: None,
+        userhash: false,
+        stale: false,
+    });
+
+    let mut authenticator = Authenticator::new("admin", "123456").with_cnonce("0011");
+    let with_body = authenticator
+        .authorization(&auth_type, "SET_PARAMETER", "rtsp://10.0.0.1/", b"x: y\r\n")
+        .unwrap();
+    let mut authenticator = Authenticator::new("admin", "123456").with_cnonce("0011");
+    let without_body = authenticator
+        .authorization(&auth_type, "SET_PARAMETER", "rtsp://10.0.0.1/", b"")
+        .unwrap();
+
+    assert!(with_body.contains("qop=auth-int"));
+    // 消息体参与摘要计算
+    assert_ne!(with_body, without_body);
+}
+
+// 测试RFC 7616中的MD5和SHA-256示例
+#[rstest]
+#[case(Some("MD5"), "8ca523f5e9506fed4657c9700eebdbec")]
+#[case(Some("SHA-256"), "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1")]
+fn test_digest_algorithm_rfc7616_example(
+    #[case] algorithm: Option<&str>,
+    #[case] expected_response: &str,
+) {
+    let auth_type = AuthType::Digest(DigestAuthInfo {
+        realm: "http-auth@example.org".to_string(),
+        nonce: "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v".to_string(),
+        qop: Some("auth".to_string()),
+        algorithm: algorithm.map(|name| name.to_string()),
+        opaque: Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS".to_string()),
+        userhash: false,
+        stale: false,
+    });
+
+    let mut authenticator = Authenticator::new("Mufasa", "Circle of Life")
+        .with_cnonce("f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ");
+    let auth_header = authenticator
+        .authorization(&auth_type, "GET", "/dir/index.html", b"")
+        .unwrap();
+
+    assert!(auth_header.contains(&format!("response=\"{}\"", expected_response)));
+    assert!(auth_header.contains(&format!("algorithm={}", algorithm.unwrap())));
+}
+
+// 测试算法名称解析
+#[rstest]
+#[case("MD5", Some(DigestAlgorithm::Md5))]
+#[case("md5-sess", Some(DigestAlgorithm::Md5Sess))]
+#[case("SHA-256", Some(DigestAlgorithm::Sha256))]
+#[case("SHA-256-sess", Some(DigestAlgorithm::Sha256Sess))]
+#[case("SHA-512-256", Some(DigestAlgorithm::Sha512_256))]
+#[case("SHA-1", None)]
+fn test_digest_algorithm_parse(#[case] name: &str, #[case] expected: Option<DigestAlgorithm>) {
+    assert_eq!(DigestAlgorithm::parse(name), expected);
+}
+
+// 测试多个Digest挑战时选择最强的算法
+#[test]
+fn test_strongest_digest_challenge_selected() {
+    let response = "RTSP/1.0 401 Unauthorized\r\n".to_string()
+        + "CSeq: 1\r\n"
+        + "WWW-Authenticate: Digest realm=\"NVR\", nonce=\"aaa\", algorithm=MD5\r\n"
+        + "WWW-Authenticate: Digest realm=\"NVR\", nonce=\"bbb\", algorithm=SHA-256\r\n"
+        + "WWW-Authenticate: Digest realm=\"NVR\", nonce=\"ccc\", algorithm=SHA-1\r\n"
+        + "WWW-Authenticate: Basic realm=\"NVR\"\r\n\r\n";
+
+    match parse_auth_challenge(&response).unwrap() {
+        AuthType::Digest(info) => {
+            assert_eq!(info.nonce, "bbb");
+            assert_eq!(info.digest_algorithm(), Some(DigestAlgorithm::Sha256));
+        }
+        other => panic!("Expected Digest authentication type, got {:?}", other),
+    }
+}
+
+// 测试userhash和会话算法
+#[test]
+fn test_digest_userhash_and_session_algorithm() {
+    let realm = "MediaMTX";
+    let auth_type = AuthType::Digest(DigestAuthInfo {
+        realm: realm.to_string(),
+        nonce: "n0nce".to_string(),
+        qop: Some("auth".to_string()),
+        algorithm: Some("SHA-256-sess".to_string()),
+        opaque: None,
+        userhash: true,
+        stale: false,
+    });
+
+    let mut authenticator = Authenticator::new("admin", "admin").with_cnonce("c0ffee");
+    let auth_header = authenticator
+        .authorization(&auth_type, "DESCRIBE", "rtsp://10.0.0.1:8554/cam", b"")
+        .unwrap();
+
+    let hashed_username = DigestAlgorithm::Sha256.hash(&format!("admin:{}", realm));
+    assert!(auth_header.contains(&format!("username=\"{}\"", hashed_username)));
+    assert!(auth_header.contains("userhash=true"));
+    assert!(auth_header.contains("algorithm=SHA-256-sess"));
+    assert!(!auth_header.contains("username=\"admin\""));
+}
+
+// 测试带引号逗号的参数和同一头部中的多个挑战
+#[test]
+fn test_parse_challenges_quoted_commas() {
+    let challenges = parse_challenges(
+        "basic realm=\"Cam, Inc\", Digest realm=\"Cam, Inc\", qop=\"auth,auth-int\", nonce=\"a\\\"b\", algorithm=MD5",
+    );
+    assert_eq!(challenges.len(), 2);
+    assert!(challenges[0].is_scheme("Basic"));
+    assert_eq!(challenges[0].param("realm"), Some("Cam, Inc"));
+    assert!(challenges[1].is_scheme("Digest"));
+    assert_eq!(challenges[1].param("qop"), Some("auth,auth-int"));
+    assert_eq!(challenges[1].param("nonce"), Some("a\"b"));
+    assert_eq!(challenges[1].param("ALGORITHM"), Some("MD5"));
+}
+
+// 测试未加引号且包含'='的参数值
+#[test]
+fn test_parse_challenges_unquoted_base64_nonce() {
+    let challenges = parse_challenges("Digest realm=cam, nonce=YWJjZA==, stale=FALSE");
+    assert_eq!(challenges.len(), 1);
+    assert_eq!(challenges[0].param("nonce"), Some("YWJjZA=="));
+    assert_eq!(challenges[0].param("stale"), Some("FALSE"));
+}
+
+// 测试认证方案选择策略
+#[rstest]
+#[case(AuthPolicy::PreferDigest, vec!["Digest"])]
+#[case(AuthPolicy::PreferBasic, vec!["Basic"])]
+#[case(AuthPolicy::TryBoth, vec!["Digest", "Basic"])]
+fn test_select_auth_types(#[case] policy: AuthPolicy, #[case] expected: Vec<&str>) {
+    let raw = "RTSP/1.0 401 Unauthorized\r\n".to_string()
+        + "WWW-Authenticate: Basic realm=\"IPC\"\r\n"
+        + "WWW-Authenticate: Digest realm=\"IPC\", nonce=\"123\"\r\n\r\n";
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    let offered = parse_response_challenges(&response).unwrap();
+
+    let schemes: Vec<&str> = select_auth_types(offered, policy)
+        .iter()
+        .map(|auth_type| auth_type.scheme())
+        .collect();
+    assert_eq!(schemes, expected);
+
+    match &parse_response_challenges(&response).unwrap()[1] {
+        AuthType::Basic(info) => assert_eq!(info.realm.as_deref(), Some("IPC")),
+        other => panic!("Expected Basic authentication type, got {:?}", other),
+    }
+}
+
+// 测试仅声明Digest时try-both仍会尝试Basic
+#[test]
+fn test_try_both_adds_basic() {
+    let raw = "RTSP/1.0 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"IPC\", nonce=\"1\"\r\n\r\n";
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    let offered = parse_response_challenges(&response).unwrap();
+    assert_eq!(offered.len(), 1);
+
+    let schemes: Vec<&str> = select_auth_types(offered.clone(), AuthPolicy::TryBoth)
+        .iter()
+        .map(|auth_type| auth_type.scheme())
+        .collect();
+    assert_eq!(schemes, vec!["Digest", "Basic"]);
+    assert_eq!(select_auth_types(offered, AuthPolicy::PreferBasic).len(), 1);
+}
+
+// 测试解析stale参数
+#[rstest]
+#[case("stale=TRUE", true)]
+#[case("stale=\"true\"", true)]
+#[case("stale=false", false)]
+#[case("opaque=\"x\"", false)]
+fn test_parse_digest_stale(#[case] param: &str, #[case] expected: bool) {
+    let raw = format!(
+        "RTSP/1.0 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"cam\", nonce=\"n2\", {}\r\n\r\n",
+        param
+    );
+    match parse_auth_challenge(&raw).unwrap() {
+        AuthType::Digest(info) => assert_eq!(info.stale, expected),
+        other => panic!("Expected Digest authentication type, got {:?}", other),
+    }
+}
+
+// 测试用户名中的引号和反斜杠在Digest认证头中被转义
+#[test]
+fn test_digest_escapes_quoted_username() {
+    let auth_type = AuthType::Digest(DigestAuthInfo {
+        realm: "RTSP SERVER".to_string(),
+        nonce: "72fb3f3f23ded5a9d8f9be5a4535bf84".to_string(),
+        qop: None,
+        algorithm: None,
+        opaque: None,
+        userhash: false,
+        stale: false,
+    });
+    let username = "ad\"min\\";
+    let header =
+        generate_auth_header(&auth_type, username, "123456", "DESCRIBE", "rtsp://10.0.0.1/").unwrap();
+    assert!(header.starts_with("Digest username=\"ad\\\"min\\\\\", realm=\"RTSP SERVER\""));
+
+    // 服务器解析出的用户名与原始用户名一致
+    let challenges = parse_challenges(&header);
+    assert_eq!(challenges.len(), 1);
+    assert_eq!(challenges[0].param("username"), Some(username));
+    assert_eq!(challenges[0].param("uri"), Some("rtsp://10.0.0.1/"));
+}
@@ -0,0 +1,264 @@
+mod common;
+
+use common::{MockReply, MockRequest, MockRtspServer, basic_auth_handler};
+use rust_rtsp_bruter::brute::{BruteForcer, StopPolicy};
+use rust_rtsp_bruter::errors::errors::{AuthenticationResult, RtspError};
+use rust_rtsp_bruter::iterator::credential_iterator::CredentialIterator;
+use rust_rtsp_bruter::iterator::ip_iterator::{IpIterator, IpPortAddr};
+use rust_rtsp_bruter::rtsp::rtsp_worker::RTSP_WORKER_MANAGER;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+// 接受多个账户的服务器：admin:admin、operator:operator、operator:1234、viewer:viewer
+fn multi_account_handler(request: &MockRequest) -> MockReply {
+    match request.header("Authorization") {
+        Some(
+            "Basic YWRtaW46YWRtaW4="
+            | "Basic b3BlcmF0b3I6b3BlcmF0b3I="
+            | "Basic b3BlcmF0b3I6MTIzNA=="
+            | "Basic dmlld2VyOnZpZXdlcg==",
+        ) => MockReply::new(
+            request,
+            "200 OK",
+            &["Content-Type: application/sdp"],
+            "v=0\r\ns=Mock\r\n",
+        ),
+        _ => basic_auth_handler(request),
+    }
+}
+
+// 按用户名和密码排序的已找到凭据
+fn found_accounts(brute_forcer: &BruteForcer) -> Vec<String> {
+    let mut accounts: Vec<String> = brute_forcer
+        .found_credentials()
+        .iter()
+        .map(|cred| format!("{}:{}", cred.username, cred.password))
+        .collect();
+    accounts.sort();
+    accounts
+}
+
+fn ip_port(server: &MockRtspServer) -> IpPortAddr {
+    IpPortAddr::new(server.addr.ip(), server.addr.port())
+}
+
+// 测试暴力枚举器根据不同的响应跳过目标、退避重试或切换流路径
+// 全局工作线程池绑定在测试运行时上，因此所有场景放在同一个测试中
+#[tokio::test(flavor = "multi_thread")]
+async fn test_brute_forcer_reacts_to_outcomes() {
+    RTSP_WORKER_MANAGER.start().await;
+    let brute_forcer = BruteForcer::new();
+
+    // 403：跳过目标，后续的凭据作为已取消处理，不再尝试
+    let forbidden =
+        MockRtspServer::start(|request| MockReply::new(request, "403 Forbidden", &[], "")).await;
+    let target = ip_port(&forbidden);
+    let found = brute_forcer
+        .try_credentials("admin", "1", &target, "")
+        .await
+        .unwrap();
+    assert!(found.is_none());
+    assert!(matches!(
+        brute_forcer.try_credentials("admin", "2", &target, "").await,
+        Err(RtspError::Cancelled)
+    ));
+    assert_eq!(forbidden.requests(), 1);
+    assert!(brute_forcer.host_token(&target).is_cancelled());
+    assert_eq!(
+        brute_forcer.skipped_hosts().get(&target),
+        Some(&AuthenticationResult::Forbidden)
+    );
+
+    // 503：按照Retry-After退避后重试
+    let unavailable = Arc::new(AtomicUsize::new(0));
+    let unavailable_clone = unavailable.clone();
+    let busy = MockRtspServer::start(move |request| {
+        if unavailable_clone.fetch_add(1, Ordering::SeqCst) == 0 {
+            MockReply::new(request, "503 Service Unavailable", &["Retry-After: 0"], "")
+        } else {
+            basic_auth_handler(request)
+        }
+    })
+    .await;
+    let found = brute_forcer
+        .try_credentials("admin", "admin", &ip_port(&busy), "")
+        .await
+        .unwrap();
+    assert!(found.is_some());
+
+    // 404：探测常见的流路径并切换到存在的路径
+    let camera = MockRtspServer::start(|request| {
+        if request.url.ends_with("/live.sdp") {
+            basic_auth_handler(request)
+        } else {
+            MockReply::new(request, "404 Not Found", &[], "")
+        }
+    })
+    .await;
+    let found = brute_forcer
+        .try_credentials("admin", "admin", &ip_port(&camera), "")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.path, "/live.sdp");
+
+    // 302：跟随重定向并记录最终的URL
+    let stream = MockRtspServer::start(basic_auth_handler).await;
+    let location = format!("Location: {}", stream.url("/stream"));
+    let nvr = MockRtspServer::start(move |request| {
+        MockReply::new(request, "302 Moved Temporarily", &[&location], "")
+    })
+    .await;
+    let found = brute_forcer
+        .try_credentials("admin", "admin", &ip_port(&nvr), "/")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.ip_port, ip_port(&nvr));
+    assert_eq!(found.final_url, Some(stream.url("/stream")));
+
+    // 流水线：跳过无法连接的目标，找到凭据后不再尝试该目标剩余的凭据
+    let camera = MockRtspServer::start(basic_auth_handler).await;
+    let closed = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+    let unreachable = IpPortAddr::new(closed.ip(), closed.port());
+    let passwords = std::iter::once("admin".to_string())
+        .chain((0..50).map(|i| format!("wrong{}", i)))
+        .collect();
+    let pipeline = BruteForcer::new()
+        .with_max_concurrent(1)
+        .with_ip_iterator(IpIterator::new(vec![unreachable.clone(), ip_port(&camera)]))
+        .with_cred_iterator(CredentialIterator::new(vec!["admin".to_string()], passwords));
+    pipeline.brute_force().await.unwrap();
+    assert!(pipeline.has_valid_credentials_for_ip(&ip_port(&camera)));
+    assert!(!pipeline.has_valid_credentials_for_ip(&unreachable));
+    assert_eq!(camera.requests(), 2);
+
+    // 端口检查的并发数单独配置，可连接的目标按检查完成的顺序进入凭据阶段
+    let cameras = [
+        MockRtspServer::start(basic_auth_handler).await,
+        MockRtspServer::start(basic_auth_handler).await,
+    ];
+    let targets = vec![
+        unreachable.clone(),
+        ip_port(&cameras[0]),
+        unreachable.clone(),
+        ip_port(&cameras[1]),
+    ];
+    let pipeline = BruteForcer::new()
+        .with_max_concurrent(2)
+        .with_connect_concurrency(Some(8))
+        .with_ip_iterator(IpIterator::new(targets))
+        .with_cred_iterator(CredentialIterator::new(
+            vec!["root".to_string(), "admin".to_string()],
+            vec!["admin".to_string()],
+        ));
+    pipeline.brute_force().await.unwrap();
+    for camera in &cameras {
+        assert!(pipeline.has_valid_credentials_for_ip(&ip_port(camera)));
+    }
+
+    // 生产者在可连接的目标之间轮流生成尝试，不会先用完一个目标的所有凭据
+    let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut cameras = Vec::new();
+    for name in ['a', 'b'] {
+        let order = order.clone();
+        cameras.push(
+            MockRtspServer::start(move |request| {
+                order.lock().unwrap().push(name);
+                basic_auth_handler(request)
+            })
+            .await,
+        );
+    }
+    let passwords = (0..10)
+        .map(|i| format!("wrong{}", i))
+        .chain(std::iter::once("admin".to_string()))
+        .collect();
+    let pipeline = BruteForcer::new()
+        .with_max_concurrent(1)
+        .with_connect_concurrency(Some(4))
+        .with_ip_iterator(IpIterator::new(cameras.iter().map(ip_port).collect()))
+        .with_cred_iterator(CredentialIterator::new(vec!["admin".to_string()], passwords));
+    pipeline.brute_force().await.unwrap();
+    for camera in &cameras {
+        assert!(pipeline.has_valid_credentials_for_ip(&ip_port(camera)));
+    }
+    let order = order.lock().unwrap().clone();
+    let first_b = order.iter().position(|name| *name == 'b').unwrap();
+    let last_a = order.iter().rposition(|name| *name == 'a').unwrap();
+    assert!(first_b < last_a, "{:?}", order);
+    // 目标的尝试全部完成后不再保留其状态
+    assert_eq!(pipeline.active_hosts(), 0);
+
+    // 取消目标时正在等待响应的尝试立即结束
+    let silent = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = silent.local_addr().unwrap();
+    let target = IpPortAddr::new(addr.ip(), addr.port());
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = silent.accept().await {
+            connections.push(stream);
+        }
+    });
+    let attempt = tokio::spawn({
+        let brute_forcer = brute_forcer.clone();
+        let target = target.clone();
+        async move { brute_forcer.try_credentials("admin", "admin", &target, "").await }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!attempt.is_finished());
+    brute_forcer.host_token(&target).cancel();
+    let result = tokio::time::timeout(Duration::from_secs(1), attempt)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(result, Err(RtspError::Cancelled)));
+
+    // 找到凭据后继续测试：记录目标上所有有效的账户，按用户名停止时每个用户名只记录第一个密码
+    let camera = MockRtspServer::start(multi_account_handler).await;
+    let credentials = CredentialIterator::new(
+        vec!["admin".to_string(), "operator".to_string(), "viewer".to_string()],
+        vec![
+            "admin".to_string(),
+            "operator".to_string(),
+            "1234".to_string(),
+            "viewer".to_string(),
+        ],
+    );
+    let all = BruteForcer::new()
+        .with_max_concurrent(1)
+        .with_stop_policy(StopPolicy::Never)
+        .with_ip_iterator(IpIterator::new(vec![ip_port(&camera)]))
+        .with_cred_iterator(credentials.clone());
+    all.brute_force().await.unwrap();
+    assert_eq!(
+        found_accounts(&all),
+        vec!["admin:admin", "operator:1234", "operator:operator", "viewer:viewer"]
+    );
+    assert_eq!(all.active_hosts(), 0);
+
+    // 重复的目标共享状态，最后一次出现的尝试完成后才释放
+    let duplicated = BruteForcer::new()
+        .with_max_concurrent(2)
+        .with_ip_iterator(IpIterator::new(vec![ip_port(&camera), ip_port(&camera)]))
+        .with_cred_iterator(CredentialIterator::new(
+            vec!["admin".to_string()],
+            vec!["1".to_string(), "2".to_string(), "admin".to_string()],
+        ));
+    duplicated.brute_force().await.unwrap();
+    assert_eq!(found_accounts(&duplicated), vec!["admin:admin"]);
+    assert_eq!(duplicated.active_hosts(), 0);
+
+    let per_username = BruteForcer::new()
+        .with_max_concurrent(1)
+        .with_stop_policy(StopPolicy::PerUsername)
+        .with_ip_iterator(IpIterator::new(vec![ip_port(&camera)]))
+        .with_cred_iterator(credentials);
+    per_username.brute_force().await.unwrap();
+    assert_eq!(
+        found_accounts(&per_username),
+        vec!["admin:admin", "operator:operator", "viewer:viewer"]
+    );
+}
@@ -1,7 +1,9 @@
+use clap::Parser;
 use rand::Rng;
 use rust_rtsp_bruter::cli::cli::parse_brute_args;
-use rust_rtsp_bruter::config::config::Cli;
+use rust_rtsp_bruter::config::config::{AppConfig, Cli, ScanOptions};
 use std::io::Write;
+use std::path::PathBuf;
 
 // 临时文件结构体，实现Drop特性自动删除文件
 struct TempFile {
@@ -38,8 +40,8 @@ fn create_temp_file(content: &str) -> TempFile {
 }
 
 // 测试有效的参数组合：文件+文件
-#[test]
-fn test_parse_brute_args_files_files() {
+#[tokio::test]
+async fn test_parse_brute_args_files_files() {
     let users_file = create_temp_file("user1\nuser2");
     let passwords_file = create_temp_file("pass1\npass2");
     let ips_file = create_temp_file("192.168.1.1\n192.168.1.2");
@@ -52,12 +54,13 @@ fn test_parse_brute_args_files_files() {
         ips_file: Some(ips_file.path().to_string()),
         ips_string: None,
         max_concurrent: 5,
+        options: ScanOptions::default(),
     };
 
     // 我们不能真正执行brute_force，所以这里只测试参数解析
     // 实际测试中，我们会使用mock对象来模拟BruteForcer
-    match parse_brute_args(cli) {
-        Ok(_) => assert!(true),
+    match parse_brute_args(cli).await {
+        Ok(_) => {}
         Err(e) => {
             panic!("Failed to parse brute args: {}", e);
         }
@@ -65,8 +68,8 @@ fn test_parse_brute_args_files_files() {
 }
 
 // 测试有效的参数组合：文件+字符串
-#[test]
-fn test_parse_brute_args_files_string() {
+#[tokio::test]
+async fn test_parse_brute_args_files_string() {
     let users_file = create_temp_file("user1\nuser2");
     let password = "password123".to_string();
     let ips_file = create_temp_file("192.168.1.1");
@@ -79,10 +82,11 @@ fn test_parse_brute_args_files_string() {
         ips_file: Some(ips_file.path().to_string()),
         ips_string: None,
         max_concurrent: 5,
+        options: ScanOptions::default(),
     };
 
-    match parse_brute_args(cli) {
-        Ok(_) => assert!(true),
+    match parse_brute_args(cli).await {
+        Ok(_) => {}
         Err(e) => {
             panic!("Failed to parse brute args: {}", e);
         }
@@ -90,8 +94,8 @@ fn test_parse_brute_args_files_string() {
 }
 
 // 测试有效的参数组合：字符串+文件
-#[test]
-fn test_parse_brute_args_string_files() {
+#[tokio::test]
+async fn test_parse_brute_args_string_files() {
     let username = "admin".to_string();
     let passwords_file = create_temp_file("pass1\npass2");
     let ips_string = "127.0.0.1".to_string();
@@ -104,10 +108,11 @@ fn test_parse_brute_args_string_files() {
         ips_file: None,
         ips_string: Some(ips_string),
         max_concurrent: 5,
+        options: ScanOptions::default(),
     };
 
-    match parse_brute_args(cli) {
-        Ok(_) => assert!(true),
+    match parse_brute_args(cli).await {
+        Ok(_) => {}
         Err(e) => {
             panic!("Failed to parse brute args: {}", e);
         }
@@ -115,8 +120,8 @@ fn test_parse_brute_args_string_files() {
 }
 
 // 测试有效的参数组合：字符串+字符串
-#[test]
-fn test_parse_brute_args_string_string() {
+#[tokio::test]
+async fn test_parse_brute_args_string_string() {
     let username = "admin".to_string();
     let password = "password".to_string();
     let ips_string = "127.0.0.1".to_string();
@@ -129,14 +134,15 @@ fn test_parse_brute_args_string_string() {
         ips_file: None,
         ips_string: Some(ips_string),
         max_concurrent: 5,
+        options: ScanOptions::default(),
     };
 
-    assert!(parse_brute_args(cli).is_ok());
+    assert!(parse_brute_args(cli).await.is_ok());
 }
 
 // 测试无效的参数组合：同时提供ips_file和ips_string
-#[test]
-fn test_parse_brute_args_invalid_ip_source() {
+#[tokio::test]
+async fn test_parse_brute_args_invalid_ip_source() {
     let users_file = create_temp_file("user1");
     let passwords_file = create_temp_file("pass1");
     let ips_file = create_temp_file("192.168.1.1");
@@ -150,17 +156,18 @@ fn test_parse_brute_args_invalid_ip_source() {
         ips_file: Some(ips_file.path().to_string()),
         ips_string: Some(ips_string),
         max_concurrent: 5,
+        options: ScanOptions::default(),
     };
 
-    let result = parse_brute_args(cli);
+    let result = parse_brute_args(cli).await;
     assert!(result.is_err());
     let err_msg = result.err().unwrap().to_string();
     assert!(err_msg.contains("Either ips_file or ips_string must be provided"));
 }
 
 // 测试无效的参数组合：同时提供users_file和users_string
-#[test]
-fn test_parse_brute_args_invalid_user_source() {
+#[tokio::test]
+async fn test_parse_brute_args_invalid_user_source() {
     let users_file = create_temp_file("user1");
     let users_string = "admin".to_string();
     let passwords_file = create_temp_file("pass1");
@@ -174,10 +181,40 @@ fn test_parse_brute_args_invalid_user_source() {
         ips_file: None,
         ips_string: Some(ips_string),
         max_concurrent: 5,
+        options: ScanOptions::default(),
     };
 
     // 这里我们期望Clap会在解析阶段就失败，而不是在handle_cli中
     // 但为了演示，我们仍然测试这种情况
-    let result = parse_brute_args(cli);
+    let result = parse_brute_args(cli).await;
     assert!(result.is_err());
 }
+
+// 测试开关选项的合并：命令行显式关闭配置文件中开启的选项，未指定时沿用配置文件
+#[test]
+fn test_cli_overrides_config_switches() {
+    let config_file = create_temp_file(
+        "mode = \"brute\"\nmax_concurrent = 5\nkeep_alive = true\nverify_playback = true\n",
+    );
+    let config = AppConfig::load_from_file(&PathBuf::from(config_file.path())).unwrap();
+
+    let cli = Cli::try_parse_from([
+        "rust-rtsp-bruter",
+        "args",
+        "--keep-alive=false",
+        "--brute-proxy",
+    ])
+    .unwrap();
+    let merged = config.merge_with_cli(cli).unwrap();
+    assert_eq!(merged.options.keep_alive, Some(false));
+    assert_eq!(merged.options.verify_playback, Some(true));
+    assert_eq!(merged.options.brute_proxy, Some(true));
+    assert_eq!(merged.options.cache_challenge, None);
+
+    let cli = Cli::try_parse_from(["rust-rtsp-bruter", "args"]).unwrap();
+    let merged = config.merge_with_cli(cli).unwrap();
+    assert_eq!(merged.options.keep_alive, Some(true));
+
+    // 开关选项的取值必须用=连接，不会吞掉后面的参数
+    assert!(Cli::try_parse_from(["rust-rtsp-bruter", "args", "--keep-alive", "false"]).is_err());
+}
@@ -0,0 +1,192 @@
+// 集成测试共用的模拟RTSP服务器
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// 模拟服务器收到的请求
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    // 请求所在连接的序号（从0开始）
+    pub connection: usize,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn cseq(&self) -> u32 {
+        self.header("CSeq").and_then(|cseq| cseq.parse().ok()).unwrap_or(0)
+    }
+}
+
+// 处理函数的返回值：响应内容以及是否在响应后关闭连接
+pub struct MockReply {
+    pub data: Vec<u8>,
+    pub close: bool,
+}
+
+impl MockReply {
+    // 构建带CSeq的响应
+    pub fn new(request: &MockRequest, status: &str, headers: &[&str], body: &str) -> Self {
+        let mut response = format!("RTSP/1.0 {}\r\nCSeq: {}\r\n", status, request.cseq());
+        for header in headers {
+            response += header;
+            response += "\r\n";
+        }
+        if !body.is_empty() {
+            response += &format!("Content-Length: {}\r\n", body.len());
+        }
+        response += "\r\n";
+        response += body;
+        MockReply {
+            data: response.into_bytes(),
+            close: false,
+        }
+    }
+
+    pub fn closing(mut self) -> Self {
+        self.close = true;
+        self
+    }
+}
+
+// 模拟RTSP服务器
+pub struct MockRtspServer {
+    pub addr: SocketAddr,
+    connections: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockRtspServer {
+    // 启动服务器，每个请求由handler生成响应
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> MockReply + Send + Sync + 'static,
+    {
+        Self::start_on("127.0.0.1:0", handler).await
+    }
+
+    // 在指定地址上启动服务器，如"[::1]:0"
+    pub async fn start_on<F>(bind_addr: &str, handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> MockReply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind(bind_addr).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let handler = Arc::new(handler);
+
+        let connections_clone = connections.clone();
+        let requests_clone = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(_) => break,
+                };
+                let index = connections_clone.fetch_add(1, Ordering::SeqCst);
+                let handler = handler.clone();
+                let requests = requests_clone.clone();
+                tokio::spawn(async move {
+                    serve_connection(stream, index, handler, requests).await;
+                });
+            }
+        });
+
+        MockRtspServer {
+            addr,
+            connections,
+            requests,
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("rtsp://{}{}", self.addr, path)
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+async fn serve_connection<F>(
+    mut stream: TcpStream,
+    index: usize,
+    handler: Arc<F>,
+    requests: Arc<AtomicUsize>,
+) where
+    F: Fn(&MockRequest) -> MockReply + Send + Sync + 'static,
+{
+    let mut buffer = Vec::new();
+    loop {
+        let head_end = loop {
+            if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break position;
+            }
+            let mut chunk = [0; 4096];
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            }
+        };
+        let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+        buffer.drain(..head_end + 4);
+
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let request = MockRequest {
+            method: request_line.next().unwrap_or_default().to_string(),
+            url: request_line.next().unwrap_or_default().to_string(),
+            headers: lines
+                .filter_map(|line| line.split_once(':'))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect(),
+            connection: index,
+        };
+        requests.fetch_add(1, Ordering::SeqCst);
+
+        let reply = handler(&request);
+        if stream.write_all(&reply.data).await.is_err() {
+            return;
+        }
+        if reply.close {
+            let _ = stream.shutdown().await;
+            return;
+        }
+    }
+}
+
+// 简单的Basic认证处理：admin:admin通过，其余返回401
+pub fn basic_auth_handler(request: &MockRequest) -> MockReply {
+    // "admin:admin"的base64编码
+    match request.header("Authorization") {
+        Some("Basic YWRtaW46YWRtaW4=") => MockReply::new(
+            request,
+            "200 OK",
+            &["Content-Type: application/sdp"],
+            "v=0\r\ns=Mock\r\n",
+        ),
+        _ => MockReply::new(
+            request,
+            "401 Unauthorized",
+            &["WWW-Authenticate: Basic realm=\"mock\""],
+            "",
+        ),
+    }
+}
@@ -0,0 +1,124 @@
+use rust_rtsp_bruter::iterator::credential_iterator::CredentialIterator;
+use rust_rtsp_bruter::iterator::credential_reader::CredentialReader;
+use rust_rtsp_bruter::iterator::wordlist::Wordlist;
+
+fn pair(username: &str, password: &str) -> (String, String) {
+    (username.to_string(), password.to_string())
+}
+
+fn next(credentials: &mut CredentialIterator) -> Option<(String, String)> {
+    credentials.next().map(Result::unwrap)
+}
+
+fn collect(credentials: CredentialIterator) -> Vec<(String, String)> {
+    credentials.collect::<Result<_, _>>().unwrap()
+}
+
+/// 测试用户名和密码的组合顺序和数量
+#[test]
+fn test_credential_combinations() {
+    let credentials = CredentialIterator::new(
+        vec!["admin".to_string(), "root".to_string()],
+        vec!["12345".to_string(), "admin".to_string()],
+    );
+    assert_eq!(credentials.len(), 4);
+    assert_eq!(
+        collect(credentials),
+        vec![
+            pair("admin", "12345"),
+            pair("admin", "admin"),
+            pair("root", "12345"),
+            pair("root", "admin"),
+        ]
+    );
+
+    let mut empty = CredentialIterator::new(vec!["admin".to_string()], vec![]);
+    assert_eq!(empty.len(), 0);
+    assert!(empty.next().is_none());
+}
+
+/// 测试从文件逐行读取字典：跳过空行，克隆的迭代器从当前位置继续
+#[test]
+fn test_credentials_streamed_from_files() {
+    let dir = std::env::temp_dir();
+    let users = dir.join(format!("users_{}.txt", std::process::id()));
+    let passwords = dir.join(format!("passwords_{}.txt", std::process::id()));
+    std::fs::write(&users, "admin\n\n  root  \n").unwrap();
+    std::fs::write(&passwords, "12345\n\nadmin\npass\n").unwrap();
+
+    let mut credentials =
+        CredentialReader::from_files(users.to_str().unwrap(), passwords.to_str().unwrap())
+            .into_iterator()
+            .unwrap();
+    assert_eq!(credentials.len(), 6);
+    assert_eq!(next(&mut credentials), Some(pair("admin", "12345")));
+    assert_eq!(next(&mut credentials), Some(pair("admin", "admin")));
+
+    let mut resumed = credentials.clone();
+    assert_eq!(resumed.size_hint(), (4, Some(4)));
+    assert_eq!(next(&mut resumed), Some(pair("admin", "pass")));
+    assert_eq!(next(&mut resumed), Some(pair("root", "12345")));
+    assert_eq!(resumed.len(), 2);
+    assert_eq!(credentials.count(), 4);
+
+    let credentials =
+        CredentialReader::from_file_and_string(users.to_str().unwrap(), "secret".to_string())
+            .into_iterator()
+            .unwrap();
+    assert_eq!(
+        collect(credentials),
+        vec![pair("admin", "secret"), pair("root", "secret")]
+    );
+
+    std::fs::remove_file(&users).unwrap();
+    std::fs::remove_file(&passwords).unwrap();
+}
+
+/// 测试字典内容不会载入内存：单词在迭代时才从文件中顺序读取，
+/// 原地改写文件内容（长度不变）之后读到的是新的内容
+#[test]
+fn test_wordlist_not_materialized() {
+    let file = std::env::temp_dir().join(format!("large_wordlist_{}.txt", std::process::id()));
+    let words: String = (0..100_000).map(|i| format!("pass{:06}\r\n", i)).collect();
+    std::fs::write(&file, &words).unwrap();
+
+    let wordlist = Wordlist::from_file(&file).unwrap();
+    assert_eq!(wordlist.len(), 100_000);
+    let last = wordlist.words().unwrap().last().unwrap().unwrap();
+    assert_eq!(last, "pass099999");
+
+    std::fs::write(&file, words.replace("pass", "PASS")).unwrap();
+    let credentials = CredentialIterator::from_wordlists(
+        Wordlist::from_words(vec!["admin".to_string()]),
+        wordlist,
+    );
+    let mut resumed = credentials.clone().skip(50_000).map(Result::unwrap);
+    assert_eq!(resumed.next(), Some(pair("admin", "PASS050000")));
+    assert_eq!(
+        credentials.last().map(Result::unwrap),
+        Some(pair("admin", "PASS099999"))
+    );
+
+    std::fs::remove_file(&file).unwrap();
+}
+
+/// 测试字典文件在统计之后被截断时返回错误，而不是提前结束迭代
+#[test]
+fn test_truncated_wordlist_is_an_error() {
+    let file = std::env::temp_dir().join(format!("truncated_wordlist_{}.txt", std::process::id()));
+    std::fs::write(&file, "12345\nadmin\npass\n").unwrap();
+    let wordlist = Wordlist::from_file(&file).unwrap();
+    std::fs::write(&file, "12345\n").unwrap();
+
+    let mut credentials = CredentialIterator::from_wordlists(
+        Wordlist::from_words(vec!["admin".to_string()]),
+        wordlist,
+    );
+    assert_eq!(next(&mut credentials), Some(pair("admin", "12345")));
+    let error = credentials.next().unwrap().unwrap_err();
+    assert!(error.to_string().contains("truncated"), "{}", error);
+    assert!(credentials.next().is_none());
+    assert_eq!(credentials.len(), 0);
+
+    std::fs::remove_file(&file).unwrap();
+}
@@ -0,0 +1,302 @@
+use rust_rtsp_bruter::rtsp::depacketizer::{
+    DepacketizedMedia, Depacketizer, RtpPacket, make_quantization_tables,
+};
+use rust_rtsp_bruter::rtsp::sdp::SessionDescription;
+
+const SPS: &[u8] = &[0x67, 0x4D, 0x00, 0x2A];
+const PPS: &[u8] = &[0x68, 0xEE, 0x3C, 0x80];
+
+// 构建RTP数据包
+fn rtp(sequence: u16, marker: bool, payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x80, if marker { 0xE0 } else { 0x60 }];
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0x0B, 0xB8, 0x12, 0x34, 0x56, 0x78]);
+    packet.extend_from_slice(payload);
+    packet
+}
+
+fn push_all(depacketizer: &mut Depacketizer, packets: &[Vec<u8>]) {
+    for packet in packets {
+        depacketizer
+            .push(&RtpPacket::parse(packet).unwrap())
+            .unwrap();
+    }
+}
+
+fn annex_b(nal_units: &[&[u8]]) -> Vec<u8> {
+    nal_units
+        .iter()
+        .flat_map(|nal| [&[0, 0, 0, 1][..], nal].concat())
+        .collect()
+}
+
+// 测试解析带CSRC、扩展头和填充的RTP数据包
+#[test]
+fn test_parse_rtp_packet() {
+    let data = [
+        0xB1, 0xE0, 0x00, 0x07, 0x00, 0x00, 0x0B, 0xB8, 0x12, 0x34, 0x56,
+        0x78, // 固定头部
+        0xAA, 0xAA, 0xAA, 0xAA, // CSRC
+        0xBE, 0xDE, 0x00, 0x01, 0xBB, 0xBB, 0xBB, 0xBB, // 扩展头
+        0x65, 0x88, // 负载
+        0x00, 0x02, // 填充
+    ];
+    let packet = RtpPacket::parse(&data).unwrap();
+    assert!(packet.marker);
+    assert_eq!(packet.payload_type, 96);
+    assert_eq!(packet.sequence, 7);
+    assert_eq!(packet.timestamp, 3000);
+    assert_eq!(packet.ssrc, 0x12345678);
+    assert_eq!(packet.payload, &[0x65, 0x88]);
+
+    assert!(RtpPacket::parse(&[0x80, 0x60, 0x00]).is_err());
+    assert!(RtpPacket::parse(&[0x40; 12]).is_err());
+}
+
+// 测试H.264的单NAL单元包、STAP-A和FU-A，参数集写在裸流开头
+#[test]
+fn test_h264_depacketization() {
+    let mut depacketizer = Depacketizer::h264(&[SPS.to_vec(), PPS.to_vec()]);
+    push_all(
+        &mut depacketizer,
+        &[
+            // STAP-A：SEI和单字节的AUD
+            rtp(1, false, &[0x18, 0x00, 0x02, 0x06, 0x05, 0x00, 0x01, 0x09]),
+            // FU-A分为三片的IDR
+            rtp(2, false, &[0x7C, 0x85, 0x88, 0x80]),
+            rtp(3, false, &[0x7C, 0x05, 0x40]),
+            rtp(4, true, &[0x7C, 0x45, 0x20]),
+            rtp(5, true, &[0x41, 0x9A, 0x02]),
+        ],
+    );
+    assert_eq!(depacketizer.packets(), 5);
+    assert_eq!(
+        depacketizer.finish(),
+        DepacketizedMedia::AnnexB(annex_b(&[
+            SPS,
+            PPS,
+            &[0x06, 0x05],
+            &[0x09],
+            &[0x65, 0x88, 0x80, 0x40, 0x20],
+            &[0x41, 0x9A, 0x02],
+        ]))
+    );
+}
+
+// 测试丢包时丢弃未完成的FU-A分片
+#[test]
+fn test_h264_drops_incomplete_fragment() {
+    let mut depacketizer = Depacketizer::h264(&[]);
+    push_all(
+        &mut depacketizer,
+        &[
+            rtp(10, false, &[0x7C, 0x85, 0x88]),
+            rtp(12, true, &[0x7C, 0x45, 0x20]),
+            rtp(13, true, &[0x41, 0x9A]),
+        ],
+    );
+    assert_eq!(
+        depacketizer.finish(),
+        DepacketizedMedia::AnnexB(annex_b(&[&[0x41, 0x9A]]))
+    );
+
+    // 只有参数集时不输出
+    let depacketizer = Depacketizer::h264(&[SPS.to_vec()]);
+    assert!(depacketizer.finish().is_empty());
+}
+
+// 测试H.265的聚合包和分片单元
+#[test]
+fn test_h265_depacketization() {
+    let mut depacketizer = Depacketizer::h265(&[], false);
+    push_all(
+        &mut depacketizer,
+        &[
+            // 聚合包：VPS和SPS
+            rtp(
+                1,
+                false,
+                &[
+                    0x60, 0x01, 0x00, 0x03, 0x40, 0x01, 0x0C, 0x00, 0x03, 0x42, 0x01, 0x01,
+                ],
+            ),
+            // 分片单元：IDR_W_RADL（类型19）
+            rtp(2, false, &[0x62, 0x01, 0x93, 0xAF, 0x01]),
+            rtp(3, true, &[0x62, 0x01, 0x53, 0x02]),
+        ],
+    );
+    assert_eq!(
+        depacketizer.finish(),
+        DepacketizedMedia::AnnexB(annex_b(&[
+            &[0x40, 0x01, 0x0C],
+            &[0x42, 0x01, 0x01],
+            &[0x26, 0x01, 0xAF, 0x01, 0x02],
+        ]))
+    );
+
+    // 带DONL的分片单元
+    let mut depacketizer = Depacketizer::h265(&[], true);
+    push_all(
+        &mut depacketizer,
+        &[
+            rtp(1, false, &[0x62, 0x01, 0x93, 0x00, 0x05, 0xAF]),
+            rtp(2, true, &[0x62, 0x01, 0x53, 0x01]),
+        ],
+    );
+    assert_eq!(
+        depacketizer.finish(),
+        DepacketizedMedia::AnnexB(annex_b(&[&[0x26, 0x01, 0xAF, 0x01]]))
+    );
+}
+
+// 根据SDP创建解包器，H.265从sprop-vps/sps/pps读取参数集
+#[test]
+fn test_depacketizer_for_media() {
+    let sdp = "v=0\r\ns=Mock\r\nt=0 0\r\n\
+m=video 0 RTP/AVP 98\r\na=rtpmap:98 H265/90000\r\n\
+a=fmtp:98 sprop-vps=QAEMAQ==;sprop-sps=QgEB;sprop-pps=RAHA\r\n\
+m=video 0 RTP/AVP 26\r\n\
+m=audio 0 RTP/AVP 8\r\n";
+    let session = SessionDescription::parse(sdp).unwrap();
+
+    let mut h265 = Depacketizer::for_media(&session.media[0]).unwrap();
+    push_all(&mut h265, &[rtp(1, true, &[0x26, 0x01, 0xAF])]);
+    assert_eq!(
+        h265.finish(),
+        DepacketizedMedia::AnnexB(annex_b(&[
+            &[0x40, 0x01, 0x0C, 0x01],
+            &[0x42, 0x01, 0x01],
+            &[0x44, 0x01, 0xC0],
+            &[0x26, 0x01, 0xAF],
+        ]))
+    );
+    assert!(Depacketizer::for_media(&session.media[1]).is_some());
+    assert!(Depacketizer::for_media(&session.media[2]).is_none());
+}
+
+// 遍历JPEG的标记段，返回(标记, 段内容)
+fn jpeg_segments(jpeg: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+    let mut segments = Vec::new();
+    let mut position = 2;
+    loop {
+        assert_eq!(jpeg[position], 0xFF);
+        let marker = jpeg[position + 1];
+        let length = u16::from_be_bytes([jpeg[position + 2], jpeg[position + 3]]) as usize;
+        segments.push((marker, jpeg[position + 4..position + 2 + length].to_vec()));
+        position += 2 + length;
+        if marker == 0xDA {
+            return segments;
+        }
+    }
+}
+
+// RTP/JPEG分片：宽640，高480
+fn jpeg_payload(offset: u32, jpeg_type: u8, q: u8, extra: &[u8], scan: &[u8]) -> Vec<u8> {
+    let mut payload = vec![0];
+    payload.extend_from_slice(&offset.to_be_bytes()[1..]);
+    payload.extend_from_slice(&[jpeg_type, q, 80, 60]);
+    payload.extend_from_slice(extra);
+    payload.extend_from_slice(scan);
+    payload
+}
+
+// 测试MJPEG分片重组并生成完整的JPEG文件
+#[test]
+fn test_mjpeg_depacketization() {
+    let mut depacketizer = Depacketizer::mjpeg();
+    push_all(
+        &mut depacketizer,
+        &[
+            rtp(1, false, &jpeg_payload(0, 1, 50, &[], &[0x11, 0x22])),
+            rtp(2, true, &jpeg_payload(2, 1, 50, &[], &[0x33])),
+            // 丢失第一个分片的帧被丢弃
+            rtp(4, true, &jpeg_payload(3, 1, 50, &[], &[0x44])),
+        ],
+    );
+    let frames = match depacketizer.finish() {
+        DepacketizedMedia::Jpeg(frames) => frames,
+        other => panic!("Expected JPEG frames, got {:?}", other),
+    };
+    assert_eq!(frames.len(), 1);
+    let jpeg = &frames[0];
+    assert!(jpeg.ends_with(&[0x11, 0x22, 0x33, 0xFF, 0xD9]));
+
+    let segments = jpeg_segments(jpeg);
+    let tables = make_quantization_tables(50);
+    let dqt: Vec<&Vec<u8>> = segments
+        .iter()
+        .filter(|(marker, _)| *marker == 0xDB)
+        .map(|(_, content)| content)
+        .collect();
+    assert_eq!(dqt.len(), 2);
+    assert_eq!(dqt[0][0], 0);
+    assert_eq!(&dqt[0][1..], &tables[..64]);
+    assert_eq!(dqt[1][0], 1);
+    assert_eq!(&dqt[1][1..], &tables[64..]);
+
+    // SOF0：高480，宽640，类型1的亮度采样为2x2
+    let (_, sof) = segments.iter().find(|(marker, _)| *marker == 0xC0).unwrap();
+    assert_eq!(&sof[..6], &[8, 0x01, 0xE0, 0x02, 0x80, 3]);
+    assert_eq!(sof[7], 0x22);
+
+    // 霍夫曼表中各长度的码字数之和等于符号数
+    let dht: Vec<&Vec<u8>> = segments
+        .iter()
+        .filter(|(marker, _)| *marker == 0xC4)
+        .map(|(_, content)| content)
+        .collect();
+    assert_eq!(dht.len(), 4);
+    for table in dht {
+        let count: usize = table[1..17].iter().map(|count| *count as usize).sum();
+        assert_eq!(table.len(), 17 + count);
+    }
+}
+
+// 测试Q值为128-255时带内传输的量化表，以及重启标记头部
+#[test]
+fn test_mjpeg_in_band_tables() {
+    // 重启间隔16
+    let restart = [0x00, 0x10, 0xFF, 0xFF];
+    let mut first = restart.to_vec();
+    first.extend_from_slice(&[0, 0, 0, 128]);
+    first.extend_from_slice(&[7; 64]);
+    first.extend_from_slice(&[9; 64]);
+    // 第二帧省略量化表，使用之前收到的表
+    let mut second = restart.to_vec();
+    second.extend_from_slice(&[0, 0, 0, 0]);
+
+    let mut depacketizer = Depacketizer::mjpeg();
+    push_all(
+        &mut depacketizer,
+        &[
+            rtp(1, true, &jpeg_payload(0, 64, 255, &first, &[0x55])),
+            rtp(2, true, &jpeg_payload(0, 64, 255, &second, &[0x66])),
+        ],
+    );
+    let frames = match depacketizer.finish() {
+        DepacketizedMedia::Jpeg(frames) => frames,
+        other => panic!("Expected JPEG frames, got {:?}", other),
+    };
+    assert_eq!(frames.len(), 2);
+    for frame in &frames {
+        let segments = jpeg_segments(frame);
+        assert!(segments.contains(&(0xDD, vec![0x00, 0x10])));
+        assert!(segments.contains(&(0xDB, [&[0][..], &[7; 64]].concat())));
+        assert!(segments.contains(&(0xDB, [&[1][..], &[9; 64]].concat())));
+        // 类型64对应类型0，亮度采样为2x1
+        let (_, sof) = segments.iter().find(|(marker, _)| *marker == 0xC0).unwrap();
+        assert_eq!(sof[7], 0x21);
+    }
+    assert!(frames[0].ends_with(&[0x55, 0xFF, 0xD9]));
+    assert!(frames[1].ends_with(&[0x66, 0xFF, 0xD9]));
+
+    // 没有收到过的量化表
+    let mut depacketizer = Depacketizer::mjpeg();
+    let packet = rtp(1, true, &jpeg_payload(0, 64, 200, &second, &[0x66]));
+    assert!(
+        depacketizer
+            .push(&RtpPacket::parse(&packet).unwrap())
+            .is_err()
+    );
+}
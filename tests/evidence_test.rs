@@ -0,0 +1,189 @@
+mod common;
+
+use common::{MockReply, MockRequest, MockRtspServer};
+use rust_rtsp_bruter::iterator::ip_iterator::IpPortAddr;
+use rust_rtsp_bruter::rtsp::auth::AuthPolicy;
+use rust_rtsp_bruter::rtsp::client::RtspClient;
+use rust_rtsp_bruter::rtsp::connection::RtspConnection;
+use rust_rtsp_bruter::rtsp::evidence::EvidenceOptions;
+use rust_rtsp_bruter::rtsp::playback::PlaybackVerifier;
+use rust_rtsp_bruter::rtsp::sdp::SessionDescription;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const SDP: &str = "v=0\r\ns=Mock\r\nt=0 0\r\na=control:*\r\n\
+m=video 0 RTP/AVP 96\r\na=rtpmap:96 H264/90000\r\n\
+a=fmtp:96 packetization-mode=1;sprop-parameter-sets=Z00AKg==,aO48gA==\r\na=control:trackID=1\r\n\
+m=video 0 RTP/AVP 26\r\na=control:trackID=2\r\n\
+m=audio 0 RTP/AVP 8\r\na=control:trackID=3\r\n";
+
+// 构建交织的RTP数据包
+fn interleaved(channel: u8, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x80, 0xE0];
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0, 0x12, 0x34, 0x56, 0x78]);
+    packet.extend_from_slice(payload);
+    let mut frame = vec![b'$', channel];
+    frame.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+    frame.extend_from_slice(&packet);
+    frame
+}
+
+// 模拟摄像头：SETUP按请求的交织通道回复，PLAY后发送H.264和MJPEG数据包
+fn camera_handler(request: &MockRequest) -> MockReply {
+    match request.method.as_str() {
+        "SETUP" => {
+            let transport = format!("Transport: {}", request.header("Transport").unwrap());
+            MockReply::new(
+                request,
+                "200 OK",
+                &["Session: 5A3F;timeout=60", &transport],
+                "",
+            )
+        }
+        "PLAY" => {
+            let mut reply = MockReply::new(request, "200 OK", &["Session: 5A3F"], "");
+            reply.data.extend(interleaved(0, 1, &[0x65, 0x88, 0x84]));
+            reply
+                .data
+                .extend(interleaved(2, 1, &[0, 0, 0, 0, 1, 50, 80, 60, 0x12, 0x34]));
+            reply
+        }
+        _ => MockReply::new(request, "200 OK", &[], ""),
+    }
+}
+
+// 测试录制各轨道的媒体流并写入目标的取证目录
+#[tokio::test]
+async fn test_capture_evidence() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let requests_clone = requests.clone();
+    let server = MockRtspServer::start(move |request| {
+        requests_clone.lock().unwrap().push(request.clone());
+        camera_handler(request)
+    })
+    .await;
+    let url = server.url("/live");
+    let mut session = SessionDescription::parse(SDP).unwrap();
+    session.resolve_controls(&url);
+
+    let options = EvidenceOptions::new(
+        std::env::temp_dir()
+            .join(format!("evidence_{}", std::process::id()))
+            .to_str()
+            .unwrap(),
+        Duration::from_millis(300),
+    );
+    let directory = options.host_directory(&IpPortAddr::new(server.addr.ip(), server.addr.port()));
+    assert!(directory.ends_with(format!("127.0.0.1_{}", server.addr.port())));
+
+    let evidence = RtspClient::new("admin", "admin")
+        .capture_evidence(&url, &session, options.duration, &directory)
+        .await
+        .unwrap();
+    assert_eq!(evidence.packets, 2);
+    assert_eq!(
+        evidence.files,
+        vec![
+            directory.join("track1.h264"),
+            directory.join("track2_0001.jpg")
+        ]
+    );
+
+    let h264 = std::fs::read(directory.join("track1.h264")).unwrap();
+    assert_eq!(
+        h264,
+        [
+            &[0, 0, 0, 1, 0x67, 0x4D, 0x00, 0x2A][..],
+            &[0, 0, 0, 1, 0x68, 0xEE, 0x3C, 0x80],
+            &[0, 0, 0, 1, 0x65, 0x88, 0x84],
+        ]
+        .concat()
+    );
+    let jpeg = std::fs::read(directory.join("track2_0001.jpg")).unwrap();
+    assert!(jpeg.starts_with(&[0xFF, 0xD8]));
+    assert!(jpeg.ends_with(&[0x12, 0x34, 0xFF, 0xD9]));
+    std::fs::remove_dir_all(&options.directory).unwrap();
+
+    // 音频轨道不支持解包，不进行SETUP；第二个SETUP携带会话ID
+    let requests = requests.lock().unwrap();
+    let setup: Vec<&MockRequest> = requests
+        .iter()
+        .filter(|request| request.method == "SETUP")
+        .collect();
+    assert_eq!(setup.len(), 2);
+    assert_eq!(setup[0].header("Session"), None);
+    assert_eq!(
+        setup[1].header("Transport"),
+        Some("RTP/AVP/TCP;unicast;interleaved=2-3")
+    );
+    assert_eq!(setup[1].header("Session"), Some("5A3F"));
+    assert!(requests.iter().any(|request| request.method == "TEARDOWN"));
+}
+
+// 测试SDP中的轨道过多时只录制交织通道能容纳的轨道
+#[tokio::test]
+async fn test_capture_track_limit() {
+    let setups = Arc::new(Mutex::new(Vec::new()));
+    let setups_clone = setups.clone();
+    let server = MockRtspServer::start(move |request| {
+        if request.method == "SETUP" {
+            let transport = request.header("Transport").unwrap_or_default().to_string();
+            setups_clone.lock().unwrap().push(transport);
+        }
+        camera_handler(request)
+    })
+    .await;
+    let url = server.url("/live");
+    let mut sdp = "v=0\r\ns=Mock\r\nt=0 0\r\na=control:*\r\n".to_string();
+    for track in 0..200 {
+        sdp.push_str(&format!("m=video 0 RTP/AVP 26\r\na=control:trackID={}\r\n", track));
+    }
+    let mut session = SessionDescription::parse(&sdp).unwrap();
+    session.resolve_controls(&url);
+
+    let addr = IpPortAddr::new(server.addr.ip(), server.addr.port());
+    let tracks = PlaybackVerifier::new(
+        RtspConnection::connect(addr).await.unwrap(),
+        &server.addr.ip().to_string(),
+        server.addr.port(),
+        "admin",
+        "admin",
+        AuthPolicy::default(),
+    )
+    .capture(&url, &session, Duration::from_millis(100))
+    .await
+    .unwrap();
+    assert_eq!(tracks.len(), 128);
+    let setups = setups.lock().unwrap();
+    assert_eq!(setups.len(), 128);
+    assert_eq!(setups[127], "RTP/AVP/TCP;unicast;interleaved=254-255");
+}
+
+// 测试缓存的媒体数据达到上限后提前结束录制
+#[tokio::test]
+async fn test_capture_byte_limit() {
+    let server = MockRtspServer::start(camera_handler).await;
+    let url = server.url("/live");
+    let mut session = SessionDescription::parse(SDP).unwrap();
+    session.resolve_controls(&url);
+
+    let addr = IpPortAddr::new(server.addr.ip(), server.addr.port());
+    let started = Instant::now();
+    let tracks = PlaybackVerifier::new(
+        RtspConnection::connect(addr).await.unwrap(),
+        &server.addr.ip().to_string(),
+        server.addr.port(),
+        "admin",
+        "admin",
+        AuthPolicy::default(),
+    )
+    .with_max_capture_bytes(20)
+    .capture(&url, &session, Duration::from_secs(10))
+    .await
+    .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    // 第一个数据包15字节，第二个数据包超过上限
+    assert_eq!(tracks[0].1.packets(), 1);
+    assert_eq!(tracks[1].1.packets(), 0);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::{MockRequest, MockRtspServer, basic_auth_handler};
+use rust_rtsp_bruter::brute::BruteForcer;
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::iterator::ip_iterator::IpPortAddr;
+use rust_rtsp_bruter::iterator::ip_reader::IpReader;
+use rust_rtsp_bruter::iterator::resolver::DNS_CACHE;
+use rust_rtsp_bruter::rtsp::client::RtspClient;
+use rust_rtsp_bruter::rtsp::rtsp_worker::RTSP_WORKER_MANAGER;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+async fn recording_server(ip: IpAddr) -> (MockRtspServer, Arc<Mutex<Vec<MockRequest>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let requests_clone = requests.clone();
+    let bind_addr = SocketAddr::new(ip, 0).to_string();
+    let server = MockRtspServer::start_on(&bind_addr, move |request| {
+        requests_clone.lock().unwrap().push(request.clone());
+        basic_auth_handler(request)
+    })
+    .await;
+    (server, requests)
+}
+
+// 测试域名目标的显示：URL中使用域名，结果中带上连接的地址
+#[test]
+fn test_hostname_target_display() {
+    let addr = IpPortAddr::new(LOCALHOST, 8554).with_hostname("camera.local");
+    assert_eq!(addr.host(), "camera.local");
+    assert_eq!(addr.authority(), "camera.local:8554");
+    assert_eq!(addr.to_string(), "camera.local:8554 (127.0.0.1)");
+    assert_eq!(addr.location("/live"), "camera.local:8554/live (127.0.0.1)");
+    assert_ne!(addr, IpPortAddr::new(LOCALHOST, 8554));
+
+    let addr = IpPortAddr::new(LOCALHOST, 554);
+    assert_eq!(addr.authority(), "127.0.0.1:554");
+    assert_eq!(addr.location("/live"), "127.0.0.1:554/live");
+}
+
+// 测试读取域名目标：保留域名，解析到的每个地址和端口都作为单独的目标
+#[tokio::test]
+async fn test_ip_reader_keeps_hostname() {
+    let targets: Vec<IpPortAddr> = IpReader::from_string("localhost:{554,8554}")
+        .into_iterator()
+        .await
+        .unwrap()
+        .collect();
+    let addrs = DNS_CACHE.resolve("localhost").await.unwrap();
+    assert!(addrs.contains(&LOCALHOST));
+    assert_eq!(targets.len(), addrs.len() * 2);
+    assert!(
+        targets
+            .iter()
+            .all(|target| target.hostname.as_deref() == Some("localhost"))
+    );
+    assert!(targets.contains(&IpPortAddr::new(LOCALHOST, 8554).with_hostname("localhost")));
+
+    // 未指定端口时使用默认端口
+    let targets: Vec<IpPortAddr> = IpReader::from_string("LOCALHOST")
+        .into_iterator()
+        .await
+        .unwrap()
+        .collect();
+    assert!(targets.iter().all(|target| target.port == 554));
+
+    assert!(
+        IpReader::from_string("bad/host:554")
+            .into_iterator()
+            .await
+            .is_err()
+    );
+}
+
+// 测试URL中的域名被解析后连接，请求URL和Host头中保留域名
+#[tokio::test]
+async fn test_describe_hostname_url() {
+    // 客户端连接域名解析到的第一个地址
+    let ip = DNS_CACHE.resolve("localhost").await.unwrap()[0];
+    let (server, requests) = recording_server(ip).await;
+    let url = format!("rtsp://localhost:{}/live", server.addr.port());
+
+    let result = RtspClient::new("admin", "admin")
+        .describe(&url)
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::Success);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in requests.iter() {
+        assert_eq!(request.url, url);
+        assert_eq!(
+            request.header("Host"),
+            Some(format!("localhost:{}", server.addr.port()).as_str())
+        );
+    }
+}
+
+// 测试暴力枚举域名目标时连接固定的地址，不需要解析域名，请求和结果中保留域名
+#[tokio::test(flavor = "multi_thread")]
+async fn test_brute_forcer_pins_hostname_address() {
+    RTSP_WORKER_MANAGER.start().await;
+    let brute_forcer = BruteForcer::new();
+    let (server, requests) = recording_server(LOCALHOST).await;
+    let port = server.addr.port();
+    // 保留的.invalid域名无法解析，只能通过固定的地址连接
+    let target = IpPortAddr::new(LOCALHOST, port).with_hostname("camera.invalid");
+
+    let found = brute_forcer
+        .try_credentials("admin", "admin", &target, "/live")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.ip_port, target);
+    assert_eq!(
+        found.to_string(),
+        format!("camera.invalid:{}/live (127.0.0.1) with admin:admin", port)
+    );
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in requests.iter() {
+        assert_eq!(request.url, format!("rtsp://camera.invalid:{}/live", port));
+        assert_eq!(
+            request.header("Host"),
+            Some(format!("camera.invalid:{}", port).as_str())
+        );
+    }
+}
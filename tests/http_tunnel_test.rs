@@ -0,0 +1,295 @@
+mod common;
+
+use base64::Engine;
+use common::{MockRtspServer, basic_auth_handler};
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::iterator::ip_iterator::IpPortAddr;
+use rust_rtsp_bruter::rtsp::client::{ClientOptions, RtspClient};
+use rust_rtsp_bruter::rtsp::http_tunnel::HttpTunnel;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+
+// 读取HTTP请求头
+async fn read_http_header(stream: &mut TcpStream) -> String {
+    let mut header = Vec::new();
+    while !header.ends_with(b"\r\n\r\n") {
+        header.push(stream.read_u8().await.unwrap());
+    }
+    String::from_utf8(header).unwrap()
+}
+
+fn header_value<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+// 模拟HTTP隧道服务器：按x-sessioncookie关联GET和POST连接，解码POST中的请求后转发给模拟的RTSP服务器
+struct TunnelProxy {
+    addr: SocketAddr,
+    // 收到的HTTP请求头
+    headers: Arc<Mutex<Vec<String>>>,
+    // 解码后的RTSP请求
+    decoded: Arc<Mutex<Vec<u8>>>,
+}
+
+impl TunnelProxy {
+    async fn start(backend: SocketAddr, status: &'static str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let headers = Arc::new(Mutex::new(Vec::new()));
+        let decoded = Arc::new(Mutex::new(Vec::new()));
+        let sessions: Arc<Mutex<HashMap<String, OwnedWriteHalf>>> = Arc::default();
+
+        let headers_clone = headers.clone();
+        let decoded_clone = decoded.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let headers = headers_clone.clone();
+                let decoded = decoded_clone.clone();
+                let sessions = sessions.clone();
+                tokio::spawn(async move {
+                    let header = read_http_header(&mut stream).await;
+                    headers.lock().unwrap().push(header.clone());
+                    let cookie = header_value(&header, "x-sessioncookie")
+                        .unwrap()
+                        .to_string();
+                    if header.starts_with("GET ") {
+                        let reply = format!(
+                            "HTTP/1.0 {}\r\nContent-Type: application/x-rtsp-tunnelled\r\n\r\n",
+                            status
+                        );
+                        stream.write_all(reply.as_bytes()).await.unwrap();
+                        let (mut backend_read, backend_write) =
+                            TcpStream::connect(backend).await.unwrap().into_split();
+                        sessions.lock().unwrap().insert(cookie, backend_write);
+                        let _ = tokio::io::copy(&mut backend_read, &mut stream).await;
+                    } else {
+                        let mut backend = sessions.lock().unwrap().remove(&cookie).unwrap();
+                        let mut encoded = Vec::new();
+                        let mut buffer = [0u8; 1024];
+                        loop {
+                            let n = stream.read(&mut buffer).await.unwrap_or(0);
+                            if n == 0 {
+                                return;
+                            }
+                            encoded.extend_from_slice(&buffer[..n]);
+                            // 每个请求单独编码，以填充结束
+                            let complete = encoded.len() / 4 * 4;
+                            let chunk: Vec<u8> = encoded.drain(..complete).collect();
+                            let data = base64::engine::general_purpose::STANDARD
+                                .decode(&chunk)
+                                .unwrap();
+                            decoded.lock().unwrap().extend_from_slice(&data);
+                            backend.write_all(&data).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        TunnelProxy {
+            addr,
+            headers,
+            decoded,
+        }
+    }
+}
+
+fn tunnel_options(port: u16) -> ClientOptions {
+    ClientOptions {
+        http_tunnel_ports: vec![port],
+        ..Default::default()
+    }
+}
+
+// 测试通过HTTP隧道完成DESCRIBE认证，GET和POST使用相同的会话cookie
+#[tokio::test]
+async fn test_describe_over_http_tunnel() {
+    let server = MockRtspServer::start(basic_auth_handler).await;
+    let proxy = TunnelProxy::start(server.addr, "200 OK").await;
+    let url = format!("rtsp://{}/live", proxy.addr);
+
+    let result = RtspClient::new("admin", "admin")
+        .with_options(tunnel_options(proxy.addr.port()))
+        .describe(&url)
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::Success);
+    assert_eq!(server.requests(), 2);
+
+    let headers = proxy.headers.lock().unwrap();
+    assert_eq!(headers.len(), 2);
+    assert!(headers[0].starts_with("GET /live HTTP/1.0\r\n"));
+    assert_eq!(
+        header_value(&headers[0], "Accept"),
+        Some("application/x-rtsp-tunnelled")
+    );
+    assert!(headers[1].starts_with("POST /live HTTP/1.0\r\n"));
+    assert_eq!(
+        header_value(&headers[1], "Content-Type"),
+        Some("application/x-rtsp-tunnelled")
+    );
+    let cookie = header_value(&headers[0], "x-sessioncookie").unwrap();
+    assert_eq!(cookie.len(), 22);
+    assert_eq!(header_value(&headers[1], "x-sessioncookie"), Some(cookie));
+
+    // 隧道内的RTSP请求使用rtsp://的URL
+    let decoded = String::from_utf8(proxy.decoded.lock().unwrap().clone()).unwrap();
+    assert!(decoded.starts_with(&format!("DESCRIBE {} RTSP/1.0\r\n", url)));
+    assert_eq!(decoded.matches("DESCRIBE ").count(), 2);
+}
+
+// 测试隧道的GET请求被拒绝时返回连接错误
+#[tokio::test]
+async fn test_http_tunnel_rejected() {
+    let server = MockRtspServer::start(basic_auth_handler).await;
+    let proxy = TunnelProxy::start(server.addr, "404 Not Found").await;
+
+    let result = RtspClient::new("admin", "admin")
+        .with_options(tunnel_options(proxy.addr.port()))
+        .describe(&format!("rtsp://{}/live", proxy.addr))
+        .await;
+    assert!(result.is_err());
+    assert_eq!(server.requests(), 0);
+}
+
+// 测试只有指定的目标使用HTTP隧道：同一端口上的另一台主机直接连接
+#[tokio::test]
+async fn test_http_tunnel_per_target() {
+    let backend = MockRtspServer::start(basic_auth_handler).await;
+    let proxy = TunnelProxy::start(backend.addr, "200 OK").await;
+    let direct = MockRtspServer::start_on(
+        &format!("127.0.0.2:{}", proxy.addr.port()),
+        basic_auth_handler,
+    )
+    .await;
+    let options = ClientOptions {
+        http_tunnel_targets: [proxy.addr].into_iter().collect(),
+        ..Default::default()
+    };
+
+    for url in [format!("rtsp://{}/live", proxy.addr), direct.url("/live")] {
+        let result = RtspClient::new("admin", "admin")
+            .with_options(options.clone())
+            .describe(&url)
+            .await
+            .unwrap();
+        assert_eq!(result, AuthenticationResult::Success);
+    }
+    // 隧道目标的GET和POST经过隧道，另一台主机直接收到RTSP请求
+    assert_eq!(proxy.headers.lock().unwrap().len(), 2);
+    assert_eq!(backend.requests(), 2);
+    assert_eq!(direct.requests(), 2);
+}
+
+// 测试写入在发送部分数据后被取消（如超时）时隧道被标记为损坏，之后的写入返回错误
+#[tokio::test]
+async fn test_cancelled_write_breaks_tunnel() {
+    // GET返回200，POST连接不读取数据，发送缓冲区写满后写入无法完成
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let header = read_http_header(&mut stream).await;
+            if header.starts_with("GET ") {
+                stream.write_all(b"HTTP/1.0 200 OK\r\n\r\n").await.unwrap();
+            }
+            streams.push(stream);
+        }
+    });
+
+    let target = IpPortAddr::new(addr.ip(), addr.port());
+    let mut tunnel = HttpTunnel::connect(&target, "/live").await.unwrap();
+    let request = vec![b'A'; 16 * 1024 * 1024];
+    let write = tokio::time::timeout(Duration::from_millis(200), tunnel.write_all(&request)).await;
+    assert!(write.is_err());
+
+    let error = tunnel
+        .write_all(b"OPTIONS * RTSP/1.0\r\nCSeq: 2\r\n\r\n")
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+}
+
+// 测试POST连接的数据达到声明的Content-Length之前，使用相同的cookie重新建立POST连接
+#[tokio::test]
+async fn test_http_tunnel_reopens_post() {
+    // 按建立顺序记录每个POST连接的请求头和收到的数据
+    type Post = (usize, String, Vec<u8>);
+    let posts: Arc<Mutex<Vec<Post>>> = Arc::default();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let posts_clone = posts.clone();
+    tokio::spawn(async move {
+        let mut streams = Vec::new();
+        let mut order = 0;
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let header = read_http_header(&mut stream).await;
+            if header.starts_with("GET ") {
+                stream.write_all(b"HTTP/1.0 200 OK\r\n\r\n").await.unwrap();
+                streams.push(stream);
+                continue;
+            }
+            let posts = posts_clone.clone();
+            order += 1;
+            tokio::spawn(async move {
+                let mut body = Vec::new();
+                stream.read_to_end(&mut body).await.unwrap();
+                posts.lock().unwrap().push((order, header, body));
+            });
+        }
+    });
+
+    let target = IpPortAddr::new(addr.ip(), addr.port());
+    let mut tunnel = HttpTunnel::connect(&target, "/live").await.unwrap();
+    let mut sent = Vec::new();
+    let mut encoded_len = 0;
+    for cseq in 0..150 {
+        let request = format!(
+            "OPTIONS * RTSP/1.0\r\nCSeq: {}\r\nX-Padding: {}\r\n\r\n",
+            cseq,
+            "A".repeat(400)
+        );
+        tunnel.write_all(request.as_bytes()).await.unwrap();
+        sent.extend_from_slice(request.as_bytes());
+        encoded_len += base64::engine::general_purpose::STANDARD.encode(&request).len();
+    }
+    assert!(encoded_len > 2 * 32767);
+    drop(tunnel);
+
+    // 等待所有POST连接关闭
+    let mut posts = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let posts = posts.lock().unwrap().clone();
+            if posts.iter().map(|(_, _, body)| body.len()).sum::<usize>() == encoded_len {
+                return posts;
+            }
+            drop(posts);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    posts.sort_by_key(|(order, _, _)| *order);
+    assert_eq!(posts.len(), 3);
+
+    let cookie = header_value(&posts[0].1, "x-sessioncookie").unwrap().to_string();
+    let mut decoded = Vec::new();
+    for (_, header, body) in &posts {
+        assert_eq!(header_value(header, "x-sessioncookie"), Some(cookie.as_str()));
+        assert_eq!(header_value(header, "Content-Length"), Some("32767"));
+        assert!(body.len() <= 32767);
+        // 每个POST的数据在4字节边界处切分，可以单独解码
+        for chunk in body.chunks(4) {
+            decoded.extend(base64::engine::general_purpose::STANDARD.decode(chunk).unwrap());
+        }
+    }
+    assert_eq!(decoded, sent);
+}
@@ -1,5 +1,7 @@
 use rstest::rstest;
-use rust_rtsp_bruter::iterator::ip_port_parser::{parse_ip_port};
+use rust_rtsp_bruter::iterator::ip_iterator::{IpIterator, IpPortAddr, TargetSpec};
+use rust_rtsp_bruter::iterator::ip_port_parser::{parse_ip_port, parse_ip_range};
+use rust_rtsp_bruter::iterator::ip_reader::IpReader;
 use std::net::{IpAddr, Ipv4Addr};
 
 /// 测试单个IP地址解析
@@ -249,6 +251,25 @@ fn test_cidr_boundaries(#[case] input: &str) {
     assert!(!ips.is_empty());
 }
 
+/// 测试短前缀的CIDR按需展开，不会因为地址空间过大而失败
+#[rstest]
+#[case("10.1.2.3/8", 1 << 24, "10.0.0.0", "10.255.255.255")]
+#[case("172.16.0.0/12", 1 << 20, "172.16.0.0", "172.31.255.255")]
+#[case("192.168.1.0/1", 1 << 31, "128.0.0.0", "255.255.255.255")]
+#[case("192.168.1.0/0", 1 << 32, "0.0.0.0", "255.255.255.255")]
+fn test_short_cidr_prefix(
+    #[case] input: &str,
+    #[case] len: usize,
+    #[case] first: Ipv4Addr,
+    #[case] last: Ipv4Addr,
+) {
+    let range = parse_ip_range(input).unwrap();
+    assert_eq!(range.ips.len(), len);
+    assert_eq!(range.ips.get(0), Some(IpAddr::V4(first)));
+    assert_eq!(range.ips.get(len - 1), Some(IpAddr::V4(last)));
+    assert_eq!(range.ips.get(len), None);
+}
+
 /// 测试错误处理
 #[rstest]
 #[case("invalid-ip")]
@@ -312,3 +333,213 @@ fn test_cidr_with_port(#[case] input: &str, #[case] expected: Vec<(IpAddr, Vec<u
         assert_eq!(ip_port.ports, expected[i].1);
     }
 }
+
+/// 测试IPv6地址、带方括号的端口和CIDR
+#[rstest]
+#[case("fe80::1", vec!["fe80::1"], vec![])]
+#[case("[fe80::1]", vec!["fe80::1"], vec![])]
+#[case("[fe80::1]:554", vec!["fe80::1"], vec![554])]
+#[case("[2001:db8::10]:{554,8554}", vec!["2001:db8::10"], vec![554, 8554])]
+#[case("2001:db8::5/126", vec!["2001:db8::4", "2001:db8::5", "2001:db8::6", "2001:db8::7"], vec![])]
+#[case("[2001:db8::/127]:554", vec!["2001:db8::", "2001:db8::1"], vec![554])]
+fn test_ipv6(#[case] input: &str, #[case] expected_ips: Vec<&str>, #[case] expected_ports: Vec<u16>) {
+    let result = parse_ip_port(input).unwrap();
+    let ips: Vec<IpAddr> = result.iter().map(|ip_port| ip_port.ip).collect();
+    let expected: Vec<IpAddr> = expected_ips.iter().map(|ip| ip.parse().unwrap()).collect();
+    assert_eq!(ips, expected);
+    for ip_port in result {
+        assert_eq!(ip_port.ports, expected_ports);
+    }
+}
+
+/// 测试IPv6 CIDR的范围限制和错误格式
+#[rstest]
+#[case("2001:db8::/64")]
+#[case("2001:db8::/111")]
+#[case("2001:db8::/129")]
+#[case("[fe80::1]554")]
+#[case("[fe80::1:554")]
+#[case("[fe80::zz]:554")]
+fn test_ipv6_errors(#[case] input: &str) {
+    assert!(parse_ip_port(input).is_err());
+}
+
+/// 测试IpPortAddr解析和显示IPv6地址时使用方括号
+#[test]
+fn test_ipv6_ip_port_addr() {
+    let addr: IpPortAddr = "[fe80::1]:554".parse().unwrap();
+    assert_eq!(addr.ip, "fe80::1".parse::<IpAddr>().unwrap());
+    assert_eq!(addr.port, 554);
+    assert_eq!(addr.to_string(), "[fe80::1]:554");
+    assert_eq!(addr.host(), "[fe80::1]");
+    assert!("fe80::1:554".parse::<IpPortAddr>().is_err());
+
+    let addr: IpPortAddr = "192.168.1.1:8554".parse().unwrap();
+    assert_eq!(addr.to_string(), "192.168.1.1:8554");
+    assert_eq!(addr.host(), "192.168.1.1");
+
+    let ips: Vec<IpPortAddr> = IpIterator::from_strings(vec![
+        "fe80::1".to_string(),
+        "[fe80::2]".to_string(),
+        "[fe80::3]:8554".to_string(),
+        "10.0.0.1".to_string(),
+    ])
+    .unwrap()
+    .collect();
+    assert_eq!(
+        ips.iter().map(|ip| ip.to_string()).collect::<Vec<_>>(),
+        vec!["[fe80::1]:554", "[fe80::2]:554", "[fe80::3]:8554", "10.0.0.1:554"]
+    );
+}
+
+/// 测试地址范围按需展开：长度和按下标取地址不需要生成完整列表
+#[test]
+fn test_lazy_ip_range() {
+    let range = parse_ip_range("10.{0-255}.0.0/16:{554,8554}").unwrap();
+    assert_eq!(range.ips.len(), 256 * 65536);
+    assert_eq!(range.ips.get(0), Some("10.0.0.0".parse().unwrap()));
+    assert_eq!(range.ips.get(65536 + 257), Some("10.1.1.1".parse().unwrap()));
+    assert_eq!(range.ips.get(256 * 65536), None);
+    assert!(range.ips.contains(&"10.200.3.4".parse().unwrap()));
+    assert!(!range.ips.contains(&"11.0.0.1".parse().unwrap()));
+
+    let targets = IpIterator::from_specs(vec![TargetSpec::from_range(range)]);
+    assert_eq!(targets.size_hint(), (256 * 65536 * 2, Some(256 * 65536 * 2)));
+    let first: Vec<String> = targets.take(3).map(|addr| addr.to_string()).collect();
+    assert_eq!(first, vec!["10.0.0.0:554", "10.0.0.0:8554", "10.0.0.1:554"]);
+
+    let range = parse_ip_range("2001:db8::/112").unwrap();
+    assert_eq!(range.ips.len(), 1 << 16);
+    assert_eq!(range.ips.get(255), Some("2001:db8::ff".parse().unwrap()));
+}
+
+/// 测试同一规则内重复的端口只保留一次
+#[rstest]
+#[case("1.2.3.4:{554,554}", vec![554])]
+#[case("1.2.3.4:{550-556,554}", vec![550, 551, 552, 553, 554, 555, 556])]
+#[case("1.2.3.4:{8554,554-555,8554}", vec![8554, 554, 555])]
+#[case("[::1]:{554,554}", vec![554])]
+#[case("[::1]:{554,8554,554}", vec![554, 8554])]
+#[case("1.2.3.4:{1-65535,80,1-65535}", (1..=65535).collect())]
+fn test_duplicate_ports(#[case] input: &str, #[case] expected_ports: Vec<u16>) {
+    let range = parse_ip_range(input).unwrap();
+    assert_eq!(range.ports, expected_ports);
+    let targets = IpIterator::from_specs(vec![TargetSpec::from_range(range)]);
+    let len = expected_ports.len();
+    assert_eq!(targets.size_hint(), (len, Some(len)));
+    assert_eq!(targets.count(), len);
+}
+
+/// 测试目标数量：单个地址与范围的重复在创建时计算，范围之间有重叠时只给出上限
+#[test]
+fn test_target_count() {
+    let specs = |inputs: &[&str]| {
+        let specs = inputs
+            .iter()
+            .map(|input| TargetSpec::from_range(parse_ip_range(input).unwrap()))
+            .collect();
+        IpIterator::from_specs(specs)
+    };
+
+    // 范围之间没有重叠时数量是精确的
+    let targets = specs(&[
+        "192.168.1.5",
+        "192.168.1.0/24:554",
+        "192.168.1.5:554",
+        "192.168.1.7:8554",
+        "10.0.0.{1-3}",
+        "[fe80::/124]:{554,8554}",
+        "[fe80::8]:554",
+    ]);
+    assert_eq!(targets.size_hint(), (292, Some(292)));
+    assert_eq!(targets.count(), 292);
+
+    // 范围之间有重叠时迭代时去重，数量不超过上限
+    let targets = specs(&[
+        "192.168.1.5",
+        "192.168.{0-3}.0/30:{554,8554}",
+        "192.168.1.0/24:554",
+        "192.168.{1,3}.{1-5}:{80,554}",
+        "192.168.1.5:8554",
+        "[fe80::/124]:{554,8554}",
+        "[fe80::8]:554",
+        "fe80::/120",
+    ]);
+    let (_, Some(max)) = targets.size_hint() else {
+        panic!("IpIterator has an upper bound");
+    };
+    assert!(max >= 569);
+    assert_eq!(targets.count(), 569);
+}
+
+/// 测试带http://前缀的目标通过HTTP隧道连接，未指定端口时使用80，与不带前缀的相同地址是不同的目标
+#[tokio::test]
+async fn test_http_tunnel_targets() {
+    let range = parse_ip_range("http://10.0.0.{1-2}:{80,8080}").unwrap();
+    assert!(range.http_tunnel);
+    assert!(!parse_ip_range("10.0.0.1:80").unwrap().http_tunnel);
+    assert!(parse_ip_range("HTTP://[fe80::1]:80").unwrap().http_tunnel);
+
+    let file = std::env::temp_dir().join(format!("tunnel_targets_{}.txt", std::process::id()));
+    std::fs::write(
+        &file,
+        "http://10.0.0.1\n10.0.0.1:80\nhttp://10.0.0.{1-2}:80\n",
+    )
+    .unwrap();
+    let targets = IpReader::from_file(file.to_str().unwrap())
+        .into_iterator()
+        .await
+        .unwrap();
+    std::fs::remove_file(&file).unwrap();
+
+    assert_eq!(targets.size_hint(), (3, Some(3)));
+    let addrs: Vec<IpPortAddr> = targets.collect();
+    let tunnels: Vec<bool> = addrs.iter().map(|addr| addr.http_tunnel).collect();
+    assert_eq!(tunnels, vec![true, false, true]);
+    let addrs: Vec<String> = addrs.iter().map(|addr| addr.to_string()).collect();
+    assert_eq!(
+        addrs,
+        vec!["http://10.0.0.1:80", "10.0.0.1:80", "http://10.0.0.2:80"]
+    );
+}
+
+/// 测试重复的目标只出现一次，范围之间有重叠时size_hint给出上限
+#[tokio::test]
+async fn test_ip_iterator_dedup() {
+    let file = std::env::temp_dir().join(format!("ip_targets_{}.txt", std::process::id()));
+    std::fs::write(
+        &file,
+        "192.168.1.{1-4}\n192.168.1.0/30\n192.168.1.2:554\n192.168.1.2:8554\n10.0.0.1\n",
+    )
+    .unwrap();
+    let mut targets = IpReader::from_file(file.to_str().unwrap())
+        .into_iterator()
+        .await
+        .unwrap();
+    std::fs::remove_file(&file).unwrap();
+
+    assert_eq!(targets.size_hint(), (0, Some(10)));
+    targets.next();
+    assert_eq!(targets.size_hint(), (0, Some(9)));
+    let rest: Vec<String> = targets.clone().map(|addr| addr.to_string()).collect();
+    assert_eq!(
+        rest,
+        vec![
+            "192.168.1.2:554",
+            "192.168.1.3:554",
+            "192.168.1.4:554",
+            "192.168.1.0:554",
+            "192.168.1.2:8554",
+            "10.0.0.1:554",
+        ]
+    );
+    assert_eq!(targets.count(), 6);
+
+    let targets = IpIterator::new(vec![
+        "10.0.0.1:554".parse().unwrap(),
+        "10.0.0.1:554".parse().unwrap(),
+        "10.0.0.2:554".parse().unwrap(),
+    ]);
+    assert_eq!(targets.size_hint(), (2, Some(2)));
+    assert_eq!(targets.count(), 2);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{MockReply, MockRequest, MockRtspServer, basic_auth_handler};
+use rstest::rstest;
+use rust_rtsp_bruter::brute::BruteForcer;
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::iterator::ip_iterator::IpPortAddr;
+use rust_rtsp_bruter::rtsp::client::RtspClient;
+use rust_rtsp_bruter::rtsp::common::RtspResponse;
+use rust_rtsp_bruter::rtsp::path_discovery::{
+    DiscoveredPath, PathStatus, load_stream_paths, normalize_path,
+};
+use rust_rtsp_bruter::rtsp::rtsp_worker::RTSP_WORKER_MANAGER;
+
+// 模拟只有/live.sdp和大华路径存在的摄像头
+fn camera_handler(request: &MockRequest) -> MockReply {
+    let path = request.url.splitn(4, '/').nth(3).unwrap_or_default();
+    match path {
+        "live.sdp" | "cam/realmonitor?channel=1&subtype=0" => basic_auth_handler(request),
+        _ => MockReply::new(request, "404 Not Found", &[], ""),
+    }
+}
+
+// 测试根据无认证请求的状态码判断路径状态
+#[rstest]
+#[case("RTSP/1.0 200 OK\r\n\r\n", Some(PathStatus::Open))]
+#[case("RTSP/1.0 401 Unauthorized\r\n\r\n", Some(PathStatus::AuthRequired))]
+#[case("RTSP/1.0 404 Not Found\r\n\r\n", Some(PathStatus::NotFound))]
+#[case("RTSP/1.0 503 Service Unavailable\r\n\r\n", None)]
+fn test_path_status_from_response(#[case] raw: &str, #[case] expected: Option<PathStatus>) {
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    assert_eq!(PathStatus::from_response(&response), expected);
+}
+
+// 测试读取流路径字典
+#[test]
+fn test_load_stream_paths() {
+    assert_eq!(normalize_path(" live.sdp "), "/live.sdp");
+    assert_eq!(normalize_path("/11"), "/11");
+
+    let file = std::env::temp_dir().join(format!("stream_paths_{}.txt", std::process::id()));
+    std::fs::write(
+        &file,
+        "# 海康威视\nStreaming/Channels/101\n\n/live.sdp\n/live.sdp\n",
+    )
+    .unwrap();
+    let paths = load_stream_paths(file.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(paths, vec!["/Streaming/Channels/101", "/live.sdp"]);
+}
+
+// 测试区分404和401，并保留路径中的查询参数
+#[tokio::test]
+async fn test_client_distinguishes_missing_paths() {
+    let server = MockRtspServer::start(camera_handler).await;
+
+    let response = RtspClient::new("", "")
+        .probe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(response.status_code, 404);
+    let response = RtspClient::new("", "")
+        .probe(&server.url("/cam/realmonitor?channel=1&subtype=0"))
+        .await
+        .unwrap();
+    assert_eq!(response.status_code, 401);
+
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert!(matches!(result, AuthenticationResult::NotFound));
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/live.sdp"))
+        .await
+        .unwrap();
+    assert!(matches!(result, AuthenticationResult::Success));
+}
+
+// 测试探测流路径后使用需要认证的路径尝试凭据，并记录探测到的路径
+#[tokio::test(flavor = "multi_thread")]
+async fn test_brute_forcer_discovers_paths() {
+    let server = MockRtspServer::start(camera_handler).await;
+    let ip_port = IpPortAddr::new(server.addr.ip(), server.addr.port());
+    let paths: Vec<String> = [
+        "/",
+        "/Streaming/Channels/101",
+        "/live.sdp",
+        "/cam/realmonitor?channel=1&subtype=0",
+    ]
+    .iter()
+    .map(|path| path.to_string())
+    .collect();
+    RTSP_WORKER_MANAGER.start().await;
+
+    let brute_forcer = BruteForcer::new().with_stream_paths(Some(paths.clone()));
+    let discovered = brute_forcer.discover_paths(&ip_port, &paths).await;
+    assert_eq!(
+        discovered,
+        vec![
+            DiscoveredPath {
+                path: "/live.sdp".to_string(),
+                status: PathStatus::AuthRequired,
+            },
+            DiscoveredPath {
+                path: "/cam/realmonitor?channel=1&subtype=0".to_string(),
+                status: PathStatus::AuthRequired,
+            },
+        ]
+    );
+
+    let found = brute_forcer
+        .try_credentials("admin", "admin", &ip_port, "/live.sdp")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.path, "/live.sdp");
+
+    // 找到凭据后其他路径也使用该凭据验证
+    let recorded = brute_forcer.discovered_paths().remove(&ip_port).unwrap();
+    assert!(
+        recorded
+            .iter()
+            .all(|discovered| discovered.status == PathStatus::Authorized)
+    );
+}
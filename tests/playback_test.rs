@@ -0,0 +1,264 @@
+mod common;
+
+use common::{MockReply, MockRequest, MockRtspServer};
+use rstest::rstest;
+use rust_rtsp_bruter::brute::BruteForcer;
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::iterator::credential_iterator::CredentialIterator;
+use rust_rtsp_bruter::iterator::ip_iterator::{IpIterator, IpPortAddr};
+use rust_rtsp_bruter::rtsp::auth::AuthPolicy;
+use rust_rtsp_bruter::rtsp::client::{ClientOptions, RtspClient};
+use rust_rtsp_bruter::rtsp::common::build_rtsp_request;
+use rust_rtsp_bruter::rtsp::connection::RtspConnection;
+use rust_rtsp_bruter::rtsp::playback::{
+    PlaybackStatus, PlaybackVerifier, is_rtp_packet, parse_interleaved, parse_session_id,
+};
+use rust_rtsp_bruter::rtsp::rtsp_worker::RTSP_WORKER_MANAGER;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SDP: &str = "v=0\r\ns=Mock\r\nt=0 0\r\na=control:*\r\n\
+m=video 0 RTP/AVP 96\r\na=rtpmap:96 H264/90000\r\na=control:trackID=1\r\n\
+m=audio 0 RTP/AVP 8\r\na=control:trackID=2\r\n";
+
+// 交织的RTCP帧和RTP帧（版本2，负载类型96）
+const FRAMES: &[u8] = &[
+    b'$', 1, 0, 8, 0x80, 0xC8, 0, 1, 0, 0, 0, 0, b'$', 0, 0, 12, 0x80, 0x60, 0, 1, 0, 0, 0, 0, 0,
+    0, 0, 1,
+];
+
+fn verify_options() -> ClientOptions {
+    ClientOptions {
+        verify_playback: true,
+        ..Default::default()
+    }
+}
+
+// 模拟摄像头：describe_auth控制DESCRIBE是否需要认证，SETUP和PLAY始终需要认证，send_media控制PLAY后是否发送数据
+fn camera_handler(request: &MockRequest, describe_auth: bool, send_media: bool) -> MockReply {
+    let authorized = request.header("Authorization") == Some("Basic YWRtaW46YWRtaW4=");
+    let unauthorized = || {
+        MockReply::new(
+            request,
+            "401 Unauthorized",
+            &["WWW-Authenticate: Basic realm=\"mock\""],
+            "",
+        )
+    };
+    match request.method.as_str() {
+        "DESCRIBE" if authorized || !describe_auth => {
+            MockReply::new(request, "200 OK", &["Content-Type: application/sdp"], SDP)
+        }
+        "SETUP" | "PLAY" | "DESCRIBE" if !authorized => unauthorized(),
+        "SETUP" => MockReply::new(
+            request,
+            "200 OK",
+            &[
+                "Session: 12345678;timeout=60",
+                "Transport: RTP/AVP/TCP;unicast;interleaved=0-1",
+            ],
+            "",
+        ),
+        "PLAY" => {
+            let mut reply = MockReply::new(request, "200 OK", &["Session: 12345678"], "");
+            if send_media {
+                reply.data.extend_from_slice(FRAMES);
+            }
+            reply
+        }
+        _ => MockReply::new(request, "200 OK", &[], ""),
+    }
+}
+
+// 测试只有DESCRIBE请求携带Accept头，请求中不再有固定的Transport头
+#[test]
+fn test_build_request_headers() {
+    let describe = build_rtsp_request(
+        "DESCRIBE",
+        "rtsp://1.2.3.4:554/",
+        "1.2.3.4",
+        554,
+        1,
+        None,
+        &[],
+    );
+    assert!(describe.contains("Accept: application/sdp\r\n"));
+    assert!(!describe.contains("Transport"));
+
+    let setup = build_rtsp_request(
+        "SETUP",
+        "rtsp://1.2.3.4:554/trackID=1",
+        "1.2.3.4",
+        554,
+        2,
+        Some("Basic YWRtaW46YWRtaW4="),
+        &[("Transport", "RTP/AVP/TCP;unicast;interleaved=0-1")],
+    );
+    assert!(!setup.contains("Accept"));
+    assert!(setup.contains("Transport: RTP/AVP/TCP;unicast;interleaved=0-1\r\n"));
+    assert!(setup.ends_with("Authorization: Basic YWRtaW46YWRtaW4=\r\n\r\n"));
+}
+
+// 测试解析Transport和Session头
+#[rstest]
+#[case("RTP/AVP/TCP;unicast;interleaved=0-1", Some((0, 1)))]
+#[case("RTP/AVP/TCP;unicast;interleaved=2-3;ssrc=1234ABCD", Some((2, 3)))]
+#[case("RTP/AVP/TCP;interleaved=4", Some((4, 5)))]
+#[case("RTP/AVP;unicast;client_port=8000-8001", None)]
+fn test_parse_interleaved(#[case] transport: &str, #[case] expected: Option<(u8, u8)>) {
+    assert_eq!(parse_interleaved(transport), expected);
+}
+
+#[test]
+fn test_parse_session_and_rtp() {
+    assert_eq!(parse_session_id("12345678;timeout=60"), "12345678");
+    assert_eq!(parse_session_id(" 12345678 "), "12345678");
+    assert!(is_rtp_packet(&FRAMES[16..]));
+    assert!(!is_rtp_packet(&FRAMES[4..12]));
+}
+
+// 测试DESCRIBE成功后SETUP/PLAY收到RTP数据包，并在结束时TEARDOWN
+#[tokio::test]
+async fn test_verify_playback_streaming() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let requests_clone = requests.clone();
+    let server = MockRtspServer::start(move |request| {
+        requests_clone.lock().unwrap().push(request.clone());
+        camera_handler(request, true, true)
+    })
+    .await;
+
+    let outcome = RtspClient::new("admin", "admin")
+        .with_options(verify_options())
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(outcome.playback, Some(PlaybackStatus::Streaming));
+
+    let requests = requests.lock().unwrap();
+    let describe = requests
+        .iter()
+        .find(|request| request.method == "DESCRIBE")
+        .unwrap();
+    assert_eq!(describe.header("Transport"), None);
+    let setup: Vec<&MockRequest> = requests
+        .iter()
+        .filter(|request| request.method == "SETUP")
+        .collect();
+    // 第一个SETUP收到401后携带认证头重发，只SETUP视频轨道
+    assert_eq!(setup.len(), 2);
+    assert_eq!(setup[1].url, server.url("/live/trackID=1"));
+    assert_eq!(
+        setup[1].header("Transport"),
+        Some("RTP/AVP/TCP;unicast;interleaved=0-1")
+    );
+    // PLAY和TEARDOWN直接携带认证头和会话ID
+    for method in ["PLAY", "TEARDOWN"] {
+        let request = requests
+            .iter()
+            .find(|request| request.method == method)
+            .unwrap();
+        assert_eq!(request.url, server.url("/live"));
+        assert_eq!(request.header("Session"), Some("12345678"));
+        assert!(request.header("Authorization").is_some());
+    }
+}
+
+// 测试DESCRIBE无需认证但SETUP需要认证的设备：凭据由SETUP/PLAY验证，不作为无需认证的目标
+#[tokio::test]
+async fn test_verify_playback_setup_requires_auth() {
+    let server = MockRtspServer::start(|request| camera_handler(request, false, true)).await;
+
+    let outcome = RtspClient::new("admin", "wrong")
+        .with_options(verify_options())
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Failed);
+    let playback = outcome.playback.unwrap();
+    assert!(!playback.media_flowed());
+    assert_eq!(playback.to_string(), "SETUP rejected (401 Unauthorized)");
+
+    let outcome = RtspClient::new("admin", "admin")
+        .with_options(verify_options())
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(outcome.playback, Some(PlaybackStatus::Streaming));
+}
+
+// 测试暴力破解DESCRIBE无需认证但SETUP需要认证的设备：错误的凭据不会取消目标，记录使SETUP/PLAY成功的凭据
+#[tokio::test(flavor = "multi_thread")]
+async fn test_brute_force_setup_requires_auth() {
+    RTSP_WORKER_MANAGER.start().await;
+    let server = MockRtspServer::start(|request| camera_handler(request, false, true)).await;
+    let target = IpPortAddr::new(server.addr.ip(), server.addr.port());
+    let brute_forcer = BruteForcer::new().with_client_options(verify_options());
+
+    let result = brute_forcer
+        .try_credentials("admin", "wrong", &target, "/live")
+        .await
+        .unwrap();
+    eprintln!("MARK1");
+    assert!(result.is_none());
+    assert!(!brute_forcer.host_token(&target).is_cancelled());
+    assert!(!brute_forcer.is_host_skipped(&target));
+    assert!(!brute_forcer.has_valid_credentials_for_ip(&target));
+
+    let found = brute_forcer
+        .try_credentials("admin", "admin", &target, "/live")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (found.username.as_str(), found.password.as_str()),
+        ("admin", "admin")
+    );
+    assert_eq!(found.playback, Some(PlaybackStatus::Streaming));
+    assert_eq!(brute_forcer.found_credentials(), vec![found]);
+
+    // 完整的流水线中错误的凭据之后仍然尝试剩余的凭据
+    let pipeline = BruteForcer::new()
+        .with_max_concurrent(1)
+        .with_client_options(verify_options())
+        .with_ip_iterator(IpIterator::new(vec![target.clone()]))
+        .with_cred_iterator(CredentialIterator::new(
+            vec!["admin".to_string()],
+            vec![
+                "12345".to_string(),
+                "wrong".to_string(),
+                "admin".to_string(),
+            ],
+        ));
+    pipeline.brute_force().await.unwrap();
+    let found: Vec<(String, String)> = pipeline
+        .found_credentials()
+        .into_iter()
+        .map(|cred| (cred.username, cred.password))
+        .collect();
+    assert_eq!(found, vec![("admin".to_string(), "admin".to_string())]);
+}
+
+// 测试PLAY成功但没有收到媒体数据
+#[tokio::test]
+async fn test_verify_playback_no_media() {
+    let server = MockRtspServer::start(|request| camera_handler(request, true, false)).await;
+    let addr = IpPortAddr::new(server.addr.ip(), server.addr.port());
+
+    let connection = RtspConnection::connect(addr).await.unwrap();
+    let status = PlaybackVerifier::new(
+        connection,
+        &server.addr.ip().to_string(),
+        server.addr.port(),
+        "admin",
+        "admin",
+        AuthPolicy::default(),
+    )
+    .with_media_timeout(Duration::from_millis(200))
+    .verify(&server.url("/live"), None)
+    .await
+    .unwrap();
+    assert_eq!(status, PlaybackStatus::NoMedia);
+}
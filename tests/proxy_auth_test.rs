@@ -0,0 +1,286 @@
+mod common;
+
+use common::{MockReply, MockRequest, MockRtspServer, basic_auth_handler};
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::iterator::ip_iterator::IpPortAddr;
+use rust_rtsp_bruter::rtsp::auth::{self, AuthPolicy, AuthType, Authenticator};
+use rust_rtsp_bruter::rtsp::client::{
+    ClientOptions, CredentialTarget, ProxyCredentials, RtspClient,
+};
+use rust_rtsp_bruter::rtsp::common::RtspResponse;
+use rust_rtsp_bruter::rtsp::connection::RtspConnection;
+use rust_rtsp_bruter::rtsp::playback::{PlaybackStatus, PlaybackVerifier};
+use std::sync::{Arc, Mutex};
+
+// "proxy:secret"的base64编码
+const PROXY_AUTHORIZATION: &str = "Basic cHJveHk6c2VjcmV0";
+
+// 模拟代理：没有正确的Proxy-Authorization时返回407，否则转发给Basic认证的摄像头
+fn proxy_handler(request: &MockRequest) -> MockReply {
+    match request.header("Proxy-Authorization") {
+        Some(PROXY_AUTHORIZATION) => basic_auth_handler(request),
+        _ => MockReply::new(
+            request,
+            "407 Proxy Authentication Required",
+            &["Proxy-Authenticate: Basic realm=\"relay\""],
+            "",
+        ),
+    }
+}
+
+// 模拟代理后面的摄像头：所有请求都需要代理认证和admin:admin的Basic认证，PLAY后发送一个RTP数据包
+fn proxied_camera_handler(request: &MockRequest) -> MockReply {
+    if request.header("Proxy-Authorization") != Some(PROXY_AUTHORIZATION) {
+        return proxy_handler(request);
+    }
+    if request.header("Authorization") != Some("Basic YWRtaW46YWRtaW4=") {
+        return basic_auth_handler(request);
+    }
+    match request.method.as_str() {
+        "DESCRIBE" => MockReply::new(
+            request,
+            "200 OK",
+            &["Content-Type: application/sdp"],
+            "v=0\r\ns=Mock\r\nm=video 0 RTP/AVP 96\r\na=control:trackID=1\r\n",
+        ),
+        "SETUP" => MockReply::new(
+            request,
+            "200 OK",
+            &[
+                "Session: 12345678",
+                "Transport: RTP/AVP/TCP;unicast;interleaved=0-1",
+            ],
+            "",
+        ),
+        "PLAY" => {
+            let mut reply = MockReply::new(request, "200 OK", &["Session: 12345678"], "");
+            reply
+                .data
+                .extend_from_slice(&[b'$', 0, 0, 12, 0x80, 0x60, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1]);
+            reply
+        }
+        _ => MockReply::new(request, "200 OK", &[], ""),
+    }
+}
+
+async fn recording_server(
+    handler: fn(&MockRequest) -> MockReply,
+) -> (MockRtspServer, Arc<Mutex<Vec<MockRequest>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let requests_clone = requests.clone();
+    let server = MockRtspServer::start(move |request| {
+        requests_clone.lock().unwrap().push(request.clone());
+        handler(request)
+    })
+    .await;
+    (server, requests)
+}
+
+async fn recording_proxy() -> (MockRtspServer, Arc<Mutex<Vec<MockRequest>>>) {
+    recording_server(proxy_handler).await
+}
+
+fn proxy_options(username: &str, password: &str) -> ClientOptions {
+    ClientOptions {
+        proxy_credentials: Some(ProxyCredentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        }),
+        ..Default::default()
+    }
+}
+
+// 测试只解析Proxy-Authenticate头中的代理挑战
+#[test]
+fn test_parse_proxy_challenges() {
+    let response = RtspResponse::parse(
+        b"RTSP/1.0 407 Proxy Authentication Required\r\nCSeq: 1\r\n\
+WWW-Authenticate: Basic realm=\"camera\"\r\n\
+Proxy-Authenticate: Digest realm=\"relay\", nonce=\"abc\"\r\n\r\n",
+    )
+    .unwrap();
+    let offered = auth::parse_proxy_challenges(&response).unwrap();
+    assert_eq!(offered.len(), 1);
+    match &offered[0] {
+        AuthType::Digest(info) => {
+            assert_eq!(info.realm, "relay");
+            assert_eq!(info.nonce, "abc");
+        }
+        other => panic!("Expected Digest challenge, got {:?}", other),
+    }
+    let offered = auth::parse_response_challenges(&response).unwrap();
+    assert!(matches!(offered[..], [AuthType::Basic(_)]));
+}
+
+// 测试配置代理凭据后先通过代理认证，再进行摄像头认证
+#[tokio::test]
+async fn test_describe_through_authenticating_proxy() {
+    let (server, requests) = recording_proxy().await;
+
+    let result = RtspClient::new("admin", "admin")
+        .with_options(proxy_options("proxy", "secret"))
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::Success);
+
+    // 407后携带Proxy-Authorization重试，之后的请求都携带该头
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].header("Proxy-Authorization"), None);
+    assert_eq!(
+        requests[1].header("Proxy-Authorization"),
+        Some(PROXY_AUTHORIZATION)
+    );
+    assert_eq!(requests[1].header("Authorization"), None);
+    assert_eq!(
+        requests[2].header("Proxy-Authorization"),
+        Some(PROXY_AUTHORIZATION)
+    );
+    assert_eq!(
+        requests[2].header("Authorization"),
+        Some("Basic YWRtaW46YWRtaW4=")
+    );
+}
+
+// 测试没有代理凭据或代理凭据错误时返回407结果
+#[tokio::test]
+async fn test_proxy_authentication_required() {
+    let (server, _) = recording_proxy().await;
+
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::ProxyAuthenticationRequired);
+
+    let result = RtspClient::new("admin", "admin")
+        .with_options(proxy_options("proxy", "wrong"))
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::ProxyAuthenticationRequired);
+}
+
+// 测试枚举代理凭据：代理接受凭据即为成功，不对摄像头进行认证
+#[tokio::test]
+async fn test_brute_force_proxy_credentials() {
+    let (server, requests) = recording_proxy().await;
+    let options = ClientOptions {
+        credential_target: CredentialTarget::Proxy,
+        ..Default::default()
+    };
+
+    let result = RtspClient::new("proxy", "wrong")
+        .with_options(options.clone())
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::Failed);
+
+    let result = RtspClient::new("proxy", "secret")
+        .with_options(options)
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::Success);
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 4);
+    assert!(
+        requests
+            .iter()
+            .all(|request| request.header("Authorization").is_none())
+    );
+}
+
+// 测试经过需要认证的代理验证播放：SETUP/PLAY同样携带Proxy-Authorization，不会被407拒绝
+#[tokio::test]
+async fn test_verify_playback_through_authenticating_proxy() {
+    let (server, requests) = recording_server(proxied_camera_handler).await;
+    let options = ClientOptions {
+        verify_playback: true,
+        ..proxy_options("proxy", "secret")
+    };
+
+    let outcome = RtspClient::new("admin", "admin")
+        .with_options(options)
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(outcome.playback, Some(PlaybackStatus::Streaming));
+
+    let requests = requests.lock().unwrap();
+    let playback: Vec<&MockRequest> = requests
+        .iter()
+        .filter(|request| request.method != "DESCRIBE")
+        .collect();
+    assert!(playback.iter().any(|request| request.method == "PLAY"));
+    assert!(
+        playback
+            .iter()
+            .all(|request| request.header("Proxy-Authorization") == Some(PROXY_AUTHORIZATION))
+    );
+}
+
+// 测试验证器在SETUP收到407后使用代理凭据重试一次
+#[tokio::test]
+async fn test_verifier_retries_on_proxy_challenge() {
+    let (server, requests) = recording_server(proxied_camera_handler).await;
+    let addr = IpPortAddr::new(server.addr.ip(), server.addr.port());
+    let connection = RtspConnection::connect(addr).await.unwrap();
+
+    let status = PlaybackVerifier::new(
+        connection,
+        &server.addr.ip().to_string(),
+        server.addr.port(),
+        "admin",
+        "admin",
+        AuthPolicy::default(),
+    )
+    .with_proxy_auth(Authenticator::new("proxy", "secret"), None)
+    .verify(&server.url("/live"), None)
+    .await
+    .unwrap();
+    assert_eq!(status, PlaybackStatus::Streaming);
+
+    // 407后携带Proxy-Authorization重试，401后再携带摄像头的认证头
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].header("Proxy-Authorization"), None);
+    assert_eq!(
+        requests[1].header("Proxy-Authorization"),
+        Some(PROXY_AUTHORIZATION)
+    );
+    assert_eq!(requests[1].header("Authorization"), None);
+    assert_eq!(
+        requests[2].header("Authorization"),
+        Some("Basic YWRtaW46YWRtaW4=")
+    );
+}
+
+// 测试枚举代理凭据时播放验证不把代理凭据发送给摄像头
+#[tokio::test]
+async fn test_verify_playback_with_proxy_credentials() {
+    let (server, requests) = recording_server(proxied_camera_handler).await;
+    let options = ClientOptions {
+        verify_playback: true,
+        credential_target: CredentialTarget::Proxy,
+        ..Default::default()
+    };
+
+    let outcome = RtspClient::new("proxy", "secret")
+        .with_options(options)
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(outcome.playback.unwrap().to_string(), "SETUP rejected (401 Unauthorized)");
+
+    let requests = requests.lock().unwrap();
+    assert!(requests.iter().any(|request| request.method == "SETUP"));
+    assert!(
+        requests
+            .iter()
+            .all(|request| request.header("Authorization").is_none())
+    );
+}
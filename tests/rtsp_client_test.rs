@@ -0,0 +1,485 @@
+mod common;
+
+use common::{MockReply, MockRequest, MockRtspServer, basic_auth_handler};
+use rstest::rstest;
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::rtsp::auth::{AuthPolicy, AuthType, Authenticator, DigestAuthInfo};
+use rust_rtsp_bruter::rtsp::challenge_cache::ChallengeCache;
+use rust_rtsp_bruter::rtsp::client::{ClientOptions, RtspClient};
+use rust_rtsp_bruter::rtsp::sdp::Codec;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn keep_alive_options() -> ClientOptions {
+    ClientOptions {
+        keep_alive: true,
+        ..Default::default()
+    }
+}
+
+// 测试默认模式下每次尝试都使用新连接
+#[tokio::test]
+async fn test_describe_without_keep_alive() {
+    let server = MockRtspServer::start(basic_auth_handler).await;
+
+    let result = RtspClient::new("admin", "wrong")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert!(matches!(result, AuthenticationResult::Failed));
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert!(matches!(result, AuthenticationResult::Success));
+
+    assert_eq!(server.connections(), 2);
+    assert_eq!(server.requests(), 4);
+}
+
+// 测试keep-alive模式下多次凭据尝试复用同一连接，CSeq持续递增
+#[tokio::test]
+async fn test_keep_alive_reuses_connection() {
+    let cseqs = Arc::new(Mutex::new(Vec::new()));
+    let cseqs_clone = cseqs.clone();
+    let server = MockRtspServer::start(move |request| {
+        cseqs_clone.lock().unwrap().push(request.cseq());
+        basic_auth_handler(request)
+    })
+    .await;
+
+    for password in ["123456", "12345", "admin"] {
+        let client = RtspClient::new("admin", password).with_options(keep_alive_options());
+        let result = client.describe(&server.url("/")).await.unwrap();
+        match password {
+            "admin" => assert!(matches!(result, AuthenticationResult::Success)),
+            _ => assert!(matches!(result, AuthenticationResult::Failed)),
+        }
+    }
+
+    assert_eq!(server.connections(), 1);
+    assert_eq!(*cseqs.lock().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+}
+
+// 测试服务器发送Connection: close后透明地重新连接
+#[tokio::test]
+async fn test_keep_alive_reconnects_after_connection_close() {
+    let server = MockRtspServer::start(|request| {
+        let reply = basic_auth_handler(request);
+        if request.header("Authorization").is_none() {
+            // 每个401之后都要求关闭连接
+            MockReply::new(
+                request,
+                "401 Unauthorized",
+                &["WWW-Authenticate: Basic realm=\"mock\"", "Connection: close"],
+                "",
+            )
+            .closing()
+        } else {
+            reply
+        }
+    })
+    .await;
+
+    for password in ["wrong", "admin"] {
+        let client = RtspClient::new("admin", password).with_options(keep_alive_options());
+        let result = client.describe(&server.url("/")).await.unwrap();
+        match password {
+            "admin" => assert!(matches!(result, AuthenticationResult::Success)),
+            _ => assert!(matches!(result, AuthenticationResult::Failed)),
+        }
+    }
+
+    assert_eq!(server.requests(), 4);
+    assert!(server.connections() >= 2);
+}
+
+// 测试复用的连接被服务器关闭后自动重试
+#[tokio::test]
+async fn test_keep_alive_retries_on_stale_connection() {
+    let server = MockRtspServer::start(|request| {
+        // 每个连接只处理一次成功的认证，之后关闭连接（不发送Connection头）
+        let reply = basic_auth_handler(request);
+        if request.header("Authorization").is_some() {
+            reply.closing()
+        } else {
+            reply
+        }
+    })
+    .await;
+
+    for _ in 0..3 {
+        let client = RtspClient::new("admin", "admin").with_options(keep_alive_options());
+        let result = client.describe(&server.url("/")).await.unwrap();
+        assert!(matches!(result, AuthenticationResult::Success));
+    }
+    assert_eq!(server.connections(), 3);
+}
+
+// 简单的Digest认证处理：nonce由调用方提供，admin:admin通过
+fn digest_reply(request: &MockRequest, nonce: &str, stale: bool) -> MockReply {
+    let challenge = format!(
+        "WWW-Authenticate: Digest realm=\"mock\", nonce=\"{}\"{}",
+        nonce,
+        if stale { ", stale=TRUE" } else { "" }
+    );
+    let authorization = request.header("Authorization").unwrap_or_default();
+    let digest = AuthType::Digest(DigestAuthInfo {
+        realm: "mock".to_string(),
+        nonce: nonce.to_string(),
+        ..Default::default()
+    });
+    let expected = Authenticator::new("admin", "admin")
+        .authorization(&digest, &request.method, &request.url, b"")
+        .unwrap();
+    if authorization == expected {
+        MockReply::new(request, "200 OK", &[], "")
+    } else {
+        MockReply::new(request, "401 Unauthorized", &[&challenge], "")
+    }
+}
+
+// 测试缓存挑战后跳过无认证的探测请求
+#[tokio::test]
+async fn test_cached_challenge_skips_probe() {
+    let server =
+        MockRtspServer::start(|request| digest_reply(request, "fixed-nonce", false)).await;
+    let options = ClientOptions {
+        challenge_cache: Some(ChallengeCache::default()),
+        ..Default::default()
+    };
+
+    for password in ["1", "2", "admin"] {
+        let client = RtspClient::new("admin", password).with_options(options.clone());
+        let result = client.describe(&server.url("/")).await.unwrap();
+        match password {
+            "admin" => assert!(matches!(result, AuthenticationResult::Success)),
+            _ => assert!(matches!(result, AuthenticationResult::Failed)),
+        }
+    }
+
+    // 只有第一次尝试发送了无认证的探测请求
+    assert_eq!(server.requests(), 4);
+    assert_eq!(options.challenge_cache.unwrap().len(), 1);
+}
+
+// 测试多次尝试复用缓存的nonce时nonce计数递增，不会被服务器视为重放
+#[tokio::test]
+async fn test_cached_nonce_count_increments() {
+    let counts = Arc::new(Mutex::new(Vec::new()));
+    let counts_clone = counts.clone();
+    let server = MockRtspServer::start(move |request| {
+        if let Some(authorization) = request.header("Authorization") {
+            let nc = authorization
+                .split(", ")
+                .find_map(|param| param.strip_prefix("nc="))
+                .unwrap_or_default();
+            counts_clone.lock().unwrap().push(nc.to_string());
+        }
+        let challenge = "WWW-Authenticate: Digest realm=\"mock\", nonce=\"fixed-nonce\", qop=\"auth\"";
+        MockReply::new(request, "401 Unauthorized", &[challenge], "")
+    })
+    .await;
+    let options = ClientOptions {
+        challenge_cache: Some(ChallengeCache::default()),
+        ..Default::default()
+    };
+
+    for password in ["1", "2"] {
+        let client = RtspClient::new("admin", password).with_options(options.clone());
+        let result = client.describe(&server.url("/")).await.unwrap();
+        assert!(matches!(result, AuthenticationResult::Failed));
+    }
+    assert_eq!(*counts.lock().unwrap(), vec!["00000001", "00000002"]);
+}
+
+// 测试TryBoth策略下尝试所有认证方式，结果中记录服务器接受的所有方式
+#[tokio::test]
+async fn test_try_both_reports_every_accepted_scheme() {
+    let server = MockRtspServer::start(|request| {
+        match request.header("Authorization") {
+            Some(authorization) if authorization.starts_with("Basic ") => {
+                basic_auth_handler(request)
+            }
+            _ => digest_reply(request, "fixed-nonce", false),
+        }
+    })
+    .await;
+
+    let options = ClientOptions {
+        auth_policy: AuthPolicy::TryBoth,
+        ..Default::default()
+    };
+    let outcome = RtspClient::new("admin", "admin")
+        .with_options(options)
+        .describe_outcome(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(outcome.accepted_schemes, vec!["Digest", "Basic"]);
+
+    // 默认策略在第一个被接受的方式后停止
+    let outcome = RtspClient::new("admin", "admin")
+        .describe_outcome(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert!(outcome.accepted_schemes.is_empty());
+}
+
+// 测试缓存的nonce过期后使用新的nonce重新认证
+#[tokio::test]
+async fn test_cached_challenge_refreshed_on_new_nonce() {
+    let nonce = Arc::new(Mutex::new("nonce-1".to_string()));
+    let nonce_clone = nonce.clone();
+    let server = MockRtspServer::start(move |request| {
+        let nonce = nonce_clone.lock().unwrap().clone();
+        digest_reply(request, &nonce, false)
+    })
+    .await;
+    let options = ClientOptions {
+        challenge_cache: Some(ChallengeCache::default()),
+        ..Default::default()
+    };
+
+    let client = RtspClient::new("admin", "wrong").with_options(options.clone());
+    let result = client.describe(&server.url("/")).await.unwrap();
+    assert!(matches!(result, AuthenticationResult::Failed));
+
+    // 服务器更换nonce，缓存的挑战失效
+    *nonce.lock().unwrap() = "nonce-2".to_string();
+    let client = RtspClient::new("admin", "admin").with_options(options.clone());
+    let result = client.describe(&server.url("/")).await.unwrap();
+    assert!(matches!(result, AuthenticationResult::Success));
+    assert_eq!(server.requests(), 4);
+}
+
+// 测试nonce在认证过程中过期（stale=true）时使用新的nonce重新认证
+#[tokio::test]
+async fn test_stale_nonce_reauthenticates() {
+    let issued = Arc::new(Mutex::new(0));
+    let issued_clone = issued.clone();
+    let server = MockRtspServer::start(move |request| {
+        let mut issued = issued_clone.lock().unwrap();
+        if request.header("Authorization").is_none() {
+            return digest_reply(request, &format!("nonce-{}", *issued), false);
+        }
+        // 第一次认证时nonce已过期，签发新的nonce并标记stale
+        if *issued == 0 {
+            *issued += 1;
+            return digest_reply(request, &format!("nonce-{}", *issued), true);
+        }
+        digest_reply(request, &format!("nonce-{}", *issued), false)
+    })
+    .await;
+
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert!(matches!(result, AuthenticationResult::Success));
+    assert_eq!(server.requests(), 3);
+}
+
+// 测试服务器持续返回stale=true时只重新认证一次
+#[tokio::test]
+async fn test_stale_nonce_retried_once() {
+    let issued = Arc::new(Mutex::new(0));
+    let issued_clone = issued.clone();
+    let server = MockRtspServer::start(move |request| {
+        let mut issued = issued_clone.lock().unwrap();
+        *issued += 1;
+        digest_reply(request, &format!("nonce-{}", *issued), true)
+    })
+    .await;
+
+    let result = RtspClient::new("admin", "wrong")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert!(matches!(result, AuthenticationResult::Failed));
+    assert_eq!(server.requests(), 3);
+}
+
+// 测试200和401以外的响应被归类为对应的认证结果
+#[rstest]
+#[case("403 Forbidden", &[], AuthenticationResult::Forbidden)]
+#[case("404 Not Found", &[], AuthenticationResult::NotFound)]
+#[case("454 Session Not Found", &[], AuthenticationResult::SessionNotFound)]
+#[case("461 Unsupported Transport", &[], AuthenticationResult::UnsupportedTransport)]
+#[case(
+    "503 Service Unavailable",
+    &["Retry-After: 30"],
+    AuthenticationResult::ServiceUnavailable { retry_after: Some(Duration::from_secs(30)) }
+)]
+#[case(
+    "503 Service Unavailable",
+    &[],
+    AuthenticationResult::ServiceUnavailable { retry_after: None }
+)]
+#[case(
+    "302 Moved Temporarily",
+    &["Location: rtsp://10.0.0.2:554/live"],
+    AuthenticationResult::Redirect {
+        status: 302,
+        location: Some("rtsp://10.0.0.2:554/live".to_string()),
+    }
+)]
+#[case(
+    "305 Use Proxy",
+    &["Location: rtsp://10.0.0.9:554"],
+    AuthenticationResult::UseProxy {
+        proxy: Some("rtsp://10.0.0.9:554".to_string()),
+    }
+)]
+#[tokio::test]
+async fn test_response_outcomes(
+    #[case] status: &'static str,
+    #[case] headers: &'static [&'static str],
+    #[case] expected: AuthenticationResult,
+) {
+    let server = MockRtspServer::start(move |request| MockReply::new(request, status, headers, "")).await;
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, expected);
+}
+
+// 测试认证之后返回的状态码同样被归类
+#[tokio::test]
+async fn test_outcome_after_authentication() {
+    let server = MockRtspServer::start(|request| match request.header("Authorization") {
+        Some(_) => MockReply::new(request, "403 Forbidden", &[], ""),
+        None => basic_auth_handler(request),
+    })
+    .await;
+    let result = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(result, AuthenticationResult::Forbidden);
+
+    // 无法归类的状态码仍然作为协议错误
+    let server =
+        MockRtspServer::start(|request| MockReply::new(request, "500 Internal Server Error", &[], ""))
+            .await;
+    let error = RtspClient::new("admin", "admin")
+        .describe(&server.url("/"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Protocol error: Unexpected response: RTSP/1.0 500 Internal Server Error"
+    );
+}
+
+// 测试跟随同一主机上指向其他端口的重定向，并记录最终的URL
+#[tokio::test]
+async fn test_follow_redirect_to_other_port() {
+    let stream = MockRtspServer::start(basic_auth_handler).await;
+    let location = format!("Location: {}", stream.url("/live"));
+    let nvr = MockRtspServer::start(move |request| {
+        MockReply::new(request, "302 Moved Temporarily", &[&location], "")
+    })
+    .await;
+
+    let outcome = RtspClient::new("admin", "admin")
+        .describe_outcome(&nvr.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(outcome.url, stream.url("/live"));
+    assert!(outcome.redirected_from(&nvr.url("/")));
+}
+
+// 测试相对路径的重定向以及最大跳转次数
+#[tokio::test]
+async fn test_redirect_hops_are_bounded() {
+    let server = MockRtspServer::start(|request| {
+        MockReply::new(request, "301 Moved Permanently", &["Location: /loop"], "")
+    })
+    .await;
+    let options = ClientOptions {
+        max_redirects: 2,
+        ..Default::default()
+    };
+
+    let outcome = RtspClient::new("admin", "admin")
+        .with_options(options)
+        .describe_outcome(&server.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.url, server.url("/loop"));
+    assert!(matches!(
+        outcome.result,
+        AuthenticationResult::Redirect { status: 301, .. }
+    ));
+    assert_eq!(server.requests(), 3);
+}
+
+// 测试成功的结果带有SDP中的媒体列表
+#[tokio::test]
+async fn test_success_carries_media() {
+    let server = MockRtspServer::start(|request| match request.header("Authorization") {
+        // "admin:admin"的base64编码
+        Some("Basic YWRtaW46YWRtaW4=") => MockReply::new(
+            request,
+            "200 OK",
+            &[
+                "Content-Type: application/sdp",
+                "Content-Base: rtsp://127.0.0.1/live/",
+            ],
+            "v=0\r\ns=Camera\r\nm=video 0 RTP/AVP 96\r\na=rtpmap:96 H265/90000\r\na=control:trackID=0\r\n",
+        ),
+        _ => basic_auth_handler(request),
+    })
+    .await;
+
+    let outcome = RtspClient::new("admin", "admin")
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    let session = outcome.session.unwrap();
+    assert_eq!(session.media[0].codec(), Some(&Codec::H265));
+    assert_eq!(
+        session.media[0].control.as_deref(),
+        Some("rtsp://127.0.0.1/live/trackID=0")
+    );
+    assert!(!session.has_audio());
+
+    // 认证失败时没有会话描述
+    let outcome = RtspClient::new("admin", "wrong")
+        .describe_outcome(&server.url("/live"))
+        .await
+        .unwrap();
+    assert!(outcome.session.is_none());
+}
+
+// 测试IPv6目标：URL和Host头中的地址带方括号
+#[tokio::test]
+async fn test_describe_ipv6_target() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let requests_clone = requests.clone();
+    let server = MockRtspServer::start_on("[::1]:0", move |request| {
+        requests_clone.lock().unwrap().push(request.clone());
+        basic_auth_handler(request)
+    })
+    .await;
+    let url = server.url("/live");
+    assert_eq!(url, format!("rtsp://[::1]:{}/live", server.addr.port()));
+
+    let result = RtspClient::new("admin", "admin").describe(&url).await.unwrap();
+    assert_eq!(result, AuthenticationResult::Success);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in requests.iter() {
+        assert_eq!(request.url, url);
+        assert_eq!(
+            request.header("Host"),
+            Some(format!("[::1]:{}", server.addr.port()).as_str())
+        );
+    }
+}
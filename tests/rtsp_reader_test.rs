@@ -0,0 +1,96 @@
+use rust_rtsp_bruter::rtsp::common::{RtspMessage, RtspReader};
+use tokio::io::AsyncWriteExt;
+
+// 测试辅助函数：将数据按指定分段依次写入内存管道
+fn segmented_stream(segments: Vec<Vec<u8>>) -> tokio::io::DuplexStream {
+    let (client, mut server) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        for segment in segments {
+            server.write_all(&segment).await.unwrap();
+            server.flush().await.unwrap();
+            tokio::task::yield_now().await;
+        }
+    });
+    client
+}
+
+// 测试头部跨多个TCP分段到达
+#[tokio::test]
+async fn test_header_split_across_reads() {
+    let mut stream = segmented_stream(vec![
+        b"RTSP/1.0 401 Unauth".to_vec(),
+        b"orized\r\nCSeq: 1\r\nWWW-Authenticate: Basic realm=\"cam\"\r".to_vec(),
+        b"\n\r\n".to_vec(),
+    ]);
+    let response = RtspReader::new().read_response(&mut stream).await.unwrap();
+    assert_eq!(response.status_code, 401);
+    assert_eq!(response.cseq(), Some(1));
+    assert_eq!(
+        response.headers.get("WWW-Authenticate"),
+        Some("Basic realm=\"cam\"")
+    );
+}
+
+// 测试超过单次读取缓冲区大小的SDP消息体
+#[tokio::test]
+async fn test_large_body_read_completely() {
+    let body = "a=x-vendor-attribute:".to_string() + &"0123456789".repeat(1000) + "\r\n";
+    let head = format!(
+        "RTSP/1.0 200 OK\r\nCSeq: 2\r\nContent-Type: application/sdp\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    );
+    let raw = (head + &body).into_bytes();
+    let segments = raw.chunks(1500).map(|chunk| chunk.to_vec()).collect();
+    let mut stream = segmented_stream(segments);
+
+    let response = RtspReader::new().read_response(&mut stream).await.unwrap();
+    assert_eq!(response.status_code, 200);
+    assert_eq!(response.body.len(), body.len());
+    assert_eq!(response.body_text(), body);
+}
+
+// 测试跳过交织的RTP数据帧
+#[tokio::test]
+async fn test_skip_interleaved_frames() {
+    let mut raw = vec![b'$', 0, 0, 4, 0x80, 0x60, 0x00, 0x01];
+    raw.extend_from_slice(b"RTSP/1.0 200 OK\r\nCSeq: 5\r\n\r\n");
+    raw.extend_from_slice(&[b'$', 1, 0, 2, 0xAA, 0xBB]);
+    let mut stream = segmented_stream(vec![raw]);
+
+    let mut reader = RtspReader::new();
+    let response = reader.read_response(&mut stream).await.unwrap();
+    assert_eq!(response.cseq(), Some(5));
+
+    // 响应之后的数据帧保留在缓冲区中
+    match reader.read_message(&mut stream).await.unwrap() {
+        RtspMessage::Interleaved(frame) => {
+            assert_eq!(frame.channel, 1);
+            assert_eq!(frame.payload, vec![0xAA, 0xBB]);
+        }
+        other => panic!("Expected interleaved frame, got {:?}", other),
+    }
+}
+
+// 测试连续的两个响应不会互相吞并
+#[tokio::test]
+async fn test_consecutive_responses() {
+    let raw = "RTSP/1.0 200 OK\r\nCSeq: 1\r\nContent-Length: 3\r\n\r\nabcRTSP/1.0 404 Not Found\r\nCSeq: 2\r\n\r\n";
+    let mut stream = segmented_stream(vec![raw.as_bytes().to_vec()]);
+
+    let mut reader = RtspReader::new();
+    let first = reader.read_response(&mut stream).await.unwrap();
+    assert_eq!(first.body, b"abc");
+    let second = reader.read_response(&mut stream).await.unwrap();
+    assert_eq!(second.status_code, 404);
+    assert_eq!(second.cseq(), Some(2));
+    assert_eq!(reader.buffered(), 0);
+}
+
+// 测试连接在响应完整前关闭
+#[tokio::test]
+async fn test_connection_closed_mid_response() {
+    let mut stream = segmented_stream(vec![
+        b"RTSP/1.0 200 OK\r\nContent-Length: 10\r\n\r\nabc".to_vec(),
+    ]);
+    assert!(RtspReader::new().read_response(&mut stream).await.is_err());
+}
@@ -0,0 +1,82 @@
+use rstest::rstest;
+use rust_rtsp_bruter::rtsp::auth::{AuthType, parse_auth_challenge};
+use rust_rtsp_bruter::rtsp::common::RtspResponse;
+
+// 测试状态行解析
+#[rstest]
+#[case("RTSP/1.0 200 OK\r\nCSeq: 1\r\n\r\n", 200, "OK")]
+#[case("RTSP/1.0 401 Unauthorized\r\nCSeq: 2\r\n\r\n", 401, "Unauthorized")]
+#[case("RTSP/1.0 454 Session Not Found\r\n\r\n", 454, "Session Not Found")]
+#[case("RTSP/1.0 404\r\n\r\n", 404, "")]
+fn test_status_line(#[case] raw: &str, #[case] status_code: u16, #[case] reason: &str) {
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    assert_eq!(response.version, "RTSP/1.0");
+    assert_eq!(response.status_code, status_code);
+    assert_eq!(response.reason, reason);
+}
+
+// 测试无效的状态行
+#[rstest]
+#[case("")]
+#[case("garbage\r\n\r\n")]
+#[case("RTSP/1.0 abc OK\r\n\r\n")]
+fn test_invalid_status_line(#[case] raw: &str) {
+    assert!(RtspResponse::parse(raw.as_bytes()).is_err());
+}
+
+// 测试消息体中的状态字符串不会影响解析结果
+#[test]
+fn test_body_does_not_change_status() {
+    let body = "v=0\r\ns=401 Unauthorized\r\n";
+    let raw = format!(
+        "RTSP/1.0 200 OK\r\nCSeq: 2\r\nServer: 401 Unauthorized Camera\r\nContent-Type: application/sdp\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    assert_eq!(response.status_code, 200);
+    assert!(response.is_success());
+    assert_eq!(response.cseq(), Some(2));
+    assert_eq!(response.body_text(), body);
+}
+
+// 测试头部名称大小写不敏感以及多值头部
+#[test]
+fn test_case_insensitive_multi_valued_headers() {
+    let raw = "RTSP/1.0 401 Unauthorized\r\n".to_string()
+        + "cseq: 3\r\n"
+        + "www-authenticate: Digest realm=\"IP Camera\", nonce=\"abc\"\r\n"
+        + "WWW-AUTHENTICATE: Basic realm=\"IP Camera\"\r\n\r\n";
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    assert_eq!(response.cseq(), Some(3));
+    let challenges: Vec<&str> = response.headers.get_all("WWW-Authenticate").collect();
+    assert_eq!(challenges.len(), 2);
+    assert!(challenges[0].starts_with("Digest "));
+    assert!(challenges[1].starts_with("Basic "));
+
+    // 小写的认证头同样可以被识别
+    match parse_auth_challenge(&raw).unwrap() {
+        AuthType::Digest(info) => assert_eq!(info.realm, "IP Camera"),
+        other => panic!("Expected Digest authentication type, got {:?}", other),
+    }
+}
+
+// 测试消息体按Content-Length截取
+#[test]
+fn test_body_bounded_by_content_length() {
+    let raw = "RTSP/1.0 200 OK\r\nContent-Length: 5\r\n\r\nhelloRTSP/1.0 200 OK\r\n";
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    assert_eq!(response.content_length(), Some(5));
+    assert_eq!(response.body, b"hello");
+}
+
+// 测试折叠的头部
+#[test]
+fn test_folded_header() {
+    let raw = "RTSP/1.0 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"cam\",\r\n  nonce=\"123\"\r\n\r\n";
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    assert_eq!(
+        response.headers.get("WWW-Authenticate"),
+        Some("Digest realm=\"cam\", nonce=\"123\"")
+    );
+}
@@ -0,0 +1,124 @@
+use rust_rtsp_bruter::rtsp::common::{RtspResponse, parse_sdp_content};
+use rust_rtsp_bruter::rtsp::sdp::{Codec, SessionDescription};
+
+// 海康威视摄像头返回的SDP
+const HIKVISION_SDP: &str = "v=0\r\n\
+o=- 1109162014219182 1109162014219192 IN IP4 192.168.1.64\r\n\
+s=Media Presentation\r\n\
+e=NONE\r\n\
+b=AS:5050\r\n\
+t=0 0\r\n\
+a=control:rtsp://192.168.1.64:554/Streaming/Channels/101/?transportmode=unicast\r\n\
+m=video 0 RTP/AVP 96\r\n\
+c=IN IP4 0.0.0.0\r\n\
+b=AS:5000\r\n\
+a=recvonly\r\n\
+a=x-dimensions:1920,1080\r\n\
+a=control:rtsp://192.168.1.64:554/Streaming/Channels/101/trackID=1?transportmode=unicast\r\n\
+a=rtpmap:96 H264/90000\r\n\
+a=fmtp:96 profile-level-id=420029; packetization-mode=1; sprop-parameter-sets=Z00AKpY1QPAET8s3AQEBAg==,aO48gA==\r\n\
+m=audio 0 RTP/AVP 8\r\n\
+c=IN IP4 0.0.0.0\r\n\
+b=AS:50\r\n\
+a=recvonly\r\n\
+a=control:rtsp://192.168.1.64:554/Streaming/Channels/101/trackID=2?transportmode=unicast\r\n\
+a=rtpmap:8 PCMA/8000\r\n\
+a=Media_header:MEDIAINFO=494D4B48010100000400000111710110401F000000FA000000000000000000000000000000000000;\r\n";
+
+// 测试解析会话级别和媒体级别的字段
+#[test]
+fn test_parse_hikvision_sdp() {
+    let session = SessionDescription::parse(HIKVISION_SDP).unwrap();
+    assert_eq!(session.version, 0);
+    assert_eq!(session.session_name, "Media Presentation");
+    assert_eq!(session.timing, Some((0, 0)));
+    assert_eq!(session.origin.as_ref().unwrap().address, "192.168.1.64");
+    assert_eq!(session.media.len(), 2);
+
+    let video = &session.media[0];
+    assert!(video.is_video());
+    assert_eq!(video.connection.as_deref(), Some("IN IP4 0.0.0.0"));
+    assert_eq!(video.attribute("x-dimensions"), Some("1920,1080"));
+    let format = video.primary_format().unwrap();
+    assert_eq!(format.payload_type, 96);
+    assert_eq!(format.codec, Codec::H264);
+    assert_eq!(format.clock_rate, 90000);
+    assert_eq!(format.fmtp_parameter("packetization-mode"), Some("1"));
+    assert_eq!(
+        format.fmtp_parameter("sprop-parameter-sets"),
+        Some("Z00AKpY1QPAET8s3AQEBAg==,aO48gA==")
+    );
+
+    assert!(session.has_audio());
+    assert_eq!(session.media[1].codec(), Some(&Codec::Pcma));
+    assert_eq!(session.media[1].to_string(), "audio PCMA/8000");
+}
+
+// 测试静态负载类型以及H.265、多声道等编码
+#[test]
+fn test_parse_codecs() {
+    let sdp = "v=0\r\ns=Session\r\n\
+m=video 0 RTP/AVP 26\r\n\
+m=video 0 RTP/AVP 98\r\na=rtpmap:98 H265/90000\r\n\
+m=audio 0 RTP/AVP 97\r\na=rtpmap:97 MPEG4-GENERIC/16000/2\r\n\
+m=audio 0 RTP/AVP 0\r\n";
+    let session = SessionDescription::parse(sdp).unwrap();
+    let codecs: Vec<&Codec> = session
+        .media
+        .iter()
+        .filter_map(|media| media.codec())
+        .collect();
+    assert_eq!(
+        codecs,
+        vec![&Codec::Mjpeg, &Codec::H265, &Codec::Aac, &Codec::Pcmu]
+    );
+    assert_eq!(session.media[0].primary_format().unwrap().clock_rate, 90000);
+    assert_eq!(session.media[2].primary_format().unwrap().channels, Some(2));
+    assert_eq!(session.video().count(), 2);
+}
+
+// 测试缺少版本行的SDP
+#[test]
+fn test_parse_invalid_sdp() {
+    assert!(SessionDescription::parse("s=Session\r\nm=video 0 RTP/AVP 96\r\n").is_err());
+    assert!(SessionDescription::parse("v=0\r\nm=video\r\n").is_err());
+}
+
+// 测试根据Content-Base解析相对的控制URL
+#[test]
+fn test_resolve_controls_with_content_base() {
+    let body = "v=0\r\ns=Session\r\na=control:*\r\n\
+m=video 0 RTP/AVP 96\r\na=rtpmap:96 H264/90000\r\na=control:trackID=0\r\n\
+m=audio 0 RTP/AVP 8\r\na=control:rtsp://10.0.0.1/cam/realmonitor?channel=1&subtype=0/trackID=1\r\n";
+    let raw = format!(
+        "RTSP/1.0 200 OK\r\nCSeq: 2\r\nContent-Base: rtsp://10.0.0.1:554/live/\r\nContent-Type: application/sdp\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let response = RtspResponse::parse(raw.as_bytes()).unwrap();
+    let session = parse_sdp_content(&response, "rtsp://10.0.0.1:554/live").unwrap();
+    assert_eq!(
+        session.control.as_deref(),
+        Some("rtsp://10.0.0.1:554/live/")
+    );
+    assert_eq!(
+        session.media[0].control.as_deref(),
+        Some("rtsp://10.0.0.1:554/live/trackID=0")
+    );
+    assert_eq!(
+        session.media[1].control.as_deref(),
+        Some("rtsp://10.0.0.1/cam/realmonitor?channel=1&subtype=0/trackID=1")
+    );
+}
+
+// 测试没有Content-Base时相对于请求URL解析
+#[test]
+fn test_resolve_controls_with_request_url() {
+    let mut session =
+        SessionDescription::parse("v=0\r\nm=video 0 RTP/AVP 96\r\na=control:track1\r\n").unwrap();
+    session.resolve_controls("rtsp://10.0.0.1/cam/realmonitor?channel=1&subtype=0");
+    assert_eq!(
+        session.media[0].control.as_deref(),
+        Some("rtsp://10.0.0.1/cam/realmonitor?channel=1&subtype=0/track1")
+    );
+}
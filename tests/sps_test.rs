@@ -0,0 +1,181 @@
+use rstest::rstest;
+use rust_rtsp_bruter::rtsp::sdp::{Codec, SessionDescription};
+use rust_rtsp_bruter::rtsp::sps::{
+    BitReader, parse_h264_sps, parse_h265_sps, remove_emulation_prevention,
+};
+
+// 测试指数哥伦布编码
+#[test]
+fn test_exp_golomb() {
+    // 1 | 010 | 011 | 00100 | 00101 -> ue: 0, 1, 2, 3; se: -2
+    let data = [0b1010_0110, 0b0100_0010, 0b1000_0000];
+    let mut reader = BitReader::new(&data);
+    assert_eq!(reader.read_ue().unwrap(), 0);
+    assert_eq!(reader.read_ue().unwrap(), 1);
+    assert_eq!(reader.read_ue().unwrap(), 2);
+    assert_eq!(reader.read_ue().unwrap(), 3);
+    assert_eq!(reader.read_se().unwrap(), -2);
+    assert!(reader.read_bits(8).is_err());
+}
+
+// 测试去除防竞争字节
+#[test]
+fn test_remove_emulation_prevention() {
+    assert_eq!(
+        remove_emulation_prevention(&[0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x03, 0x00, 0x03]),
+        vec![0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03]
+    );
+}
+
+// 测试解析H.264的SPS
+#[rstest]
+// 海康威视 1920x1080 Main@4.2
+#[case("Z00AKpY1QPAET8s3AQEBAg==", 1920, 1080, "Main", "4.2")]
+// 1280x720 High@3.1
+#[case("Z2QAH6zZQFAFuhAAAAMAEAAAAwPI8YMZYA==", 1280, 720, "High", "3.1")]
+fn test_parse_h264_sps(
+    #[case] sprop: &str,
+    #[case] width: u32,
+    #[case] height: u32,
+    #[case] profile: &str,
+    #[case] level: &str,
+) {
+    use base64::Engine;
+    let nal = base64::engine::general_purpose::STANDARD
+        .decode(sprop)
+        .unwrap();
+    let info = parse_h264_sps(&nal).unwrap();
+    assert_eq!((info.width, info.height), (width, height));
+    assert_eq!(info.profile(), profile);
+    assert_eq!(info.level(), level);
+}
+
+// 测试解析scripts/wireshark_rtsp_auth_flow.txt中的H.265 SDP
+#[test]
+fn test_parse_h265_sps_from_sdp() {
+    let sdp = "v=0\r\ns=profile1\r\nt=0 0\r\na=control:*\r\n\
+m=video 0 RTP/AVP 98\r\na=control:track1\r\na=rtpmap:98 H265/90000\r\n\
+a=fmtp:98 profile-space=0;profile-id=1;tier-flag=1;level-id=0;sprop-vps=QAEMAf//IWAAAAMAAAMAAAMAAAMAlqwJ;sprop-sps=QgEBIWAAAAMAAAMAAAMAAAMAlqADwIARB8uStO8ku5uAgICCACD1gAM3+YAQ;sprop-pps=RAHAcvA7JA==;\r\n\
+m=audio 0 RTP/AVP 8\r\na=rtpmap:8 pcma/8000\r\n";
+    let session = SessionDescription::parse(sdp).unwrap();
+    let info = session.media[0].video_info.as_ref().unwrap();
+    assert_eq!(info.codec, Codec::H265);
+    assert_eq!(info.profile(), "Main");
+    assert_eq!(info.level(), "5");
+    assert_eq!(session.media[1].video_info, None);
+    println!("{}", session.media[0]);
+}
+
+// 测试无效的SPS
+#[test]
+fn test_invalid_sps() {
+    // PPS不是SPS
+    assert!(parse_h264_sps(&[0x68, 0xEE, 0x3C, 0x80]).is_err());
+    // 数据被截断
+    assert!(parse_h264_sps(&[0x67, 0x4D, 0x00]).is_err());
+    assert!(parse_h265_sps(&[0x42, 0x01, 0x01]).is_err());
+}
+
+// 按位构造NAL单元，bits为二进制字符串，末尾补齐并插入防竞争字节
+fn build_nal(header: &[u8], bits: &str) -> Vec<u8> {
+    let mut bits = format!("{}1", bits);
+    while bits.len() % 8 != 0 {
+        bits.push('0');
+    }
+    let mut nal = header.to_vec();
+    let mut zeros = 0;
+    for chunk in bits.as_bytes().chunks(8) {
+        let byte = u8::from_str_radix(std::str::from_utf8(chunk).unwrap(), 2).unwrap();
+        if zeros >= 2 && byte <= 3 {
+            nal.push(3);
+            zeros = 0;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        nal.push(byte);
+    }
+    nal
+}
+
+// ue(v)编码的二进制字符串
+fn ue(value: u64) -> String {
+    let code = format!("{:b}", value + 1);
+    format!("{}{}", "0".repeat(code.len() - 1), code)
+}
+
+// 测试构造的极大尺寸值返回错误而不是溢出
+#[test]
+fn test_oversized_sps_values() {
+    let max = u32::MAX as u64 - 1;
+
+    // H.264 Baseline，pic_width_in_mbs_minus1 = 2^32-2
+    let fields = [
+        "01000010000000000001111".to_string() + "0", // profile_idc=66, constraint, level_idc=30
+        ue(0),                                        // seq_parameter_set_id
+        ue(0),                                        // log2_max_frame_num_minus4
+        ue(0),                                        // pic_order_cnt_type
+        ue(0),                                        // log2_max_pic_order_cnt_lsb_minus4
+        ue(0),                                        // max_num_ref_frames
+        "0".to_string(),                              // gaps_in_frame_num_value_allowed_flag
+        ue(max),                                      // pic_width_in_mbs_minus1
+        ue(0),                                        // pic_height_in_map_units_minus1
+        "110".to_string(),                            // frame_mbs_only, direct_8x8, cropping
+    ];
+    assert!(parse_h264_sps(&build_nal(&[0x67], &fields.concat())).is_err());
+
+    // 裁剪值溢出
+    let fields = [
+        "01000010000000000001111".to_string() + "0",
+        ue(0),
+        ue(0),
+        ue(0),
+        ue(0),
+        ue(0),
+        "0".to_string(),
+        ue(119),
+        ue(67),
+        "111".to_string(),
+        ue(max),
+        ue(max),
+        ue(0),
+        ue(0),
+    ];
+    assert!(parse_h264_sps(&build_nal(&[0x67], &fields.concat())).is_err());
+
+    // H.265 Main，conformance window溢出
+    let fields = [
+        "0001".to_string() + "000" + "1", // vps_id, max_sub_layers_minus1=0, temporal_id_nesting
+        "00".to_string() + "0" + "00001", // profile_space, tier, profile_idc=1
+        "1".repeat(80),                   // 兼容和约束标志
+        "10011001".to_string(),           // general_level_idc=153
+        ue(0),                            // sps_seq_parameter_set_id
+        ue(1),                            // chroma_format_idc
+        ue(1920),                         // pic_width_in_luma_samples
+        ue(1080),                         // pic_height_in_luma_samples
+        "1".to_string(),                  // conformance_window_flag
+        ue(max),
+        ue(max),
+        ue(0),
+        ue(0),
+    ];
+    assert!(parse_h265_sps(&build_nal(&[0x42, 0x01], &fields.concat())).is_err());
+
+    // 不溢出时正常解析
+    let fields = [
+        "01000010000000000001111".to_string() + "0",
+        ue(0),
+        ue(0),
+        ue(0),
+        ue(0),
+        ue(0),
+        "0".to_string(),
+        ue(119),
+        ue(67),
+        "111".to_string(),
+        ue(0),
+        ue(0),
+        ue(0),
+        ue(4),
+    ];
+    let info = parse_h264_sps(&build_nal(&[0x67], &fields.concat())).unwrap();
+    assert_eq!((info.width, info.height), (1920, 1080));
+}
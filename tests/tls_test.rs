@@ -0,0 +1,117 @@
+mod common;
+
+use common::{MockRtspServer, basic_auth_handler};
+use rust_rtsp_bruter::errors::errors::AuthenticationResult;
+use rust_rtsp_bruter::iterator::ip_iterator::IpPortAddr;
+use rust_rtsp_bruter::rtsp::client::{ClientOptions, RtspClient};
+use rust_rtsp_bruter::rtsp::transport::{CertificateInfo, TlsOptions, is_tls_url};
+use rustls::ServerConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::TlsAcceptor;
+
+// TLS终端代理：使用自签名证书接受TLS连接，解密后转发给模拟的RTSP服务器
+struct TlsProxy {
+    addr: SocketAddr,
+    certificate: CertificateDer<'static>,
+}
+
+impl TlsProxy {
+    async fn start(backend: SocketAddr) -> Self {
+        let generated =
+            rcgen::generate_simple_self_signed(vec!["camera.local".to_string()]).unwrap();
+        let certificate = generated.cert.der().clone();
+        let key =
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(generated.key_pair.serialize_der()));
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(vec![certificate.clone()], key)
+                .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let mut tls = match acceptor.accept(stream).await {
+                        Ok(tls) => tls,
+                        Err(_) => return,
+                    };
+                    let mut backend = TcpStream::connect(backend).await.unwrap();
+                    let _ = tokio::io::copy_bidirectional(&mut tls, &mut backend).await;
+                });
+            }
+        });
+        TlsProxy { addr, certificate }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("rtsps://{}{}", self.addr, path)
+    }
+}
+
+fn tls_options(accept_invalid_certs: bool) -> ClientOptions {
+    ClientOptions {
+        tls: TlsOptions {
+            accept_invalid_certs,
+            ..TlsOptions::default()
+        },
+        ..Default::default()
+    }
+}
+
+// 测试根据URL协议和端口选择TLS
+#[test]
+fn test_tls_selection() {
+    assert!(is_tls_url("rtsps://1.2.3.4/live"));
+    assert!(is_tls_url("RTSPS://1.2.3.4/live"));
+    assert!(!is_tls_url("rtsp://1.2.3.4/live"));
+
+    let tls = TlsOptions::default();
+    let addr = |port| IpPortAddr::new("1.2.3.4".parse().unwrap(), port);
+    assert_eq!(tls.scheme(&addr(322)), "rtsps");
+    assert_eq!(tls.scheme(&addr(8322)), "rtsps");
+    assert_eq!(tls.scheme(&addr(554)), "rtsp");
+}
+
+// 测试默认验证服务器证书，自签名证书的握手失败
+#[tokio::test]
+async fn test_self_signed_certificate_rejected() {
+    let server = MockRtspServer::start(basic_auth_handler).await;
+    let proxy = TlsProxy::start(server.addr).await;
+
+    let result = RtspClient::new("admin", "admin")
+        .with_options(tls_options(false))
+        .describe(&proxy.url("/"))
+        .await;
+    assert!(result.is_err());
+    assert_eq!(server.requests(), 0);
+}
+
+// 测试接受自签名证书时通过TLS完成认证，并记录证书主题和指纹
+#[tokio::test]
+async fn test_accept_self_signed_certificate() {
+    let server = MockRtspServer::start(basic_auth_handler).await;
+    let proxy = TlsProxy::start(server.addr).await;
+
+    let outcome = RtspClient::new("admin", "admin")
+        .with_options(tls_options(true))
+        .describe_outcome(&proxy.url("/"))
+        .await
+        .unwrap();
+    assert_eq!(outcome.result, AuthenticationResult::Success);
+    assert_eq!(server.requests(), 2);
+
+    let expected = CertificateInfo::from_der(&proxy.certificate);
+    assert_eq!(outcome.certificate, Some(expected.clone()));
+    assert_eq!(expected.subject, "CN=rcgen self signed cert");
+    // SHA-256指纹为32字节
+    assert_eq!(expected.fingerprint.split(':').count(), 32);
+}